@@ -59,7 +59,7 @@ saveBtn.addEventListener("click", async () => {
   }
 
   const title = titleInput.value.trim() || null;
-  const sourceType = sourceTypeSelect.value;
+  const sourceType = sourceTypeSelect.value || null;
 
   const body = {
     url,
@@ -0,0 +1 @@
+ALTER TABLE entries ADD COLUMN source_type_inferred BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,7 @@
+ALTER TABLE entries ADD COLUMN author TEXT;
+ALTER TABLE entries ADD COLUMN published_at TEXT;
+ALTER TABLE entries ADD COLUMN site_name TEXT;
+ALTER TABLE entries ADD COLUMN canonical_url TEXT;
+ALTER TABLE entries ADD COLUMN image_url TEXT;
+ALTER TABLE entries ADD COLUMN language TEXT;
+ALTER TABLE entries ADD COLUMN word_count INTEGER;
@@ -0,0 +1,3 @@
+ALTER TABLE entries ADD COLUMN last_checked_at TEXT;
+ALTER TABLE entries ADD COLUMN http_status INTEGER;
+ALTER TABLE entries ADD COLUMN dead BOOLEAN NOT NULL DEFAULT FALSE;
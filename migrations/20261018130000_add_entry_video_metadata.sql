@@ -0,0 +1,3 @@
+ALTER TABLE entries ADD COLUMN duration_secs INTEGER;
+ALTER TABLE entries ADD COLUMN author_url TEXT;
+ALTER TABLE entries ADD COLUMN chapters TEXT;
@@ -0,0 +1,4 @@
+ALTER TABLE entries ADD COLUMN fetch_outcome INTEGER;
+ALTER TABLE entries ADD COLUMN fetch_http_status INTEGER;
+ALTER TABLE entries ADD COLUMN final_url TEXT;
+ALTER TABLE entries ADD COLUMN fetch_duration_ms INTEGER;
@@ -0,0 +1,12 @@
+ALTER TABLE entries ADD COLUMN domain TEXT;
+
+-- Backfill with the host of each URL, lowercased and without a leading "www."
+UPDATE entries SET domain = lower(substr(url, instr(url, '://') + 3));
+UPDATE entries SET domain = substr(domain, 1, instr(domain || '/', '/') - 1);
+UPDATE entries SET domain = substr(domain, 1, instr(domain || '?', '?') - 1);
+UPDATE entries SET domain = substr(domain, 1, instr(domain || '#', '#') - 1);
+UPDATE entries SET domain = substr(domain, instr(domain, '@') + 1);
+UPDATE entries SET domain = substr(domain, 1, instr(domain || ':', ':') - 1);
+UPDATE entries SET domain = substr(domain, 5) WHERE domain LIKE 'www.%';
+
+CREATE INDEX idx_entries_domain ON entries(domain);
@@ -0,0 +1,4 @@
+-- Set when an entry is moved to the trash
+ALTER TABLE entries ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX idx_entries_deleted_at ON entries(deleted_at);
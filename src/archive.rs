@@ -0,0 +1,381 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::Utc;
+use lol_html::{RewriteStrSettings, element, html_content::ContentType, rewrite_str};
+use scraper::{Html, Selector};
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::AppState;
+use crate::events;
+use crate::metadata::{Document, FetchResult, Fetcher, escape_html};
+use crate::models::Entry;
+
+/// Directory under the data directory that snapshots are stored in.
+const ARCHIVE_SUBDIR: &str = "archive";
+
+/// Stylesheets and images fetched per snapshot. Anything beyond is left pointing at the site.
+const MAX_INLINED_RESOURCES: usize = 50;
+
+/// Largest stylesheet or image that is inlined (2 MiB). Larger ones are left pointing at the
+/// site rather than stored cut off.
+const MAX_RESOURCE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Total size of the resources inlined into one snapshot (10 MiB), before base64 encoding.
+const MAX_INLINED_BYTES: usize = 10 * 1024 * 1024;
+
+/// Content-Security-Policy for served snapshots. Scripts are stripped when archiving, and the
+/// sandbox keeps anything that slipped through from running with our origin.
+pub const ARCHIVE_CSP: &str = "sandbox allow-popups allow-popups-to-escape-sandbox";
+
+/// Directory that snapshots are stored in.
+pub fn archive_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(ARCHIVE_SUBDIR)
+}
+
+/// Archive an entry's page in the background and record the snapshot on the entry.
+/// Failures are logged; the entry just stays without a snapshot.
+pub async fn archive_entry(state: AppState, id: i64, url: String) {
+    match archive(&state.fetcher, &state.config.data_dir(), id, &url).await {
+        Ok(Some(path)) => match Entry::set_archive_path(&state.pool, id, &path).await {
+            Ok(true) => {
+                info!(id, path, "archived {}", url);
+                events::publish_updated(&state, id).await;
+            }
+            Ok(false) => debug!(id, "Entry deleted while archiving {}", url),
+            Err(e) => warn!("Failed to record archive of {}: {}", url, e),
+        },
+        Ok(None) => debug!("Not archiving {}: not an HTML page", url),
+        Err(e) => warn!("Failed to archive {}: {}", url, e),
+    }
+}
+
+/// Write a snapshot of a page to the archive directory.
+/// Returns its path relative to the data directory, or `None` for non-HTML resources.
+async fn archive(
+    fetcher: &Fetcher,
+    data_dir: &Path,
+    id: i64,
+    url: &str,
+) -> FetchResult<Option<String>> {
+    let url = Url::parse(url)?;
+    let (Document::Html(html), _) = fetcher.fetch_document(&url).await? else {
+        return Ok(None);
+    };
+
+    let resources = fetch_resources(fetcher, &html, &url).await;
+    let snapshot = snapshot(&html, &url, &resources)?;
+
+    let relative = format!("{ARCHIVE_SUBDIR}/{id}.html");
+    let path = data_dir.join(&relative);
+    let partial = path.with_extension("html.part");
+
+    tokio::fs::create_dir_all(archive_dir(data_dir)).await?;
+    tokio::fs::write(&partial, snapshot).await?;
+    tokio::fs::rename(&partial, &path).await?;
+
+    Ok(Some(relative))
+}
+
+/// A fetched stylesheet or image, ready to be inlined.
+enum Resource {
+    Stylesheet(String),
+    /// A `data:` URI.
+    Image(String),
+}
+
+/// Stylesheet and image URLs referenced by a page, resolved against its URL.
+fn resource_urls(html: &str, page_url: &Url) -> (Vec<Url>, Vec<Url>) {
+    let document = Html::parse_document(html);
+    let collect = |selector: &str, attr: &str| -> Vec<Url> {
+        let selector = Selector::parse(selector).unwrap();
+        let mut seen = HashSet::new();
+        document
+            .select(&selector)
+            .filter_map(|el| el.value().attr(attr))
+            .filter_map(|href| page_url.join(href.trim()).ok())
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .filter(|url| seen.insert(url.clone()))
+            .collect()
+    };
+
+    (
+        collect(r#"link[rel~="stylesheet"][href]"#, "href"),
+        collect("img[src]", "src"),
+    )
+}
+
+async fn fetch_resources(fetcher: &Fetcher, html: &str, page_url: &Url) -> HashMap<Url, Resource> {
+    let (stylesheets, images) = resource_urls(html, page_url);
+    let mut resources = HashMap::new();
+    let mut remaining = MAX_INLINED_BYTES;
+
+    let urls = stylesheets
+        .into_iter()
+        .map(|url| (url, true))
+        .chain(images.into_iter().map(|url| (url, false)))
+        .take(MAX_INLINED_RESOURCES);
+
+    for (url, is_stylesheet) in urls {
+        let max_bytes = MAX_RESOURCE_BYTES.min(remaining);
+        let (bytes, content_type) = match fetcher.fetch_bytes_limited(&url, max_bytes).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                debug!("Failed to fetch {} for archiving: {}", url, e);
+                continue;
+            }
+        };
+
+        let resource = if is_stylesheet {
+            let css = String::from_utf8_lossy(&bytes);
+            Resource::Stylesheet(absolutize_css_urls(&css, &url))
+        } else {
+            match content_type.filter(|t| t.starts_with("image/")) {
+                Some(content_type) => Resource::Image(format!(
+                    "data:{content_type};base64,{}",
+                    BASE64.encode(&bytes)
+                )),
+                None => continue,
+            }
+        };
+        remaining -= bytes.len();
+        resources.insert(url, resource);
+    }
+
+    resources
+}
+
+/// Rewrite a page into a self-contained snapshot: stylesheets and images are inlined, scripts
+/// removed, and a `<base>` keeps the remaining links pointing at the original site.
+fn snapshot(html: &str, page_url: &Url, resources: &HashMap<Url, Resource>) -> FetchResult<String> {
+    let resolve = |value: Option<String>| value.and_then(|v| page_url.join(v.trim()).ok());
+    let head_seen = Cell::new(false);
+    let header = format!(
+        r#"<meta charset="utf-8"><base href="{}"><!-- Archived from {} on {} -->"#,
+        escape_html(page_url.as_str()),
+        escape_html(page_url.as_str()).replace("--", "&#45;&#45;"),
+        Utc::now().to_rfc3339()
+    );
+
+    let rewritten = rewrite_str(
+        html,
+        RewriteStrSettings::new()
+            .append_element_content_handler(element!("script, base", |el| {
+                el.remove();
+                Ok(())
+            }))
+            // The snapshot is always UTF-8, and shouldn't redirect away
+            .append_element_content_handler(element!("meta[charset], meta[http-equiv]", |el| {
+                let http_equiv = el
+                    .get_attribute("http-equiv")
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if el.has_attribute("charset")
+                    || matches!(http_equiv.as_str(), "content-type" | "refresh")
+                {
+                    el.remove();
+                }
+                Ok(())
+            }))
+            .append_element_content_handler(element!("head", |el| {
+                head_seen.set(true);
+                el.prepend(&header, ContentType::Html);
+                Ok(())
+            }))
+            .append_element_content_handler(element!(r#"link[rel~="stylesheet"][href]"#, |el| {
+                if let Some(Resource::Stylesheet(css)) =
+                    resolve(el.get_attribute("href")).and_then(|url| resources.get(&url))
+                {
+                    let css = css.replace("</style", r"<\/style");
+                    el.replace(&format!("<style>{css}</style>"), ContentType::Html);
+                }
+                Ok(())
+            }))
+            .append_element_content_handler(element!("img[src]", |el| {
+                if let Some(Resource::Image(data)) =
+                    resolve(el.get_attribute("src")).and_then(|url| resources.get(&url))
+                {
+                    el.set_attribute("src", data)?;
+                    el.remove_attribute("srcset");
+                }
+                Ok(())
+            }))
+            // <picture> sources would take precedence over the inlined <img>
+            .append_element_content_handler(element!("picture > source", |el| {
+                el.remove();
+                Ok(())
+            })),
+    )?;
+
+    if head_seen.get() {
+        Ok(rewritten)
+    } else {
+        Ok(format!("{header}{rewritten}"))
+    }
+}
+
+/// Make `url(...)` references in a stylesheet absolute, since the stylesheet is inlined into a
+/// page with a different URL.
+fn absolutize_css_urls(css: &str, stylesheet_url: &Url) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("url(") {
+        let (before, after) = rest.split_at(start + "url(".len());
+        out.push_str(before);
+
+        let Some(end) = after.find(')') else {
+            rest = after;
+            break;
+        };
+        let raw = after[..end].trim();
+        let unquoted = raw.trim_matches(['"', '\'']);
+
+        match stylesheet_url.join(unquoted) {
+            Ok(url) if !unquoted.starts_with("data:") => {
+                out.push('"');
+                out.push_str(&url.to_string().replace('"', "%22"));
+                out.push('"');
+            }
+            _ => out.push_str(raw),
+        }
+        rest = &after[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Carry over the snapshot of an imported entry, found at `relative` in the export. It's copied
+/// rather than shared, since snapshots are named after the exporting server's IDs and could be
+/// overwritten by this server's own. Anything but a file in the archive directory is refused, so
+/// an export can't point an entry at other files. Returns the copy's path relative to the data
+/// directory, or `None` if the snapshot isn't there.
+pub async fn restore_snapshot(
+    data_dir: &Path,
+    id: i64,
+    relative: &str,
+) -> std::io::Result<Option<String>> {
+    let mut components = Path::new(relative).components();
+    let in_archive_dir = matches!(
+        (components.next(), components.next(), components.next()),
+        (Some(Component::Normal(dir)), Some(Component::Normal(_)), None) if dir == ARCHIVE_SUBDIR
+    );
+    if !in_archive_dir || !tokio::fs::try_exists(data_dir.join(relative)).await? {
+        return Ok(None);
+    }
+
+    let restored = format!("{ARCHIVE_SUBDIR}/{id}.imported.html");
+    tokio::fs::copy(data_dir.join(relative), data_dir.join(&restored)).await?;
+    Ok(Some(restored))
+}
+
+/// Delete the snapshot at a path relative to the data directory.
+pub async fn remove_snapshot(data_dir: &Path, relative: &str) {
+    if let Err(e) = tokio::fs::remove_file(data_dir.join(relative)).await
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!("Failed to remove archived snapshot {}: {}", relative, e);
+    }
+}
+
+/// Delete snapshots whose entry no longer exists. Returns the number of files removed.
+pub async fn remove_orphans(pool: &SqlitePool, data_dir: &Path) -> std::io::Result<u64> {
+    let dir = archive_dir(data_dir);
+    let mut files = match tokio::fs::read_dir(&dir).await {
+        Ok(files) => files,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let referenced: HashSet<String> = Entry::archive_paths(pool)
+        .await
+        .map_err(std::io::Error::other)?
+        .into_iter()
+        .collect();
+
+    let mut removed = 0;
+    while let Some(file) = files.next_entry().await? {
+        let relative = format!("{ARCHIVE_SUBDIR}/{}", file.file_name().to_string_lossy());
+        if !referenced.contains(&relative) {
+            tokio::fs::remove_file(file.path()).await?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn resource_urls_are_resolved_and_deduplicated() {
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/style.css">
+            <link rel="icon" href="/favicon.ico">
+            </head><body>
+            <img src="a.png"><img src="a.png"><img src="data:image/png;base64,AAAA">
+            </body></html>"#;
+
+        let (stylesheets, images) = resource_urls(html, &url("https://example.com/post/1"));
+
+        assert_eq!(stylesheets, vec![url("https://example.com/style.css")]);
+        assert_eq!(images, vec![url("https://example.com/post/a.png")]);
+    }
+
+    #[test]
+    fn snapshot_inlines_resources_and_strips_scripts() {
+        let html = r#"<html><head><meta charset="windows-1252"><title>Post</title>
+            <link rel="stylesheet" href="/style.css"><script src="/app.js"></script>
+            </head><body><picture><source srcset="/a.webp"><img src="/a.png" srcset="/a@2x.png"></picture>
+            <img src="/missing.png"><script>alert(1)</script></body></html>"#;
+        let page_url = url("https://example.com/post");
+        let resources = HashMap::from([
+            (
+                url("https://example.com/style.css"),
+                Resource::Stylesheet("body { color: red }".to_string()),
+            ),
+            (
+                url("https://example.com/a.png"),
+                Resource::Image("data:image/png;base64,AAAA".to_string()),
+            ),
+        ]);
+
+        let out = snapshot(html, &page_url, &resources).unwrap();
+
+        assert!(out.contains(r#"<base href="https://example.com/post">"#));
+        assert!(out.contains(r#"<meta charset="utf-8">"#));
+        assert!(!out.contains("windows-1252"));
+        assert!(out.contains("<style>body { color: red }</style>"));
+        assert!(out.contains(r#"<img src="data:image/png;base64,AAAA">"#));
+        assert!(out.contains(r#"<img src="/missing.png">"#));
+        assert!(!out.contains("<script"));
+        assert!(!out.contains("<source"));
+    }
+
+    #[test]
+    fn snapshot_without_head_still_gets_base() {
+        let out = snapshot("<p>Hi</p>", &url("https://example.com/"), &HashMap::new()).unwrap();
+        assert!(out.starts_with(r#"<meta charset="utf-8"><base href="https://example.com/">"#));
+    }
+
+    #[test]
+    fn absolutize_css_urls_resolves_relative_references() {
+        let css = r#"@font-face { src: url(fonts/a.woff2) } .x { background: url("../img/b.png") } .y { background: url(data:image/png;base64,AA) }"#;
+
+        assert_eq!(
+            absolutize_css_urls(css, &url("https://cdn.example.com/css/site.css")),
+            r#"@font-face { src: url("https://cdn.example.com/css/fonts/a.woff2") } .x { background: url("https://cdn.example.com/img/b.png") } .y { background: url(data:image/png;base64,AA) }"#
+        );
+    }
+}
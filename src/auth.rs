@@ -24,9 +24,14 @@ pub async fn auth_guard(
 
     let token = auth_header.trim_start_matches("Bearer ").trim();
 
-    if token != state.config.auth_token {
+    if !is_valid_token(&state, token) {
         return Err(Error::Unauthorized);
     }
 
     Ok(next.run(req).await)
 }
+
+/// Whether a token given by a client is the configured API token.
+pub fn is_valid_token(state: &AppState, token: &str) -> bool {
+    !token.is_empty() && token == state.config.auth_token
+}
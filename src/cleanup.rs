@@ -4,17 +4,30 @@ use chrono::Utc;
 use sqlx::SqlitePool;
 use tracing::{error, info};
 
+use crate::{AppState, events};
+
 const CLEANUP_INTERVAL_SECS: u64 = 43_200; // 12 hours
 
+/// Days entries stay in the trash when `trash_retention_days` isn't set.
+const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
 /// Starts the background cleanup task if retention settings are configured.
 ///
 /// - Deletes entries older than `retention_days` (if set and > 0)
 /// - Deletes entries beyond `max_entries` count (if set and > 0)
-pub fn start_cleanup_task(pool: SqlitePool, retention_days: Option<u32>, max_entries: Option<u32>) {
-    let retention_days = retention_days.filter(|&d| d > 0);
-    let max_entries = max_entries.filter(|&m| m > 0);
+/// - Empties entries out of the trash after `trash_retention_days` (30 by default, never if 0)
+/// - Removes archived snapshots and cached media under `data_dir` that deleted entries left behind
+/// - Publishes `entry.deleted` to `/events` clients for each deleted entry
+pub fn start_cleanup_task(state: AppState) {
+    let retention_days = state.config.retention_days.filter(|&d| d > 0);
+    let max_entries = state.config.max_entries.filter(|&m| m > 0);
+    let trash_retention_days = state
+        .config
+        .trash_retention_days
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+    let trash_retention_days = Some(trash_retention_days).filter(|&d| d > 0);
 
-    if retention_days.is_none() && max_entries.is_none() {
+    if retention_days.is_none() && max_entries.is_none() && trash_retention_days.is_none() {
         info!("no retention policy configured, cleanup task disabled");
         return;
     }
@@ -22,39 +35,83 @@ pub fn start_cleanup_task(pool: SqlitePool, retention_days: Option<u32>, max_ent
     info!(
         retention_days = retention_days,
         max_entries = max_entries,
+        trash_retention_days = trash_retention_days,
         "starting background cleanup task"
     );
 
     tokio::spawn(async move {
+        let (pool, data_dir) = (&state.pool, state.config.data_dir());
         let mut interval = tokio::time::interval(Duration::from_secs(CLEANUP_INTERVAL_SECS));
 
         loop {
             interval.tick().await;
+            let mut deleted = 0;
 
             if let Some(days) = retention_days {
-                match cleanup_by_age(&pool, days).await {
-                    Ok(0) => {}
-                    Ok(count) => info!(count, days, "deleted entries older than retention period"),
+                match cleanup_by_age(pool, days).await {
+                    Ok(ids) if ids.is_empty() => {}
+                    Ok(ids) => {
+                        deleted += ids.len();
+                        events::publish_removed(&state, &ids).await;
+                        info!(
+                            count = ids.len(),
+                            days, "deleted entries older than retention period"
+                        )
+                    }
                     Err(e) => error!(error = %e, "failed to clean up old entries"),
                 }
             }
 
             if let Some(max) = max_entries {
-                match cleanup_by_count(&pool, max).await {
-                    Ok(0) => {}
-                    Ok(count) => info!(count, max, "deleted entries beyond max count"),
+                match cleanup_by_count(pool, max).await {
+                    Ok(ids) if ids.is_empty() => {}
+                    Ok(ids) => {
+                        deleted += ids.len();
+                        events::publish_removed(&state, &ids).await;
+                        info!(count = ids.len(), max, "deleted entries beyond max count")
+                    }
                     Err(e) => error!(error = %e, "failed to clean up excess entries"),
                 }
             }
+
+            if let Some(days) = trash_retention_days {
+                match purge_trash(pool, days).await {
+                    Ok(0) => {}
+                    Ok(count) => {
+                        deleted += count as usize;
+                        info!(count, days, "emptied entries out of the trash")
+                    }
+                    Err(e) => error!(error = %e, "failed to empty the trash"),
+                }
+            }
+
+            if deleted > 0 {
+                match crate::archive::remove_orphans(pool, &data_dir).await {
+                    Ok(0) => {}
+                    Ok(count) => info!(count, "removed archived snapshots of deleted entries"),
+                    Err(e) => error!(error = %e, "failed to remove orphaned snapshots"),
+                }
+
+                match crate::media::remove_orphans(pool, &data_dir, &state.config.base_url).await {
+                    Ok(0) => {}
+                    Ok(count) => info!(count, "removed cached media no longer referenced"),
+                    Err(e) => error!(error = %e, "failed to remove orphaned media"),
+                }
+            }
         }
     });
 }
 
-async fn cleanup_by_age(pool: &SqlitePool, days: u32) -> Result<u64, sqlx::Error> {
+async fn cleanup_by_age(pool: &SqlitePool, days: u32) -> Result<Vec<i64>, sqlx::Error> {
     let cutoff = Utc::now() - chrono::Duration::days(i64::from(days));
     crate::models::Entry::delete_older_than(pool, cutoff).await
 }
 
-async fn cleanup_by_count(pool: &SqlitePool, max: u32) -> Result<u64, sqlx::Error> {
+async fn cleanup_by_count(pool: &SqlitePool, max: u32) -> Result<Vec<i64>, sqlx::Error> {
     crate::models::Entry::delete_beyond_limit(pool, max).await
 }
+
+async fn purge_trash(pool: &SqlitePool, days: u32) -> Result<u64, sqlx::Error> {
+    let cutoff = Utc::now() - chrono::Duration::days(i64::from(days));
+    crate::models::Entry::purge_trash(pool, Some(cutoff)).await
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use serde::Deserialize;
 
 #[derive(Deserialize, Default)]
@@ -8,4 +10,70 @@ pub struct Config {
     pub auth_token: String,
     pub retention_days: Option<u32>,
     pub max_entries: Option<u32>,
+    /// Days entries stay in the trash before they are deleted for good. Defaults to 30; 0 keeps
+    /// them until the trash is emptied.
+    pub trash_retention_days: Option<u32>,
+    /// Maximum number of bytes downloaded per fetched page.
+    pub max_fetch_bytes: Option<usize>,
+    /// Timeout in seconds for a whole fetch, including the body.
+    pub fetch_timeout_secs: Option<u64>,
+    /// Timeout in seconds for establishing a connection.
+    pub fetch_connect_timeout_secs: Option<u64>,
+    pub fetch_user_agent: Option<String>,
+    /// Send a browser-like User-Agent, for sites that block bots.
+    pub fetch_browser_user_agent: Option<bool>,
+    /// HTTP(S) proxy for all outbound fetches, e.g. `http://proxy.lan:3128`. Host names are
+    /// still resolved locally to refuse non-public addresses, but the proxy makes the final
+    /// lookup, so it should refuse internal addresses too.
+    pub fetch_proxy: Option<String>,
+    pub fetch_max_redirects: Option<usize>,
+    /// Extra headers sent with every fetch, as a JSON object of names to values.
+    pub fetch_headers: Option<String>,
+    /// Maximum number of concurrent fetches per host.
+    pub fetch_host_concurrency: Option<usize>,
+    /// Minimum delay in milliseconds between fetches to the same host.
+    pub fetch_host_delay_ms: Option<u64>,
+    /// Longest `Retry-After` in seconds that is waited out before retrying.
+    pub fetch_max_retry_after_secs: Option<u64>,
+    /// Skip URLs that robots.txt disallows for our user agent.
+    pub fetch_respect_robots: Option<bool>,
+    /// Directory for archived pages and other local files. Defaults to `data`.
+    pub data_dir: Option<String>,
+    /// Store a self-contained snapshot of each saved page.
+    pub archive_pages: Option<bool>,
+    /// Download images in entry bodies and serve them from `/media`.
+    pub cache_images: Option<bool>,
+    /// Largest image in bytes that is cached.
+    pub max_image_bytes: Option<usize>,
+    /// JSON file of per-host headers and cookies to send when fetching.
+    pub fetch_rules_file: Option<String>,
+    /// Endpoint YouTube caption tracks are fetched from.
+    pub youtube_captions_url: Option<String>,
+    /// Preferred language of video transcripts, e.g. `en`.
+    pub transcript_language: Option<String>,
+    /// Include video transcripts in the feed.
+    pub transcripts_in_feed: Option<bool>,
+    /// Re-check each saved link this often, in hours. Disabled if unset or 0.
+    pub link_check_interval_hours: Option<u64>,
+    /// Comma-separated hosts and IPs/CIDRs that may be fetched even if they aren't public.
+    pub fetch_allowlist: Option<String>,
+    /// Comma-separated hosts and IPs/CIDRs that are never fetched.
+    pub fetch_denylist: Option<String>,
+    /// Port of the embedded SMTP server that saves emailed links. Disabled if unset.
+    pub smtp_port: Option<u16>,
+    /// Address the SMTP server listens on. Defaults to `127.0.0.1`.
+    pub smtp_address: Option<String>,
+    /// Comma-separated sender addresses, or `@domain`s, whose mail is accepted. Senders are
+    /// easily forged, so this filters mail rather than authenticating it.
+    pub smtp_allowed_senders: Option<String>,
+    /// Secret that recipients must carry as a plus tag, as in `save+secret@host`. Mail to other
+    /// recipients is refused.
+    pub smtp_recipient_token: Option<String>,
+}
+
+impl Config {
+    /// Directory for archived pages and other local files.
+    pub fn data_dir(&self) -> PathBuf {
+        PathBuf::from(self.data_dir.as_deref().unwrap_or("data"))
+    }
 }
@@ -1,9 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-use crate::models;
+use crate::{events, export, import, models, save};
 
 #[derive(Serialize, Deserialize, ToSchema, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -30,12 +30,164 @@ impl From<EntrySourceType> for models::EntrySourceType {
     }
 }
 
+/// How fetching an entry's page for its metadata went.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchOutcome {
+    Success,
+    /// Fetched, but not an HTML page, e.g. an image or a PDF.
+    NonHtml,
+    Timeout,
+    /// The server answered with an error status, see `fetch_http_status`.
+    HttpError,
+    /// Refused by the fetch allowlist/denylist, the non-public address check or robots.txt.
+    Blocked,
+    ParseError,
+    /// The host couldn't be resolved or connected to.
+    NetworkError,
+}
+
+impl From<models::FetchOutcome> for FetchOutcome {
+    fn from(outcome: models::FetchOutcome) -> Self {
+        match outcome {
+            models::FetchOutcome::Success => FetchOutcome::Success,
+            models::FetchOutcome::NonHtml => FetchOutcome::NonHtml,
+            models::FetchOutcome::Timeout => FetchOutcome::Timeout,
+            models::FetchOutcome::HttpError => FetchOutcome::HttpError,
+            models::FetchOutcome::Blocked => FetchOutcome::Blocked,
+            models::FetchOutcome::ParseError => FetchOutcome::ParseError,
+            models::FetchOutcome::NetworkError => FetchOutcome::NetworkError,
+        }
+    }
+}
+
+impl From<FetchOutcome> for models::FetchOutcome {
+    fn from(outcome: FetchOutcome) -> Self {
+        match outcome {
+            FetchOutcome::Success => models::FetchOutcome::Success,
+            FetchOutcome::NonHtml => models::FetchOutcome::NonHtml,
+            FetchOutcome::Timeout => models::FetchOutcome::Timeout,
+            FetchOutcome::HttpError => models::FetchOutcome::HttpError,
+            FetchOutcome::Blocked => models::FetchOutcome::Blocked,
+            FetchOutcome::ParseError => models::FetchOutcome::ParseError,
+            FetchOutcome::NetworkError => models::FetchOutcome::NetworkError,
+        }
+    }
+}
+
+/// A chapter of a video.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Chapter {
+    /// Offset into the video, in seconds.
+    pub start_secs: i64,
+    pub title: String,
+}
+
+impl From<models::Chapter> for Chapter {
+    fn from(chapter: models::Chapter) -> Self {
+        Self {
+            start_secs: chapter.start_secs,
+            title: chapter.title,
+        }
+    }
+}
+
+/// A line of a video transcript.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TranscriptSegment {
+    /// Offset into the video, in seconds.
+    pub start_secs: i64,
+    pub text: String,
+}
+
+impl From<models::TranscriptSegment> for TranscriptSegment {
+    fn from(segment: models::TranscriptSegment) -> Self {
+        Self {
+            start_secs: segment.start_secs,
+            text: segment.text,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct AddEntryRequest {
     #[validate(url)]
     pub url: String,
     pub title: Option<String>,
-    pub source_type: EntrySourceType,
+    /// Detected from the page when omitted.
+    pub source_type: Option<EntrySourceType>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchEntryStatus {
+    Created,
+    /// Skipped since the URL was already saved, or came earlier in the batch.
+    Duplicate,
+    /// Not saved since the request was invalid, e.g. not a URL.
+    Invalid,
+    Failed,
+}
+
+/// The result of adding one entry of a batch.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchEntryResult {
+    pub url: String,
+    pub status: BatchEntryStatus,
+    /// The new entry, when created.
+    pub entry: Option<EntryResponse>,
+    /// Why the entry couldn't be added, when invalid or failed.
+    pub error: Option<String>,
+}
+
+impl BatchEntryResult {
+    pub fn new(url: String, outcome: save::BatchOutcome) -> Self {
+        let (status, entry, error) = match outcome {
+            save::BatchOutcome::Created(entry) => {
+                (BatchEntryStatus::Created, Some((*entry).into()), None)
+            }
+            save::BatchOutcome::Duplicate => (BatchEntryStatus::Duplicate, None, None),
+            save::BatchOutcome::Failed(error) => (BatchEntryStatus::Failed, None, Some(error)),
+        };
+        Self {
+            url,
+            status,
+            entry,
+            error,
+        }
+    }
+
+    pub fn invalid(url: String, error: String) -> Self {
+        Self {
+            url,
+            status: BatchEntryStatus::Invalid,
+            entry: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Per-entry results of a batch add, in request order.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchAddResponse {
+    pub entries: Vec<BatchEntryResult>,
+}
+
+/// A quick save, from a query string or a form.
+#[derive(Deserialize, IntoParams, ToSchema)]
+pub struct QuickSaveParams {
+    /// The page to save.
+    pub url: Option<String>,
+    pub title: Option<String>,
+    /// Detected from the page when omitted.
+    pub source_type: Option<EntrySourceType>,
+    /// Comma-separated tags.
+    pub tags: Option<String>,
+    /// The API token.
+    pub token: Option<String>,
+    /// Redirect back to the saved page instead of showing a confirmation page.
+    pub redirect: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -45,7 +197,48 @@ pub struct EntryResponse {
     pub title: String,
     pub body: Option<String>,
     pub source_type: EntrySourceType,
+    /// Whether `source_type` was detected from the page rather than set by the client.
+    pub source_type_inferred: bool,
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub site_name: Option<String>,
+    pub canonical_url: Option<String>,
+    pub image_url: Option<String>,
+    pub language: Option<String>,
+    pub word_count: Option<i64>,
+    /// Length of a video, in seconds.
+    pub duration_secs: Option<i64>,
+    /// Page of the author, e.g. a video's channel.
+    pub author_url: Option<String>,
+    /// Chapters of a video, in order.
+    pub chapters: Vec<Chapter>,
+    /// Transcript of a video, in order. Left out of lists, events and webhook payloads to keep
+    /// them small.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<Vec<TranscriptSegment>>,
+    /// Name of the per-host fetch rule (from `FETCH_RULES_FILE`) applied when fetching the page.
+    pub fetch_rule: Option<String>,
+    /// How fetching the page's metadata went. Missing for entries saved before it was recorded.
+    pub fetch_outcome: Option<FetchOutcome>,
+    /// Status the page answered with when `fetch_outcome` is `http_error`.
+    pub fetch_http_status: Option<i64>,
+    /// URL the page was served from, after redirects.
+    pub final_url: Option<String>,
+    /// How long fetching the page's metadata took, in milliseconds.
+    pub fetch_duration_ms: Option<i64>,
+    pub tags: Vec<String>,
+    /// Whether a snapshot of the page is available at `/entries/{id}/archive`.
+    pub archived: bool,
+    /// When the link was last checked by the dead-link checker.
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Status the link answered with on its last check, if it answered at all.
+    pub http_status: Option<i64>,
+    /// Whether the link appeared to be gone when last checked.
+    pub dead: bool,
     pub created_at: DateTime<Utc>,
+    /// When the entry was moved to the trash. Only set on entries listed from `/trash` and in
+    /// JSON exports.
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl From<models::Entry> for EntryResponse {
@@ -56,20 +249,437 @@ impl From<models::Entry> for EntryResponse {
             title: entry.title,
             body: entry.body,
             source_type: entry.source_type.into(),
+            source_type_inferred: entry.source_type_inferred,
+            author: entry.author,
+            published_at: entry.published_at,
+            site_name: entry.site_name,
+            canonical_url: entry.canonical_url,
+            image_url: entry.image_url,
+            language: entry.language,
+            word_count: entry.word_count,
+            duration_secs: entry.duration_secs,
+            author_url: entry.author_url,
+            chapters: entry
+                .chapters
+                .map(|chapters| chapters.0.into_iter().map(Chapter::from).collect())
+                .unwrap_or_default(),
+            transcript: Some(
+                entry
+                    .transcript
+                    .map(|transcript| {
+                        transcript
+                            .0
+                            .into_iter()
+                            .map(TranscriptSegment::from)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            fetch_rule: entry.fetch_rule,
+            fetch_outcome: entry.fetch_outcome.map(FetchOutcome::from),
+            fetch_http_status: entry.fetch_http_status,
+            final_url: entry.final_url,
+            fetch_duration_ms: entry.fetch_duration_ms,
+            tags: entry.tags.map(|tags| tags.0).unwrap_or_default(),
+            archived: entry.archive_path.is_some(),
+            last_checked_at: entry.last_checked_at,
+            http_status: entry.http_status,
+            dead: entry.dead,
             created_at: entry.created_at,
+            deleted_at: entry.deleted_at,
         }
     }
 }
 
+impl EntryResponse {
+    /// An entry without its transcript, for lists, events and webhook payloads, which it would
+    /// bloat.
+    pub fn summary(mut entry: models::Entry) -> Self {
+        entry.transcript = None;
+        Self {
+            transcript: None,
+            ..entry.into()
+        }
+    }
+}
+
+/// Order to list entries in.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EntrySort {
+    /// Most recently saved first.
+    #[default]
+    Newest,
+    /// Least recently saved first.
+    Oldest,
+    /// By title, ignoring case.
+    Title,
+    /// By domain, then newest first.
+    Domain,
+}
+
+impl From<EntrySort> for models::EntrySort {
+    fn from(sort: EntrySort) -> Self {
+        match sort {
+            EntrySort::Newest => models::EntrySort::Newest,
+            EntrySort::Oldest => models::EntrySort::Oldest,
+            EntrySort::Title => models::EntrySort::Title,
+            EntrySort::Domain => models::EntrySort::Domain,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListEntriesQuery {
+    /// Only list entries whose link is (`true`) or isn't (`false`) dead.
+    pub dead: Option<bool>,
+    /// Only list entries whose metadata couldn't (`true`) or could (`false`) be fetched.
+    pub failed: Option<bool>,
+    /// Only list entries whose title, body or transcript contains this text.
+    pub q: Option<String>,
+    /// Only list entries of this type.
+    pub source_type: Option<EntrySourceType>,
+    /// Only list entries saved at or after this time, e.g. `2024-01-01T00:00:00Z`.
+    pub saved_after: Option<DateTime<Utc>>,
+    /// Only list entries saved before this time.
+    pub saved_before: Option<DateTime<Utc>>,
+    /// Only list entries from this domain or its subdomains, e.g. `example.com`.
+    pub domain: Option<String>,
+    /// Only list entries that have (`true`) or don't have (`false`) a body.
+    pub has_body: Option<bool>,
+    /// Defaults to `newest`.
+    pub sort: Option<EntrySort>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct LookupEntryQuery {
+    /// URL of the entry. Normalized like saved URLs, so tracking parameters don't matter.
+    pub url: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct DeleteEntryQuery {
+    /// Delete the entry for good, even from the trash, instead of moving it to the trash.
+    pub permanent: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PurgeTrashResponse {
+    /// Number of entries deleted for good.
+    pub purged: u64,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct ListEntriesResponse {
     pub entries: Vec<EntryResponse>,
 }
 
+/// The kind of export file being imported.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// Pocket's HTML or CSV export.
+    Pocket,
+    /// Instapaper's CSV export.
+    Instapaper,
+    /// Wallabag's JSON export.
+    Wallabag,
+    /// Omnivore's JSON export.
+    Omnivore,
+    /// A browser bookmarks file.
+    Netscape,
+    /// A JSON export of this server.
+    Laterfeed,
+}
+
+impl From<import::Format> for ImportFormat {
+    fn from(format: import::Format) -> Self {
+        match format {
+            import::Format::Pocket => ImportFormat::Pocket,
+            import::Format::Instapaper => ImportFormat::Instapaper,
+            import::Format::Wallabag => ImportFormat::Wallabag,
+            import::Format::Omnivore => ImportFormat::Omnivore,
+            import::Format::Netscape => ImportFormat::Netscape,
+            import::Format::Laterfeed => ImportFormat::Laterfeed,
+        }
+    }
+}
+
+impl From<ImportFormat> for import::Format {
+    fn from(format: ImportFormat) -> Self {
+        match format {
+            ImportFormat::Pocket => import::Format::Pocket,
+            ImportFormat::Instapaper => import::Format::Instapaper,
+            ImportFormat::Wallabag => import::Format::Wallabag,
+            ImportFormat::Omnivore => import::Format::Omnivore,
+            ImportFormat::Netscape => import::Format::Netscape,
+            ImportFormat::Laterfeed => import::Format::Laterfeed,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ImportQuery {
+    /// Detected from the file when omitted.
+    pub format: Option<ImportFormat>,
+    /// Fetch each page for its metadata (default), or only save the link and its title.
+    pub fetch: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportItemStatus {
+    Imported,
+    /// Skipped since the URL was already saved.
+    Duplicate,
+    Failed,
+}
+
+/// The result of importing one link.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportItemResponse {
+    pub url: String,
+    pub status: ImportItemStatus,
+    /// ID of the new entry, when imported.
+    pub entry_id: Option<i64>,
+    /// Why the link couldn't be imported, when failed.
+    pub error: Option<String>,
+}
+
+impl From<import::ItemResult> for ImportItemResponse {
+    fn from(item: import::ItemResult) -> Self {
+        let (status, entry_id, error) = match item.outcome {
+            import::ItemOutcome::Imported(id) => (ImportItemStatus::Imported, Some(id), None),
+            import::ItemOutcome::Duplicate => (ImportItemStatus::Duplicate, None, None),
+            import::ItemOutcome::Failed(error) => (ImportItemStatus::Failed, None, Some(error)),
+        };
+        Self {
+            url: item.url,
+            status,
+            entry_id,
+            error,
+        }
+    }
+}
+
+/// Progress and per-link results of an import.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    pub id: u64,
+    pub format: ImportFormat,
+    /// Whether all links have been processed.
+    pub finished: bool,
+    /// Number of links found in the file.
+    pub total: usize,
+    /// Number of links processed so far.
+    pub processed: usize,
+    pub imported: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Results of the processed links, in file order.
+    pub items: Vec<ImportItemResponse>,
+}
+
+impl From<import::ImportJob> for ImportResponse {
+    fn from(job: import::ImportJob) -> Self {
+        Self {
+            id: job.id,
+            format: job.format.into(),
+            finished: job.finished_at.is_some(),
+            total: job.total,
+            processed: job.items.len(),
+            imported: job.count(|o| matches!(o, import::ItemOutcome::Imported(_))),
+            duplicates: job.count(|o| *o == import::ItemOutcome::Duplicate),
+            failed: job.count(|o| matches!(o, import::ItemOutcome::Failed(_))),
+            created_at: job.created_at,
+            finished_at: job.finished_at,
+            items: job
+                .items
+                .into_iter()
+                .map(ImportItemResponse::from)
+                .collect(),
+        }
+    }
+}
+
+/// The kind of file to export entries as.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Every field of every entry, bodies and trashed entries included. Can be imported again.
+    #[default]
+    Json,
+    Csv,
+    /// A bookmarks file that browsers can import.
+    Netscape,
+    /// A reading list of links.
+    Markdown,
+}
+
+impl From<ExportFormat> for export::Format {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Json => export::Format::Json,
+            ExportFormat::Csv => export::Format::Csv,
+            ExportFormat::Netscape => export::Format::Netscape,
+            ExportFormat::Markdown => export::Format::Markdown,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ExportQuery {
+    /// Defaults to `json`.
+    pub format: Option<ExportFormat>,
+}
+
+/// An entry event that webhooks can subscribe to.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Debug)]
+pub enum WebhookEvent {
+    #[serde(rename = "entry.created")]
+    Created,
+    /// The entry changed after it was saved: its page was archived or its images cached.
+    #[serde(rename = "entry.updated")]
+    Updated,
+    #[serde(rename = "entry.deleted")]
+    Deleted,
+}
+
+impl WebhookEvent {
+    pub fn name(self) -> &'static str {
+        events::Event::from(self).name()
+    }
+}
+
+impl From<WebhookEvent> for events::Event {
+    fn from(event: WebhookEvent) -> Self {
+        match event {
+            WebhookEvent::Created => events::Event::Created,
+            WebhookEvent::Updated => events::Event::Updated,
+            WebhookEvent::Deleted => events::Event::Deleted,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateWebhookRequest {
+    /// Where to POST the events.
+    #[validate(url)]
+    pub url: String,
+    /// Events to deliver. Defaults to all of them.
+    pub events: Option<Vec<WebhookEvent>>,
+    /// Key to sign payloads with. Generated when omitted.
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: i64,
+    pub url: String,
+    /// Names of the events delivered.
+    pub events: Vec<String>,
+    /// Key the payloads are signed with. Only returned when the webhook is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookResponse {
+    pub fn with_secret(webhook: models::Webhook) -> Self {
+        let secret = webhook.secret.clone();
+        Self {
+            secret: Some(secret),
+            ..webhook.into()
+        }
+    }
+}
+
+impl From<models::Webhook> for WebhookResponse {
+    fn from(webhook: models::Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            events: webhook.events.0,
+            secret: None,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ListWebhooksResponse {
+    pub webhooks: Vec<WebhookResponse>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not delivered yet, retried at `next_attempt_at`.
+    Pending,
+    Succeeded,
+    /// Given up on after too many failed attempts.
+    Failed,
+}
+
+impl From<models::DeliveryStatus> for DeliveryStatus {
+    fn from(status: models::DeliveryStatus) -> Self {
+        match status {
+            models::DeliveryStatus::Pending => DeliveryStatus::Pending,
+            models::DeliveryStatus::Succeeded => DeliveryStatus::Succeeded,
+            models::DeliveryStatus::Failed => DeliveryStatus::Failed,
+        }
+    }
+}
+
+/// One event sent, or to be sent, to a webhook.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryResponse {
+    pub id: i64,
+    pub event: String,
+    /// The JSON body sent.
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    /// When the delivery is next tried, while pending.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    /// Status the webhook answered with on the last attempt, if it answered.
+    pub response_status: Option<i64>,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<models::WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(delivery: models::WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            event: delivery.event,
+            payload: delivery.payload,
+            next_attempt_at: (delivery.status == models::DeliveryStatus::Pending)
+                .then_some(delivery.next_attempt_at),
+            status: delivery.status.into(),
+            attempts: delivery.attempts,
+            last_attempt_at: delivery.last_attempt_at,
+            response_status: delivery.response_status,
+            error: delivery.error,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ListDeliveriesResponse {
+    pub deliveries: Vec<WebhookDeliveryResponse>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
+    use sqlx::types::Json;
 
     #[test]
     fn entry_response_from_model_entry() {
@@ -80,16 +690,83 @@ mod tests {
             title: "Test Title".to_string(),
             body: Some("Test Body".to_string()),
             source_type: models::EntrySourceType::Video,
+            source_type_inferred: true,
+            author: Some("Jane Doe".to_string()),
+            published_at: Some(now),
+            site_name: Some("Example".to_string()),
+            canonical_url: Some("https://example.com/canonical".to_string()),
+            image_url: Some("https://example.com/lead.jpg".to_string()),
+            language: Some("en".to_string()),
+            word_count: Some(120),
+            duration_secs: Some(253),
+            author_url: Some("https://example.com/jane".to_string()),
+            chapters: Some(Json(vec![models::Chapter {
+                start_secs: 0,
+                title: "Intro".to_string(),
+            }])),
+            transcript: Some(Json(vec![models::TranscriptSegment {
+                start_secs: 5,
+                text: "Hello".to_string(),
+            }])),
+            fetch_rule: Some("wiki".to_string()),
+            fetch_outcome: Some(models::FetchOutcome::HttpError),
+            fetch_http_status: Some(403),
+            final_url: Some("https://example.com/final".to_string()),
+            fetch_duration_ms: Some(250),
+            tags: Some(Json(vec!["rust".to_string()])),
+            archive_path: Some("archive/42.html".to_string()),
+            last_checked_at: Some(now),
+            http_status: Some(404),
+            dead: true,
             created_at: now,
+            deleted_at: None,
         };
 
-        let response: EntryResponse = entry.into();
+        assert!(EntryResponse::summary(entry.clone()).transcript.is_none());
+        let response = EntryResponse::from(entry);
 
         assert_eq!(response.id, 42);
         assert_eq!(response.url, "https://example.com");
         assert_eq!(response.title, "Test Title");
         assert_eq!(response.body, Some("Test Body".to_string()));
         assert!(matches!(response.source_type, EntrySourceType::Video));
+        assert!(response.source_type_inferred);
+        assert_eq!(response.author, Some("Jane Doe".to_string()));
+        assert_eq!(response.published_at, Some(now));
+        assert_eq!(response.site_name, Some("Example".to_string()));
+        assert_eq!(
+            response.canonical_url,
+            Some("https://example.com/canonical".to_string())
+        );
+        assert_eq!(
+            response.image_url,
+            Some("https://example.com/lead.jpg".to_string())
+        );
+        assert_eq!(response.language, Some("en".to_string()));
+        assert_eq!(response.word_count, Some(120));
+        assert_eq!(response.duration_secs, Some(253));
+        assert_eq!(
+            response.author_url,
+            Some("https://example.com/jane".to_string())
+        );
+        assert_eq!(response.chapters.len(), 1);
+        assert_eq!(response.chapters[0].title, "Intro");
+        let transcript = response.transcript.unwrap();
+        assert_eq!(transcript.len(), 1);
+        assert_eq!(transcript[0].start_secs, 5);
+        assert_eq!(response.fetch_rule, Some("wiki".to_string()));
+        assert_eq!(response.fetch_outcome, Some(FetchOutcome::HttpError));
+        assert_eq!(response.fetch_http_status, Some(403));
+        assert_eq!(
+            response.final_url,
+            Some("https://example.com/final".to_string())
+        );
+        assert_eq!(response.fetch_duration_ms, Some(250));
+        assert_eq!(response.tags, vec!["rust"]);
+        assert!(response.archived);
+        assert_eq!(response.last_checked_at, Some(now));
+        assert_eq!(response.http_status, Some(404));
+        assert!(response.dead);
         assert_eq!(response.created_at, now);
     }
 }
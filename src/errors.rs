@@ -16,6 +16,12 @@ pub enum Error {
     #[error("Not found")]
     NotFound,
 
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 }
@@ -25,6 +31,8 @@ impl Error {
         match self {
             Error::Unauthorized => StatusCode::UNAUTHORIZED,
             Error::NotFound => StatusCode::NOT_FOUND,
+            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Error::Conflict(_) => StatusCode::CONFLICT,
             Error::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
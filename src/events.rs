@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use axum::response::sse;
+use chrono::Utc;
+use futures_util::{Stream, StreamExt, stream};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::error;
+
+use crate::{AppState, dto::EntryResponse, models::Entry, webhooks};
+
+/// Events kept for clients that reconnect with `Last-Event-ID`.
+const EVENT_LOG_SIZE: usize = 1000;
+
+/// Something that happened to an entry, sent to `/events` clients and webhooks.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The entry was saved, or restored from the trash.
+    Created,
+    /// The entry changed after it was saved, e.g. its page was archived.
+    Updated,
+    Deleted,
+}
+
+impl Event {
+    pub const ALL: [Event; 3] = [Event::Created, Event::Updated, Event::Deleted];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Created => "entry.created",
+            Event::Updated => "entry.updated",
+            Event::Deleted => "entry.deleted",
+        }
+    }
+}
+
+/// An event as streamed to clients.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub id: u64,
+    pub event: Event,
+    /// JSON with the entry's `id`, and the `entry` itself unless it was deleted.
+    pub data: String,
+}
+
+#[derive(Serialize)]
+struct ChangeData {
+    id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entry: Option<EntryResponse>,
+}
+
+/// The latest events, and a channel that streams new ones to subscribers.
+pub struct EventLog {
+    inner: Mutex<LogInner>,
+    capacity: usize,
+}
+
+struct LogInner {
+    changes: VecDeque<Arc<Change>>,
+    next_id: u64,
+    /// Taken on shutdown, which ends every stream.
+    sender: Option<broadcast::Sender<Arc<Change>>>,
+}
+
+/// What a subscriber missed since its `Last-Event-ID`.
+#[derive(Debug, PartialEq)]
+pub enum Missed {
+    /// These events, still in the log.
+    Changes(Vec<Arc<Change>>),
+    /// More than the log keeps, or events from before a restart. Clients should reload, and
+    /// resume from the given ID.
+    Unknown(u64),
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::with_capacity(EVENT_LOG_SIZE)
+    }
+}
+
+impl EventLog {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            inner: Mutex::new(LogInner {
+                changes: VecDeque::with_capacity(capacity),
+                // IDs carry on increasing across restarts, so old ones are never mistaken for new
+                next_id: Utc::now().timestamp_micros().max(1) as u64,
+                sender: Some(sender),
+            }),
+            capacity,
+        }
+    }
+
+    /// Add an event to the log and send it to the subscribers.
+    pub fn push(&self, event: Event, data: String) {
+        let mut inner = self.inner.lock().unwrap();
+        let change = Arc::new(Change {
+            id: inner.next_id,
+            event,
+            data,
+        });
+        inner.next_id += 1;
+        if inner.changes.len() == self.capacity {
+            inner.changes.pop_front();
+        }
+        inner.changes.push_back(change.clone());
+        if let Some(sender) = &inner.sender {
+            // Fails only when nobody is listening
+            let _ = sender.send(change);
+        }
+    }
+
+    /// Subscribe to new events, along with those logged after `last_id`, if given. `None` once
+    /// the log is closed.
+    pub fn subscribe(
+        &self,
+        last_id: Option<u64>,
+    ) -> Option<(Missed, broadcast::Receiver<Arc<Change>>)> {
+        let inner = self.inner.lock().unwrap();
+        let receiver = inner.sender.as_ref()?.subscribe();
+        let latest = inner.next_id - 1;
+        let oldest = inner
+            .changes
+            .front()
+            .map_or(inner.next_id, |change| change.id);
+
+        let missed = match last_id {
+            None => Missed::Changes(Vec::new()),
+            Some(id) if id < oldest - 1 || id > latest => Missed::Unknown(latest),
+            Some(id) => Missed::Changes(
+                inner
+                    .changes
+                    .iter()
+                    .filter(|change| change.id > id)
+                    .cloned()
+                    .collect(),
+            ),
+        };
+        Some((missed, receiver))
+    }
+
+    /// End every stream, so the server can shut down.
+    pub fn close(&self) {
+        self.inner.lock().unwrap().sender = None;
+    }
+}
+
+/// Publish an entry event to `/events` clients and webhooks.
+pub async fn publish(state: &AppState, event: Event, entry: &Entry) {
+    let data = ChangeData {
+        id: entry.id,
+        entry: (event != Event::Deleted).then(|| EntryResponse::summary(entry.clone())),
+    };
+    push(state, event, &data);
+    webhooks::notify(state, event, entry.id, Some(entry)).await;
+}
+
+/// Publish `entry.updated` for an entry that was changed in the background.
+pub async fn publish_updated(state: &AppState, id: i64) {
+    match Entry::fetch_by_id(&state.pool, id).await {
+        Ok(Some(entry)) => publish(state, Event::Updated, &entry).await,
+        Ok(None) => {}
+        Err(e) => error!(error = %e, id, "failed to load updated entry"),
+    }
+}
+
+/// Publish `entry.deleted` for entries removed by cleanup, with only their IDs.
+pub async fn publish_removed(state: &AppState, ids: &[i64]) {
+    for &id in ids {
+        push(state, Event::Deleted, &ChangeData { id, entry: None });
+        webhooks::notify(state, Event::Deleted, id, None).await;
+    }
+}
+
+fn push(state: &AppState, event: Event, data: &ChangeData) {
+    match serde_json::to_string(data) {
+        Ok(data) => state.events.push(event, data),
+        Err(e) => error!(error = %e, "failed to serialize event"),
+    }
+}
+
+/// The Server-Sent Events sent to a client: what it missed since `last_id`, then new events as
+/// they happen. A `reset` event tells it that some were lost and it should reload.
+pub fn stream(
+    state: &AppState,
+    last_id: Option<u64>,
+) -> impl Stream<Item = Result<sse::Event, Infallible>> + use<> {
+    let (first, receiver) = match state.events.subscribe(last_id) {
+        Some((Missed::Changes(changes), receiver)) => (
+            changes.iter().map(|change| sse_event(change)).collect(),
+            Some(receiver),
+        ),
+        Some((Missed::Unknown(latest), receiver)) => {
+            (vec![reset_event(Some(latest))], Some(receiver))
+        }
+        None => (Vec::new(), None),
+    };
+
+    let live = stream::unfold(receiver, async |receiver| {
+        let mut receiver = receiver?;
+        let event = match receiver.recv().await {
+            Ok(change) => sse_event(&change),
+            // The client fell behind; it resumes from the last event it got if it reconnects
+            Err(RecvError::Lagged(_)) => reset_event(None),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((event, Some(receiver)))
+    });
+
+    stream::iter(first).chain(live).map(Ok)
+}
+
+fn sse_event(change: &Change) -> sse::Event {
+    sse::Event::default()
+        .id(change.id.to_string())
+        .event(change.event.name())
+        .data(&change.data)
+}
+
+fn reset_event(id: Option<u64>) -> sse::Event {
+    let event = sse::Event::default().event("reset").data("{}");
+    match id {
+        Some(id) => event.id(id.to_string()),
+        None => event,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(missed: Missed) -> Vec<u64> {
+        match missed {
+            Missed::Changes(changes) => changes.iter().map(|change| change.id).collect(),
+            Missed::Unknown(_) => panic!("expected changes"),
+        }
+    }
+
+    #[test]
+    fn event_names() {
+        let names: Vec<&str> = Event::ALL.iter().map(|event| event.name()).collect();
+        assert_eq!(names, ["entry.created", "entry.updated", "entry.deleted"]);
+    }
+
+    #[test]
+    fn subscribe_replays_events_after_last_id() {
+        let log = EventLog::with_capacity(3);
+        let (_, _receiver) = log.subscribe(None).unwrap();
+        for _ in 0..4 {
+            log.push(Event::Created, "{}".to_string());
+        }
+        let first = log.inner.lock().unwrap().changes[0].id;
+
+        let (missed, _) = log.subscribe(Some(first + 1)).unwrap();
+        assert_eq!(ids(missed), [first + 2]);
+        let (missed, _) = log.subscribe(Some(first - 1)).unwrap();
+        assert_eq!(ids(missed), [first, first + 1, first + 2]);
+        let (missed, _) = log.subscribe(None).unwrap();
+        assert_eq!(ids(missed), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn subscribe_reports_dropped_and_unknown_events() {
+        let log = EventLog::with_capacity(2);
+        for _ in 0..3 {
+            log.push(Event::Deleted, "{}".to_string());
+        }
+        let latest = log.inner.lock().unwrap().next_id - 1;
+
+        // The event after `latest - 3` was dropped from the log
+        let (missed, _) = log.subscribe(Some(latest - 3)).unwrap();
+        assert_eq!(missed, Missed::Unknown(latest));
+        let (missed, _) = log.subscribe(Some(latest + 5)).unwrap();
+        assert_eq!(missed, Missed::Unknown(latest));
+        let (missed, _) = log.subscribe(Some(0)).unwrap();
+        assert_eq!(missed, Missed::Unknown(latest));
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_new_events_until_closed() {
+        let log = EventLog::default();
+        let (_, mut receiver) = log.subscribe(None).unwrap();
+
+        log.push(Event::Updated, r#"{"id":1}"#.to_string());
+        let change = receiver.recv().await.unwrap();
+        assert_eq!(change.event, Event::Updated);
+        assert_eq!(change.data, r#"{"id":1}"#);
+
+        log.close();
+        assert_eq!(receiver.recv().await.unwrap_err(), RecvError::Closed);
+        assert!(log.subscribe(None).is_none());
+    }
+
+    #[tokio::test]
+    async fn publish_removed_reaches_events_and_webhooks() {
+        use crate::models::{Webhook, WebhookDelivery};
+
+        let state = crate::init_state(crate::config::Config {
+            database_url: "sqlite::memory:".to_string(),
+            ..Default::default()
+        })
+        .await;
+        let events = [Event::Deleted.name().to_string()];
+        Webhook::create(&state.pool, "https://example.com/hook", "key", &events)
+            .await
+            .unwrap();
+        let (_, mut receiver) = state.events.subscribe(None).unwrap();
+
+        publish_removed(&state, &[7]).await;
+
+        let change = receiver.recv().await.unwrap();
+        assert_eq!(change.event, Event::Deleted);
+        assert_eq!(change.data, r#"{"id":7}"#);
+
+        let due = WebhookDelivery::fetch_due(&state.pool, Utc::now(), 10)
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].event, "entry.deleted");
+        let payload: serde_json::Value = serde_json::from_str(&due[0].payload).unwrap();
+        assert_eq!(payload["id"], 7);
+        assert!(payload.get("entry").is_none());
+    }
+}
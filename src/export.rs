@@ -0,0 +1,308 @@
+use axum::body::{Body, Bytes};
+use futures_util::{StreamExt, stream};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::dto::EntryResponse;
+use crate::metadata::escape_html;
+use crate::models::{Entry, EntrySourceType};
+
+pub type ExportResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Chunks buffered between the database and a slow client.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Columns of the CSV export.
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "url",
+    "title",
+    "source_type",
+    "author",
+    "site_name",
+    "published_at",
+    "created_at",
+    "tags",
+    "word_count",
+    "dead",
+];
+
+/// An entry of a JSON export: everything stored about it, so importing the export restores it.
+#[derive(Serialize)]
+struct ExportedEntry {
+    #[serde(flatten)]
+    entry: EntryResponse,
+    /// Snapshot of the page, relative to the data directory.
+    archive_path: Option<String>,
+}
+
+/// The kind of file to export entries as.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    /// Every field of every entry, bodies and trashed entries included. Can be imported again.
+    Json,
+    Csv,
+    /// A bookmarks file that browsers can import.
+    Netscape,
+    /// A reading list of links.
+    Markdown,
+}
+
+impl Format {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Netscape => "text/html; charset=utf-8",
+            Format::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Json => "laterfeed.json",
+            Format::Csv => "laterfeed.csv",
+            Format::Netscape => "laterfeed-bookmarks.html",
+            Format::Markdown => "laterfeed.md",
+        }
+    }
+
+    fn header(self) -> ExportResult<String> {
+        Ok(match self {
+            Format::Json => "{\"entries\": [\n".to_string(),
+            Format::Csv => csv_row(CSV_COLUMNS)?,
+            Format::Netscape => "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+                <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+                <TITLE>Laterfeed</TITLE>\n\
+                <H1>Laterfeed</H1>\n\
+                <DL><p>\n"
+                .to_string(),
+            Format::Markdown => "# Laterfeed\n\n".to_string(),
+        })
+    }
+
+    /// One entry of the export. `index` is its position, starting at 0.
+    fn entry(self, entry: Entry, index: usize) -> ExportResult<String> {
+        let tags = entry
+            .tags
+            .as_ref()
+            .map(|tags| tags.0.clone())
+            .unwrap_or_default();
+
+        Ok(match self {
+            Format::Json => {
+                let separator = if index == 0 { "" } else { ",\n" };
+                format!(
+                    "{separator}{}",
+                    serde_json::to_string(&ExportedEntry {
+                        archive_path: entry.archive_path.clone(),
+                        entry: entry.into(),
+                    })?
+                )
+            }
+            Format::Csv => csv_row(&[
+                entry.id.to_string().as_str(),
+                &entry.url,
+                &entry.title,
+                source_type_name(entry.source_type),
+                entry.author.as_deref().unwrap_or_default(),
+                entry.site_name.as_deref().unwrap_or_default(),
+                &entry
+                    .published_at
+                    .map(|date| date.to_rfc3339())
+                    .unwrap_or_default(),
+                &entry.created_at.to_rfc3339(),
+                &tags.join("|"),
+                &entry
+                    .word_count
+                    .map(|count| count.to_string())
+                    .unwrap_or_default(),
+                if entry.dead { "true" } else { "false" },
+            ])?,
+            Format::Netscape => {
+                let tags = if tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(r#" TAGS="{}""#, escape_html(&tags.join(",")))
+                };
+                format!(
+                    "    <DT><A HREF=\"{}\" ADD_DATE=\"{}\"{tags}>{}</A>\n",
+                    escape_html(&entry.url),
+                    entry.created_at.timestamp(),
+                    escape_html(&entry.title)
+                )
+            }
+            Format::Markdown => {
+                let mut line = format!(
+                    "- [{}](<{}>)",
+                    escape_markdown(&entry.title),
+                    entry.url.replace('>', "%3E")
+                );
+                if let Some(site_name) = &entry.site_name {
+                    line.push_str(&format!(" · {}", escape_markdown(site_name)));
+                }
+                line.push_str(&format!(" · {}", entry.created_at.format("%Y-%m-%d")));
+                for tag in &tags {
+                    line.push_str(&format!(" `{}`", tag.replace('`', "'")));
+                }
+                line.push('\n');
+                line
+            }
+        })
+    }
+
+    fn footer(self) -> &'static str {
+        match self {
+            Format::Json => "\n]}\n",
+            Format::Netscape => "</DL><p>\n",
+            Format::Csv | Format::Markdown => "",
+        }
+    }
+}
+
+/// Stream all entries as an export file, newest first. Entries are read from the database as
+/// the client consumes the body, so large exports aren't held in memory. Only JSON exports
+/// include trashed entries, since only they keep when the entries were trashed.
+pub fn export(pool: SqlitePool, format: Format) -> Body {
+    let (tx, rx) = mpsc::channel::<ExportResult<Bytes>>(CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        let send = |chunk: ExportResult<String>| {
+            let tx = tx.clone();
+            async move { tx.send(chunk.map(Bytes::from)).await.is_ok() }
+        };
+
+        if !send(format.header()).await {
+            return;
+        }
+
+        let mut entries = Entry::stream_all(&pool, format == Format::Json);
+        let mut index = 0;
+        while let Some(entry) = entries.next().await {
+            let chunk = match entry {
+                Ok(entry) => format.entry(entry, index),
+                Err(e) => {
+                    // The response has started, so all we can do is cut it short
+                    warn!("Failed to export entries: {}", e);
+                    Err(e.into())
+                }
+            };
+            let failed = chunk.is_err();
+            if !send(chunk).await || failed {
+                return;
+            }
+            index += 1;
+        }
+
+        send(Ok(format.footer().to_string())).await;
+    });
+
+    Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+fn source_type_name(source_type: EntrySourceType) -> &'static str {
+    match source_type {
+        EntrySourceType::Article => "article",
+        EntrySourceType::Video => "video",
+    }
+}
+
+fn csv_row(fields: &[&str]) -> ExportResult<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(fields)?;
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Escape the characters that would otherwise be read as Markdown formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(if c == '\n' { ' ' } else { c });
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use sqlx::types::Json;
+
+    fn entry() -> Entry {
+        Entry {
+            id: 7,
+            url: "https://example.com/a?b=1&c=2".to_string(),
+            title: "Tom & [Jerry]".to_string(),
+            body: Some("<p>Body</p>".to_string()),
+            source_type: EntrySourceType::Article,
+            source_type_inferred: true,
+            author: None,
+            published_at: None,
+            site_name: Some("Example".to_string()),
+            canonical_url: None,
+            image_url: None,
+            language: None,
+            word_count: Some(1),
+            duration_secs: None,
+            author_url: None,
+            chapters: None,
+            transcript: None,
+            fetch_rule: None,
+            fetch_outcome: None,
+            fetch_http_status: None,
+            final_url: None,
+            fetch_duration_ms: None,
+            tags: Some(Json(vec!["rust".to_string(), "web dev".to_string()])),
+            archive_path: None,
+            last_checked_at: None,
+            http_status: None,
+            dead: false,
+            created_at: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn csv_export_quotes_fields() {
+        assert_eq!(
+            Format::Csv.entry(entry(), 0).unwrap(),
+            "7,https://example.com/a?b=1&c=2,Tom & [Jerry],article,,Example,,\
+             2020-01-01T00:00:00+00:00,rust|web dev,1,false\n"
+        );
+    }
+
+    #[test]
+    fn netscape_export_escapes_attributes() {
+        assert_eq!(
+            Format::Netscape.entry(entry(), 0).unwrap(),
+            "    <DT><A HREF=\"https://example.com/a?b=1&amp;c=2\" ADD_DATE=\"1577836800\" \
+             TAGS=\"rust,web dev\">Tom &amp; [Jerry]</A>\n"
+        );
+    }
+
+    #[test]
+    fn markdown_export_escapes_titles() {
+        assert_eq!(
+            Format::Markdown.entry(entry(), 0).unwrap(),
+            "- [Tom & \\[Jerry\\]](<https://example.com/a?b=1&c=2>) · Example · 2020-01-01 \
+             `rust` `web dev`\n"
+        );
+    }
+
+    #[test]
+    fn json_export_separates_entries() {
+        let first = Format::Json.entry(entry(), 0).unwrap();
+        let second = Format::Json.entry(entry(), 1).unwrap();
+
+        assert!(first.starts_with("{\"id\":7,"));
+        assert!(first.contains("\"body\":\"<p>Body</p>\""));
+        assert!(second.starts_with(",\n{"));
+    }
+}
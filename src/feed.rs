@@ -1,6 +1,10 @@
-use atom_syndication::{Content, Entry as AtomEntry, Feed as AtomFeed, FixedDateTime, Link, Text};
+use atom_syndication::{
+    Category, Content, Entry as AtomEntry, Feed as AtomFeed, FixedDateTime, Link, Person, Text,
+};
 use chrono::Utc;
+use url::Url;
 
+use crate::metadata::escape_html;
 use crate::models::Entry;
 
 const FEED_ENTRY_LIMIT: i64 = 50;
@@ -10,8 +14,8 @@ pub fn entry_limit() -> i64 {
     FEED_ENTRY_LIMIT
 }
 
-/// Build an Atom feed XML string from a list of entries.
-pub fn build_atom_feed(entries: &[Entry], base_url: &str) -> String {
+/// Build an Atom feed XML string from a list of entries, optionally with video transcripts.
+pub fn build_atom_feed(entries: &[Entry], base_url: &str, include_transcripts: bool) -> String {
     let updated = entries
         .first()
         .map(|e| e.created_at)
@@ -30,7 +34,10 @@ pub fn build_atom_feed(entries: &[Entry], base_url: &str) -> String {
         ..Default::default()
     };
 
-    let atom_entries: Vec<AtomEntry> = entries.iter().map(entry_to_atom).collect();
+    let atom_entries: Vec<AtomEntry> = entries
+        .iter()
+        .map(|entry| entry_to_atom(entry, base_url, include_transcripts))
+        .collect();
 
     let feed = AtomFeed {
         title: Text::plain("Laterfeed"),
@@ -44,25 +51,136 @@ pub fn build_atom_feed(entries: &[Entry], base_url: &str) -> String {
     feed.to_string()
 }
 
-fn entry_to_atom(entry: &Entry) -> AtomEntry {
-    let link = Link {
+fn entry_to_atom(entry: &Entry, base_url: &str, include_transcripts: bool) -> AtomEntry {
+    let mut links = vec![Link {
         href: entry.url.clone(),
         rel: "alternate".to_string(),
+        hreflang: entry.language.clone(),
         ..Default::default()
-    };
+    }];
+
+    if let Some(ref canonical_url) = entry.canonical_url
+        && canonical_url != &entry.url
+    {
+        links.push(Link {
+            href: canonical_url.clone(),
+            rel: "canonical".to_string(),
+            ..Default::default()
+        });
+    }
+
+    if let Some(ref image_url) = entry.image_url {
+        links.push(Link {
+            href: image_url.clone(),
+            rel: "enclosure".to_string(),
+            mime_type: Some(image_mime_type(image_url).to_string()),
+            ..Default::default()
+        });
+    }
+
+    // Point at the local snapshot in case the original page disappears
+    let archive_url = entry
+        .archive_path
+        .as_ref()
+        .map(|_| format!("{}/entries/{}/archive", base_url, entry.id));
+    if let Some(ref archive_url) = archive_url {
+        links.push(Link {
+            href: archive_url.clone(),
+            rel: "related".to_string(),
+            mime_type: Some("text/html".to_string()),
+            title: Some("Archived copy".to_string()),
+            ..Default::default()
+        });
+    }
 
     let mut atom_entry = AtomEntry {
         title: Text::plain(&entry.title),
         id: entry.url.clone(),
         updated: FixedDateTime::from(entry.created_at),
-        links: vec![link],
+        published: entry.published_at.map(FixedDateTime::from),
+        authors: entry
+            .author
+            .iter()
+            .map(|name| Person {
+                name: name.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        categories: entry
+            .tags
+            .iter()
+            .flat_map(|tags| tags.iter())
+            .map(|tag| Category {
+                term: tag.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        links,
         ..Default::default()
     };
 
+    let mut content = String::new();
+    if entry.dead {
+        content.push_str("<p><strong>The original link appears to be dead.</strong></p>");
+    }
+    if let Some(duration) = entry.duration_secs {
+        content.push_str(&format!("<p>{}", format_timestamp(duration)));
+        match (&entry.author, &entry.author_url) {
+            (Some(author), Some(author_url)) => content.push_str(&format!(
+                r#" · <a href="{}">{}</a>"#,
+                escape_html(author_url),
+                escape_html(author)
+            )),
+            (Some(author), None) => content.push_str(&format!(" · {}", escape_html(author))),
+            (None, _) => {}
+        }
+        content.push_str("</p>");
+    }
     if let Some(ref body) = entry.body {
+        content.push_str(body);
+    }
+    if let Some(ref chapters) = entry.chapters
+        && !chapters.is_empty()
+    {
+        content.push_str("<p>Chapters:</p><ul>");
+        for chapter in chapters.iter() {
+            content.push_str(&format!(
+                r#"<li><a href="{}">{}</a> {}</li>"#,
+                escape_html(&timestamp_url(&entry.url, chapter.start_secs)),
+                format_timestamp(chapter.start_secs),
+                escape_html(&chapter.title)
+            ));
+        }
+        content.push_str("</ul>");
+    }
+    if include_transcripts
+        && let Some(ref transcript) = entry.transcript
+        && !transcript.is_empty()
+    {
+        let lines: Vec<String> = transcript
+            .iter()
+            .map(|segment| {
+                format!(
+                    r#"<a href="{}">{}</a> {}"#,
+                    escape_html(&timestamp_url(&entry.url, segment.start_secs)),
+                    format_timestamp(segment.start_secs),
+                    escape_html(&segment.text)
+                )
+            })
+            .collect();
+        content.push_str(&format!("<p>Transcript:</p><p>{}</p>", lines.join("<br>")));
+    }
+    if let Some(ref archive_url) = archive_url {
+        content.push_str(&format!(
+            r#"<p><a href="{archive_url}">Archived copy</a></p>"#
+        ));
+    }
+
+    if !content.is_empty() {
         atom_entry.content = Some(Content {
-            value: Some(body.clone()),
+            value: Some(content),
             content_type: Some("html".to_string()),
+            lang: entry.language.clone(),
             ..Default::default()
         });
     }
@@ -70,10 +188,52 @@ fn entry_to_atom(entry: &Entry) -> AtomEntry {
     atom_entry
 }
 
+/// Format seconds as `M:SS`, or `H:MM:SS` from an hour up.
+fn format_timestamp(secs: i64) -> String {
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes}:{secs:02}")
+    }
+}
+
+/// Link to a point in a video, by setting its `t` parameter.
+fn timestamp_url(url: &str, secs: i64) -> String {
+    let Ok(mut url) = Url::parse(url) else {
+        return url.to_string();
+    };
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "t")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("t", &format!("{secs}s"));
+    url.to_string()
+}
+
+/// Guess an image's MIME type from its URL's file extension.
+fn image_mime_type(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("avif") => "image/avif",
+        _ => "image/jpeg",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::EntrySourceType;
+    use crate::models::{Chapter, EntrySourceType, TranscriptSegment};
     use chrono::TimeZone;
 
     fn make_entry(id: i64, url: &str, title: &str, body: Option<&str>) -> Entry {
@@ -83,13 +243,36 @@ mod tests {
             title: title.to_string(),
             body: body.map(|s| s.to_string()),
             source_type: EntrySourceType::Article,
+            source_type_inferred: false,
+            author: None,
+            published_at: None,
+            site_name: None,
+            canonical_url: None,
+            image_url: None,
+            language: None,
+            word_count: None,
+            duration_secs: None,
+            author_url: None,
+            chapters: None,
+            transcript: None,
+            fetch_rule: None,
+            fetch_outcome: None,
+            fetch_http_status: None,
+            final_url: None,
+            fetch_duration_ms: None,
+            tags: None,
+            archive_path: None,
+            last_checked_at: None,
+            http_status: None,
+            dead: false,
             created_at: Utc.with_ymd_and_hms(2026, 1, 15, 12, 0, 0).unwrap(),
+            deleted_at: None,
         }
     }
 
     #[test]
     fn build_atom_feed_empty_entries() {
-        let xml = build_atom_feed(&[], "https://example.com");
+        let xml = build_atom_feed(&[], "https://example.com", false);
 
         assert!(xml.contains("<title>Laterfeed</title>"));
         assert!(xml.contains("<id>https://example.com/feed</id>"));
@@ -106,7 +289,7 @@ mod tests {
             make_entry(3, "https://example.com/c", "Third", Some("<p>Body C</p>")),
         ];
 
-        let xml = build_atom_feed(&entries, "https://example.com");
+        let xml = build_atom_feed(&entries, "https://example.com", false);
 
         // All entries present
         assert!(xml.contains("<title>First</title>"));
@@ -124,6 +307,20 @@ mod tests {
         assert!(xml.contains("2026-01-15"));
     }
 
+    #[test]
+    fn entry_to_atom_adds_tags_as_categories() {
+        let mut entry = make_entry(1, "https://example.com/a", "Tagged", None);
+        entry.tags = Some(sqlx::types::Json(vec![
+            "rust".to_string(),
+            "web".to_string(),
+        ]));
+
+        let atom = entry_to_atom(&entry, "https://example.com", false);
+
+        let terms: Vec<&str> = atom.categories.iter().map(|c| c.term.as_str()).collect();
+        assert_eq!(terms, vec!["rust", "web"]);
+    }
+
     #[test]
     fn entry_to_atom_maps_all_fields() {
         let entry = make_entry(
@@ -133,7 +330,7 @@ mod tests {
             Some("<p>Test body</p>"),
         );
 
-        let atom = entry_to_atom(&entry);
+        let atom = entry_to_atom(&entry, "https://laterfeed.example", false);
 
         assert_eq!(atom.title.value, "Test Article");
         assert_eq!(atom.id, "https://example.com/article");
@@ -144,4 +341,155 @@ mod tests {
         assert_eq!(content.value.unwrap(), "<p>Test body</p>");
         assert_eq!(content.content_type.unwrap(), "html");
     }
+
+    #[test]
+    fn entry_to_atom_includes_page_metadata() {
+        let mut entry = make_entry(
+            11,
+            "https://example.com/post?utm_source=x",
+            "Rich",
+            Some("<p>Body</p>"),
+        );
+        entry.author = Some("Jane Doe".to_string());
+        entry.published_at = Some(Utc.with_ymd_and_hms(2025, 12, 1, 8, 30, 0).unwrap());
+        entry.canonical_url = Some("https://example.com/post".to_string());
+        entry.image_url = Some("https://example.com/lead.png?w=800".to_string());
+        entry.language = Some("en".to_string());
+
+        let atom = entry_to_atom(&entry, "https://laterfeed.example", false);
+
+        assert_eq!(atom.authors.len(), 1);
+        assert_eq!(atom.authors[0].name, "Jane Doe");
+        assert_eq!(
+            atom.published.unwrap().to_rfc3339(),
+            "2025-12-01T08:30:00+00:00"
+        );
+        assert_eq!(atom.links.len(), 3);
+        assert_eq!(atom.links[0].hreflang.as_deref(), Some("en"));
+        assert_eq!(atom.links[1].rel, "canonical");
+        assert_eq!(atom.links[1].href, "https://example.com/post");
+        assert_eq!(atom.links[2].rel, "enclosure");
+        assert_eq!(atom.links[2].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(atom.content.unwrap().lang.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn entry_to_atom_skips_canonical_link_matching_url() {
+        let mut entry = make_entry(12, "https://example.com/post", "Same", None);
+        entry.canonical_url = Some("https://example.com/post".to_string());
+
+        let atom = entry_to_atom(&entry, "https://laterfeed.example", false);
+
+        assert_eq!(atom.links.len(), 1);
+        assert!(atom.authors.is_empty());
+        assert!(atom.published.is_none());
+    }
+
+    #[test]
+    fn entry_to_atom_links_archived_copy() {
+        let mut entry = make_entry(13, "https://example.com/gone", "Gone", Some("<p>Body</p>"));
+        entry.archive_path = Some("archive/13.html".to_string());
+
+        let atom = entry_to_atom(&entry, "https://laterfeed.example", false);
+
+        let archive_link = atom.links.iter().find(|l| l.rel == "related").unwrap();
+        assert_eq!(
+            archive_link.href,
+            "https://laterfeed.example/entries/13/archive"
+        );
+        assert_eq!(archive_link.title.as_deref(), Some("Archived copy"));
+        assert_eq!(
+            atom.content.unwrap().value.unwrap(),
+            r#"<p>Body</p><p><a href="https://laterfeed.example/entries/13/archive">Archived copy</a></p>"#
+        );
+    }
+
+    #[test]
+    fn entry_to_atom_notes_dead_links() {
+        let mut entry = make_entry(14, "https://example.com/404", "Dead", Some("<p>Body</p>"));
+        entry.dead = true;
+
+        let atom = entry_to_atom(&entry, "https://laterfeed.example", false);
+
+        assert_eq!(
+            atom.content.unwrap().value.unwrap(),
+            "<p><strong>The original link appears to be dead.</strong></p><p>Body</p>"
+        );
+    }
+
+    #[test]
+    fn entry_to_atom_renders_video_details() {
+        let mut entry = make_entry(
+            15,
+            "https://www.youtube.com/watch?v=abc&t=10s",
+            "Talk",
+            Some("<p>Description</p>"),
+        );
+        entry.duration_secs = Some(3725);
+        entry.author = Some("Conf & Co".to_string());
+        entry.author_url = Some("https://www.youtube.com/@conf".to_string());
+        entry.chapters = Some(sqlx::types::Json(vec![
+            Chapter {
+                start_secs: 0,
+                title: "Intro".to_string(),
+            },
+            Chapter {
+                start_secs: 90,
+                title: "Q&A".to_string(),
+            },
+        ]));
+
+        let atom = entry_to_atom(&entry, "https://laterfeed.example", false);
+
+        assert_eq!(
+            atom.content.unwrap().value.unwrap(),
+            concat!(
+                r#"<p>1:02:05 · <a href="https://www.youtube.com/@conf">Conf &amp; Co</a></p>"#,
+                "<p>Description</p><p>Chapters:</p><ul>",
+                r#"<li><a href="https://www.youtube.com/watch?v=abc&amp;t=0s">0:00</a> Intro</li>"#,
+                r#"<li><a href="https://www.youtube.com/watch?v=abc&amp;t=90s">1:30</a> Q&amp;A</li>"#,
+                "</ul>"
+            )
+        );
+    }
+
+    #[test]
+    fn format_timestamp_minutes_and_hours() {
+        assert_eq!(format_timestamp(0), "0:00");
+        assert_eq!(format_timestamp(253), "4:13");
+        assert_eq!(format_timestamp(10800), "3:00:00");
+    }
+
+    #[test]
+    fn entry_to_atom_includes_transcript_when_enabled() {
+        let mut entry = make_entry(16, "https://youtu.be/abc", "Talk", None);
+        entry.transcript = Some(sqlx::types::Json(vec![
+            TranscriptSegment {
+                start_secs: 0,
+                text: "Hello".to_string(),
+            },
+            TranscriptSegment {
+                start_secs: 65,
+                text: "Rock & roll".to_string(),
+            },
+        ]));
+
+        assert!(
+            entry_to_atom(&entry, "https://laterfeed.example", false)
+                .content
+                .is_none()
+        );
+        assert_eq!(
+            entry_to_atom(&entry, "https://laterfeed.example", true)
+                .content
+                .unwrap()
+                .value
+                .unwrap(),
+            concat!(
+                "<p>Transcript:</p>",
+                r#"<p><a href="https://youtu.be/abc?t=0s">0:00</a> Hello<br>"#,
+                r#"<a href="https://youtu.be/abc?t=65s">1:05</a> Rock &amp; roll</p>"#
+            )
+        );
+    }
 }
@@ -1,18 +1,40 @@
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::{StatusCode, header},
-    response::IntoResponse,
+    Form, Json,
+    body::Bytes,
+    extract::{
+        Path, Query, State,
+        rejection::{FormRejection, QueryRejection},
+    },
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{KeepAlive, Sse},
+    },
 };
 use axum_valid::Valid;
+use chrono::Utc;
+use validator::Validate;
 
 use crate::{
-    AppState, FEED_TAG,
-    dto::{AddEntryRequest, EntryResponse, ListEntriesResponse},
+    AppState, FEED_TAG, archive,
+    dto::{
+        AddEntryRequest, BatchAddResponse, BatchEntryResult, CreateWebhookRequest,
+        DeleteEntryQuery, EntryResponse, ExportQuery, ImportQuery, ImportResponse,
+        ListDeliveriesResponse, ListEntriesQuery, ListEntriesResponse, ListWebhooksResponse,
+        LookupEntryQuery, PurgeTrashResponse, QuickSaveParams, WebhookResponse,
+    },
     errors::{Error, Result},
-    feed, metadata, models,
+    events, export, feed, import, media, models, quick_save,
+    save::{self, SaveRequest},
+    webhooks,
 };
 
+/// Most entries `POST /entries/batch` takes at once.
+const MAX_BATCH_ENTRIES: usize = 100;
+
+/// Deliveries listed by `GET /webhooks/{id}/deliveries`.
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
 pub async fn health() -> &'static str {
     "ok"
 }
@@ -25,6 +47,7 @@ pub async fn health() -> &'static str {
     tag = FEED_TAG,
     responses(
         (status = 201, description = "Entry", body = EntryResponse),
+        (status = 409, description = "URL already saved"),
     ),
     security(
         ("bearer" = [])
@@ -34,30 +57,85 @@ pub async fn add_entry(
     State(state): State<AppState>,
     Valid(Json(body)): Valid<Json<AddEntryRequest>>,
 ) -> Result<impl IntoResponse> {
-    let mut title = body.title;
+    let entry = save::save_entry(
+        &state,
+        SaveRequest {
+            url: body.url,
+            title: body.title,
+            body: None,
+            source_type: body.source_type.map(Into::into),
+            tags: body.tags.unwrap_or_default(),
+            created_at: None,
+            fetch_metadata: true,
+        },
+    )
+    .await
+    .map_err(|e| match e {
+        e if save::is_duplicate(&e) => Error::Conflict("URL already saved".to_string()),
+        e => e.into(),
+    })?;
 
-    // Fetch metadata from the page for title (if missing) and body content
-    let meta = metadata::fetch_metadata(&body.url).await;
-    if title.is_none() {
-        title = meta.title;
-    }
-    let page_body = meta.body;
+    Ok((StatusCode::CREATED, Json(EntryResponse::from(entry))))
+}
 
-    // Fall back to using the URL as the title if still missing
-    let title = title.unwrap_or_else(|| body.url.clone());
+#[utoipa::path(
+    post,
+    path = "/entries/batch",
+    summary = "Add many entries",
+    operation_id = "addEntries",
+    tag = FEED_TAG,
+    request_body = Vec<AddEntryRequest>,
+    responses(
+        (status = 200, description = "Result of each entry, in request order", body = BatchAddResponse),
+        (status = 400, description = "Too many entries"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn add_entries(
+    State(state): State<AppState>,
+    Json(body): Json<Vec<AddEntryRequest>>,
+) -> Result<impl IntoResponse> {
+    if body.len() > MAX_BATCH_ENTRIES {
+        return Err(Error::BadRequest(format!(
+            "At most {MAX_BATCH_ENTRIES} entries can be added at once"
+        )));
+    }
 
-    let source_type: models::EntrySourceType = body.source_type.into();
+    // Invalid entries are reported in place, the rest are saved together
+    let mut results: Vec<Option<BatchEntryResult>> = Vec::with_capacity(body.len());
+    let mut urls = Vec::new();
+    let mut requests = Vec::new();
+    for item in body {
+        if let Err(e) = item.validate() {
+            results.push(Some(BatchEntryResult::invalid(item.url, e.to_string())));
+            continue;
+        }
+        results.push(None);
+        urls.push(item.url.clone());
+        requests.push(SaveRequest {
+            url: item.url,
+            title: item.title,
+            body: None,
+            source_type: item.source_type.map(Into::into),
+            tags: item.tags.unwrap_or_default(),
+            created_at: None,
+            fetch_metadata: true,
+        });
+    }
 
-    let entry = models::Entry::create(
-        &state.pool,
-        &body.url,
-        &title,
-        page_body.as_deref(),
-        source_type,
-    )
-    .await?;
+    let mut saved = urls
+        .into_iter()
+        .zip(save::save_batch(&state, requests).await?)
+        .map(|(url, outcome)| BatchEntryResult::new(url, outcome));
+    let entries = results
+        .into_iter()
+        .map(|result| result.or_else(|| saved.next()))
+        .collect::<Option<_>>()
+        .expect("one outcome per saved entry");
 
-    Ok((StatusCode::CREATED, Json(EntryResponse::from(entry))))
+    Ok(Json(BatchAddResponse { entries }))
 }
 
 #[utoipa::path(
@@ -66,18 +144,169 @@ pub async fn add_entry(
     summary = "List entries",
     operation_id = "listEntries",
     tag = FEED_TAG,
+    params(ListEntriesQuery),
     responses(
         (status = 200, description = "List of entries", body = ListEntriesResponse),
     )
 )]
-pub async fn list_entries(State(state): State<AppState>) -> Result<impl IntoResponse> {
-    let entries = models::Entry::fetch_all(&state.pool).await?;
+pub async fn list_entries(
+    State(state): State<AppState>,
+    Query(query): Query<ListEntriesQuery>,
+) -> Result<impl IntoResponse> {
+    // Match domains the way they're stored, so `WWW.Example.com` finds `example.com`
+    let domain = query
+        .domain
+        .as_deref()
+        .map(str::trim)
+        .filter(|domain| !domain.is_empty())
+        .map(|domain| {
+            models::url_domain(&format!("https://{domain}/"))
+                .ok_or_else(|| Error::BadRequest(format!("Invalid domain: {domain}")))
+        })
+        .transpose()?;
+    let filter = models::EntryFilter {
+        dead: query.dead,
+        failed: query.failed,
+        search: query.q.filter(|q| !q.trim().is_empty()),
+        source_type: query.source_type.map(Into::into),
+        saved_after: query.saved_after,
+        saved_before: query.saved_before,
+        domain,
+        has_body: query.has_body,
+        sort: query.sort.unwrap_or_default().into(),
+    };
+    let entries = models::Entry::fetch_all(&state.pool, &filter).await?;
 
     Ok(Json(ListEntriesResponse {
-        entries: entries.into_iter().map(|e| e.into()).collect(),
+        entries: entries.into_iter().map(EntryResponse::summary).collect(),
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/events",
+    summary = "Stream entry changes",
+    operation_id = "streamEvents",
+    tag = FEED_TAG,
+    params(
+        ("Last-Event-ID" = Option<String>, Header, description = "ID of the last event received, to resume from"),
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events: `entry.created`, `entry.updated`, `entry.deleted` and `reset`", content_type = "text/event-stream", body = String),
+    )
+)]
+pub async fn stream_events(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    // An ID we can't read can't be resumed from, which gets a `reset`
+    let last_id = headers.get("last-event-id").map(|id| {
+        id.to_str()
+            .ok()
+            .and_then(|id| id.trim().parse().ok())
+            .unwrap_or(0)
+    });
+
+    Sse::new(events::stream(&state, last_id)).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/entries/{id}",
+    summary = "Get an entry",
+    operation_id = "getEntry",
+    tag = FEED_TAG,
+    params(
+        ("id" = i64, Path, description = "Entry ID"),
+    ),
+    responses(
+        (status = 200, description = "Entry", body = EntryResponse),
+        (status = 404, description = "Entry not found"),
+    )
+)]
+pub async fn get_entry(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let entry = models::Entry::fetch_by_id(&state.pool, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    Ok(Json(EntryResponse::from(entry)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/entries/lookup",
+    summary = "Find an entry by URL",
+    operation_id = "lookupEntry",
+    tag = FEED_TAG,
+    params(LookupEntryQuery),
+    responses(
+        (status = 200, description = "Entry", body = EntryResponse),
+        (status = 404, description = "URL not saved"),
+    )
+)]
+pub async fn lookup_entry(
+    State(state): State<AppState>,
+    Query(query): Query<LookupEntryQuery>,
+) -> Result<impl IntoResponse> {
+    // Entries saved before URLs were normalized are stored as given
+    let normalized = save::normalize_url(&query.url);
+    let entry = models::Entry::fetch_by_url(&state.pool, &[&normalized, query.url.trim()])
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    Ok(Json(EntryResponse::from(entry)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/save",
+    summary = "Save a link from a bookmarklet or share sheet",
+    operation_id = "quickSave",
+    tag = FEED_TAG,
+    params(QuickSaveParams),
+    responses(
+        (status = 201, description = "Confirmation page", content_type = "text/html", body = String),
+        (status = 200, description = "Already saved", content_type = "text/html", body = String),
+        (status = 303, description = "Redirect back to the saved page, with `redirect=true`"),
+        (status = 400, description = "Not a link to a web page, or invalid parameters", content_type = "text/html", body = String),
+        (status = 401, description = "Invalid token", content_type = "text/html", body = String),
+    )
+)]
+pub async fn quick_save(
+    State(state): State<AppState>,
+    params: std::result::Result<Query<QuickSaveParams>, QueryRejection>,
+) -> Result<Response> {
+    match params {
+        Ok(Query(params)) => quick_save::quick_save(&state, params).await,
+        Err(rejection) => Ok(quick_save::invalid_params(&rejection.body_text())),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/save",
+    summary = "Save a link from a form",
+    operation_id = "quickSaveForm",
+    tag = FEED_TAG,
+    request_body(content = QuickSaveParams, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 201, description = "Confirmation page", content_type = "text/html", body = String),
+        (status = 200, description = "Already saved", content_type = "text/html", body = String),
+        (status = 303, description = "Redirect back to the saved page, with `redirect=true`"),
+        (status = 400, description = "Not a link to a web page, or invalid parameters", content_type = "text/html", body = String),
+        (status = 401, description = "Invalid token", content_type = "text/html", body = String),
+    )
+)]
+pub async fn quick_save_form(
+    State(state): State<AppState>,
+    params: std::result::Result<Form<QuickSaveParams>, FormRejection>,
+) -> Result<Response> {
+    match params {
+        Ok(Form(params)) => quick_save::quick_save(&state, params).await,
+        Err(rejection) => Ok(quick_save::invalid_params(&rejection.body_text())),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/feed",
@@ -90,7 +319,11 @@ pub async fn list_entries(State(state): State<AppState>) -> Result<impl IntoResp
 )]
 pub async fn get_feed(State(state): State<AppState>) -> Result<impl IntoResponse> {
     let entries = models::Entry::fetch_latest(&state.pool, feed::entry_limit()).await?;
-    let xml = feed::build_atom_feed(&entries, &state.config.base_url);
+    let xml = feed::build_atom_feed(
+        &entries,
+        &state.config.base_url,
+        state.config.transcripts_in_feed.unwrap_or(false),
+    );
 
     Ok((
         [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
@@ -106,9 +339,10 @@ pub async fn get_feed(State(state): State<AppState>) -> Result<impl IntoResponse
     tag = FEED_TAG,
     params(
         ("id" = i64, Path, description = "Entry ID"),
+        DeleteEntryQuery,
     ),
     responses(
-        (status = 204, description = "Entry deleted"),
+        (status = 204, description = "Entry moved to the trash, or deleted with `permanent=true`"),
         (status = 404, description = "Entry not found"),
     ),
     security(
@@ -118,12 +352,446 @@ pub async fn get_feed(State(state): State<AppState>) -> Result<impl IntoResponse
 pub async fn delete_entry(
     State(state): State<AppState>,
     Path(id): Path<i64>,
+    Query(query): Query<DeleteEntryQuery>,
+) -> Result<impl IntoResponse> {
+    let entry = models::Entry::fetch_by_id(&state.pool, id).await?;
+
+    if !query.permanent.unwrap_or(false) {
+        let entry = entry.ok_or(Error::NotFound)?;
+        if !models::Entry::trash(&state.pool, id, Utc::now()).await? {
+            return Err(Error::NotFound);
+        }
+        events::publish(&state, events::Event::Deleted, &entry).await;
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    // Entries in the trash were already reported deleted
+    let (entry, trashed) = match entry {
+        Some(entry) => (entry, false),
+        None => {
+            let entry = models::Entry::fetch_trashed_by_id(&state.pool, id)
+                .await?
+                .ok_or(Error::NotFound)?;
+            (entry, true)
+        }
+    };
+    if !models::Entry::delete_by_id(&state.pool, id).await? {
+        return Err(Error::NotFound);
+    }
+    if !trashed {
+        events::publish(&state, events::Event::Deleted, &entry).await;
+    }
+    if let Some(archive_path) = entry.archive_path {
+        archive::remove_snapshot(&state.config.data_dir(), &archive_path).await;
+    }
+    if state.config.cache_images.unwrap_or(false) {
+        let (pool, data_dir) = (state.pool.clone(), state.config.data_dir());
+        let base_url = state.config.base_url.clone();
+        tokio::spawn(async move {
+            if let Err(e) = media::remove_orphans(&pool, &data_dir, &base_url).await {
+                tracing::warn!("Failed to remove orphaned media: {}", e);
+            }
+        });
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/trash",
+    summary = "List the trash",
+    operation_id = "listTrash",
+    tag = FEED_TAG,
+    responses(
+        (status = 200, description = "Trashed entries, most recently trashed first", body = ListEntriesResponse),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn list_trash(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let entries = models::Entry::fetch_trash(&state.pool).await?;
+
+    Ok(Json(ListEntriesResponse {
+        entries: entries.into_iter().map(EntryResponse::summary).collect(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/trash",
+    summary = "Empty the trash",
+    operation_id = "purgeTrash",
+    tag = FEED_TAG,
+    responses(
+        (status = 200, description = "Trash emptied", body = PurgeTrashResponse),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn purge_trash(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let purged = models::Entry::purge_trash(&state.pool, None).await?;
+
+    if purged > 0 {
+        let (pool, data_dir) = (state.pool.clone(), state.config.data_dir());
+        let base_url = state.config.base_url.clone();
+        tokio::spawn(async move {
+            if let Err(e) = archive::remove_orphans(&pool, &data_dir).await {
+                tracing::warn!("Failed to remove orphaned snapshots: {}", e);
+            }
+            if let Err(e) = media::remove_orphans(&pool, &data_dir, &base_url).await {
+                tracing::warn!("Failed to remove orphaned media: {}", e);
+            }
+        });
+    }
+
+    Ok(Json(PurgeTrashResponse { purged }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/trash/{id}/restore",
+    summary = "Restore an entry from the trash",
+    operation_id = "restoreEntry",
+    tag = FEED_TAG,
+    params(
+        ("id" = i64, Path, description = "Entry ID"),
+    ),
+    responses(
+        (status = 200, description = "Restored entry", body = EntryResponse),
+        (status = 404, description = "Entry not in the trash"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn restore_entry(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let entry = models::Entry::restore(&state.pool, id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    // It was reported deleted when trashed, so it comes back as a new entry
+    events::publish(&state, events::Event::Created, &entry).await;
+
+    Ok(Json(EntryResponse::from(entry)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/entries/{id}/archive",
+    summary = "Get an entry's archived page",
+    operation_id = "getEntryArchive",
+    tag = FEED_TAG,
+    params(
+        ("id" = i64, Path, description = "Entry ID"),
+    ),
+    responses(
+        (status = 200, description = "Self-contained HTML snapshot of the page", content_type = "text/html", body = String),
+        (status = 404, description = "Entry not found or not archived"),
+    )
+)]
+pub async fn get_entry_archive(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    let archive_path = models::Entry::fetch_by_id(&state.pool, id)
+        .await?
+        .and_then(|entry| entry.archive_path)
+        .ok_or(Error::NotFound)?;
+
+    let html = tokio::fs::read(state.config.data_dir().join(archive_path))
+        .await
+        .map_err(|_| Error::NotFound)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CONTENT_SECURITY_POLICY, archive::ARCHIVE_CSP),
+        ],
+        html,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/imports",
+    summary = "Import entries from an export file",
+    operation_id = "startImport",
+    tag = FEED_TAG,
+    params(ImportQuery),
+    request_body(
+        content = String,
+        content_type = "text/plain",
+        description = "Pocket HTML/CSV, Instapaper CSV, Wallabag/Omnivore JSON or Netscape bookmarks file",
+    ),
+    responses(
+        (status = 202, description = "Import started", body = ImportResponse),
+        (status = 400, description = "File could not be parsed or holds no links"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn start_import(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse> {
+    let content = String::from_utf8_lossy(&body);
+    let format = query
+        .format
+        .map(Into::into)
+        .unwrap_or_else(|| import::Format::detect(&content));
+
+    let items = import::parse(format, &content)
+        .map_err(|e| Error::BadRequest(format!("invalid {format} export: {e}")))?;
+    if items.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "no links found in {format} export"
+        )));
+    }
+
+    // Saving hundreds of links takes a while, so do it in the background
+    let job = state.imports.start(format, items.len());
+    tokio::spawn(import::run_import(
+        state.clone(),
+        job.id,
+        items,
+        query.fetch.unwrap_or(true),
+    ));
+
+    Ok((StatusCode::ACCEPTED, Json(ImportResponse::from(job))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/imports/{id}",
+    summary = "Get an import's progress",
+    operation_id = "getImport",
+    tag = FEED_TAG,
+    params(
+        ("id" = u64, Path, description = "Import ID"),
+    ),
+    responses(
+        (status = 200, description = "Import", body = ImportResponse),
+        (status = 404, description = "Import not found"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn get_import(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<impl IntoResponse> {
+    let job = state.imports.get(id).ok_or(Error::NotFound)?;
+
+    Ok(Json(ImportResponse::from(job)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    summary = "Export all entries",
+    operation_id = "exportEntries",
+    tag = FEED_TAG,
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Export file, newest entries first", content(
+            (String = "application/json"),
+            (String = "text/csv"),
+            (String = "text/html"),
+            (String = "text/markdown"),
+        )),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn export_entries(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let format = export::Format::from(query.format.unwrap_or_default());
+    let disposition = format!(r#"attachment; filename="{}""#, format.file_name());
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        export::export(state.pool.clone(), format),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/media/{hash}",
+    summary = "Get a cached image",
+    operation_id = "getMedia",
+    tag = FEED_TAG,
+    params(
+        ("hash" = String, Path, description = "SHA-256 hash of the image"),
+    ),
+    responses(
+        (status = 200, description = "Image", content_type = "image/*", body = Vec<u8>),
+        (status = 404, description = "Image not found"),
+    )
+)]
+pub async fn get_media(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse> {
+    if !media::is_media_hash(&hash) {
+        return Err(Error::NotFound);
+    }
+
+    let bytes = tokio::fs::read(media::media_dir(&state.config.data_dir()).join(&hash))
+        .await
+        .map_err(|_| Error::NotFound)?;
+    let content_type = media::sniff_image_type(&bytes).unwrap_or("application/octet-stream");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+            (header::CONTENT_SECURITY_POLICY, "sandbox"),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        bytes,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    summary = "Subscribe a webhook to entry events",
+    operation_id = "createWebhook",
+    tag = FEED_TAG,
+    responses(
+        (status = 201, description = "Webhook, with the secret its payloads are signed with", body = WebhookResponse),
+        (status = 400, description = "Invalid webhook, or a URL on an internal network"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Valid(Json(body)): Valid<Json<CreateWebhookRequest>>,
+) -> Result<impl IntoResponse> {
+    let events: Vec<String> = match body.events {
+        Some(events) if events.is_empty() => {
+            return Err(Error::BadRequest("No events to subscribe to".to_string()));
+        }
+        Some(events) => events
+            .into_iter()
+            .map(|event| event.name().to_string())
+            .collect(),
+        None => events::Event::ALL
+            .iter()
+            .map(|event| event.name().to_string())
+            .collect(),
+    };
+    state
+        .webhooks
+        .check_url(&body.url)
+        .await
+        .map_err(|e| Error::BadRequest(format!("Webhook URL refused: {e}")))?;
+    let secret = body
+        .secret
+        .filter(|secret| !secret.is_empty())
+        .unwrap_or_else(webhooks::generate_secret);
+
+    let webhook = models::Webhook::create(&state.pool, &body.url, &secret, &events).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WebhookResponse::with_secret(webhook)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    summary = "List webhooks",
+    operation_id = "listWebhooks",
+    tag = FEED_TAG,
+    responses(
+        (status = 200, description = "Webhooks, without their secrets", body = ListWebhooksResponse),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn list_webhooks(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let webhooks = models::Webhook::fetch_all(&state.pool).await?;
+
+    Ok(Json(ListWebhooksResponse {
+        webhooks: webhooks.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    summary = "Delete a webhook",
+    operation_id = "deleteWebhook",
+    tag = FEED_TAG,
+    params(
+        ("id" = i64, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 204, description = "Webhook and its deliveries deleted"),
+        (status = 404, description = "Webhook not found"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
 ) -> Result<impl IntoResponse> {
-    let deleted = models::Entry::delete_by_id(&state.pool, id).await?;
+    if !models::Webhook::delete_by_id(&state.pool, id).await? {
+        return Err(Error::NotFound);
+    }
 
-    if deleted {
-        Ok(StatusCode::NO_CONTENT)
-    } else {
-        Err(Error::NotFound)
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    summary = "List a webhook's latest deliveries",
+    operation_id = "listWebhookDeliveries",
+    tag = FEED_TAG,
+    params(
+        ("id" = i64, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 200, description = "Latest deliveries, newest first", body = ListDeliveriesResponse),
+        (status = 404, description = "Webhook not found"),
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse> {
+    if !models::Webhook::exists(&state.pool, id).await? {
+        return Err(Error::NotFound);
     }
+    let deliveries =
+        models::WebhookDelivery::fetch_for_webhook(&state.pool, id, DELIVERY_LOG_LIMIT).await?;
+
+    Ok(Json(ListDeliveriesResponse {
+        deliveries: deliveries.into_iter().map(Into::into).collect(),
+    }))
 }
@@ -0,0 +1,456 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+use url::Url;
+
+use crate::config::Config;
+use crate::models::{Entry, EntrySourceType, NewEntry};
+use crate::save::{self, SaveRequest};
+use crate::{AppState, init_state};
+
+mod bookmarks;
+mod instapaper;
+mod json;
+
+/// Finished imports kept around for their reports. Older ones are dropped.
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// Items between progress log lines.
+const PROGRESS_LOG_EVERY: usize = 50;
+
+pub type ParseResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// The kind of export file being imported.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    /// Pocket's HTML export, or its newer CSV export.
+    Pocket,
+    /// Instapaper's CSV export.
+    Instapaper,
+    /// Wallabag's JSON export.
+    Wallabag,
+    /// Omnivore's JSON export.
+    Omnivore,
+    /// A browser bookmarks file, as exported by Firefox, Chrome and most other tools.
+    Netscape,
+    /// A JSON export of this server, from `GET /export`.
+    Laterfeed,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pocket" => Some(Format::Pocket),
+            "instapaper" => Some(Format::Instapaper),
+            "wallabag" => Some(Format::Wallabag),
+            "omnivore" => Some(Format::Omnivore),
+            "netscape" | "bookmarks" => Some(Format::Netscape),
+            "laterfeed" => Some(Format::Laterfeed),
+            _ => None,
+        }
+    }
+
+    /// Guess the format of an export from its contents. Formats that share a file layout are
+    /// told apart by their parsers, so the guess only needs to get the layout right.
+    pub fn detect(content: &str) -> Self {
+        let start = content.trim_start_matches('\u{feff}').trim_start();
+        if start.starts_with('{') {
+            Format::Laterfeed
+        } else if start.starts_with('[') {
+            Format::Wallabag
+        } else if start.starts_with('<') {
+            Format::Netscape
+        } else {
+            Format::Instapaper
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Pocket => "pocket",
+            Format::Instapaper => "instapaper",
+            Format::Wallabag => "wallabag",
+            Format::Omnivore => "omnivore",
+            Format::Netscape => "netscape",
+            Format::Laterfeed => "laterfeed",
+        })
+    }
+}
+
+/// A link found in an export file.
+#[derive(Debug, PartialEq)]
+pub struct ImportItem {
+    pub url: String,
+    pub title: Option<String>,
+    /// When the link was saved in the exporting service.
+    pub created_at: Option<DateTime<Utc>>,
+    /// Tags, labels and folders the link was filed under.
+    pub tags: Vec<String>,
+    /// The page's content, for exports that include it. Such links aren't fetched again.
+    pub body: Option<String>,
+    pub source_type: Option<EntrySourceType>,
+    /// Everything stored about the entry, for exports of this server. Stored as is instead of
+    /// being saved anew.
+    pub entry: Option<Box<NewEntry>>,
+}
+
+impl ImportItem {
+    /// Build an item, skipping anything that isn't an HTTP(S) link, such as `javascript:`
+    /// bookmarklets or browser-internal `place:` queries.
+    fn new(
+        url: &str,
+        title: Option<&str>,
+        created_at: Option<DateTime<Utc>>,
+        tags: Vec<String>,
+    ) -> Option<Self> {
+        let url = url.trim();
+        let parsed = Url::parse(url).ok()?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return None;
+        }
+
+        let title = title
+            .map(str::trim)
+            .filter(|title| !title.is_empty() && *title != url)
+            .map(str::to_string);
+
+        Some(Self {
+            url: url.to_string(),
+            title,
+            created_at,
+            tags,
+            body: None,
+            source_type: None,
+            entry: None,
+        })
+    }
+}
+
+/// Parse the links out of an export file.
+pub fn parse(format: Format, content: &str) -> ParseResult<Vec<ImportItem>> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format {
+        Format::Netscape => Ok(bookmarks::parse(content)),
+        // Pocket's original export is a bookmarks-like HTML file, its final one a CSV file
+        Format::Pocket if content.trim_start().starts_with('<') => Ok(bookmarks::parse(content)),
+        Format::Pocket | Format::Instapaper => instapaper::parse(content),
+        Format::Wallabag | Format::Omnivore | Format::Laterfeed => json::parse(content),
+    }
+}
+
+/// Parse a timestamp given in seconds since the epoch. Milli- and microseconds, which some
+/// browsers write instead, are recognized by their size.
+fn parse_unix_time(value: &str) -> Option<DateTime<Utc>> {
+    let value: i64 = value.trim().parse().ok()?;
+    match value {
+        ..=0 => None,
+        100_000_000_000_000.. => DateTime::from_timestamp_micros(value),
+        100_000_000_000.. => DateTime::from_timestamp_millis(value),
+        _ => DateTime::from_timestamp(value, 0),
+    }
+}
+
+/// Parse an RFC 3339 timestamp, or Wallabag's variant without a colon in the offset.
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .or_else(|_| DateTime::parse_from_str(value.trim(), "%Y-%m-%dT%H:%M:%S%z"))
+        .ok()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// What happened to a link of an import.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemOutcome {
+    /// Saved as the entry with this ID.
+    Imported(i64),
+    /// Skipped since an entry with the same URL was already saved.
+    Duplicate,
+    Failed(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct ItemResult {
+    pub url: String,
+    pub outcome: ItemOutcome,
+}
+
+/// A running or finished import, with the result of each link processed so far.
+#[derive(Clone, Debug)]
+pub struct ImportJob {
+    pub id: u64,
+    pub format: Format,
+    /// Number of links found in the file.
+    pub total: usize,
+    pub items: Vec<ItemResult>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ImportJob {
+    /// Number of links with the given kind of outcome.
+    pub fn count(&self, matches: impl Fn(&ItemOutcome) -> bool) -> usize {
+        self.items
+            .iter()
+            .filter(|item| matches(&item.outcome))
+            .count()
+    }
+}
+
+/// Imports started since the server came up. Jobs only live in memory: an import interrupted
+/// by a restart can simply be started again, since already imported links are skipped.
+#[derive(Default)]
+pub struct ImportJobs {
+    inner: Mutex<Jobs>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    next_id: u64,
+    jobs: Vec<ImportJob>,
+}
+
+impl ImportJobs {
+    /// Register a new import of `total` links.
+    pub fn start(&self, format: Format, total: usize) -> ImportJob {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+
+        let job = ImportJob {
+            id: inner.next_id,
+            format,
+            total,
+            items: Vec::with_capacity(total),
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        inner.jobs.push(job.clone());
+        job
+    }
+
+    /// Mark an import as finished, forgetting the oldest finished ones beyond the limit.
+    fn finish(&self, id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.jobs.iter_mut().find(|job| job.id == id) {
+            job.finished_at = Some(Utc::now());
+        }
+
+        let finished = inner
+            .jobs
+            .iter()
+            .filter(|j| j.finished_at.is_some())
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        inner.jobs.retain(|j| {
+            let drop = excess > 0 && j.finished_at.is_some();
+            excess -= usize::from(drop);
+            !drop
+        });
+    }
+
+    pub fn get(&self, id: u64) -> Option<ImportJob> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.iter().find(|job| job.id == id).cloned()
+    }
+
+    fn update(&self, id: u64, update: impl FnOnce(&mut ImportJob)) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.jobs.iter_mut().find(|job| job.id == id) {
+            update(job);
+        }
+    }
+}
+
+/// Save the links of an import one by one, recording the outcome of each. Links already
+/// saved are skipped, so re-running an import only adds what is missing.
+pub async fn run_import(state: AppState, id: u64, items: Vec<ImportItem>, fetch_metadata: bool) {
+    let total = items.len();
+    info!(id, total, "starting import");
+
+    for (index, item) in items.into_iter().enumerate() {
+        let url = item.url.clone();
+        let outcome = import_item(&state, item, fetch_metadata).await;
+        if let ItemOutcome::Failed(ref error) = outcome {
+            warn!("Failed to import {}: {}", url, error);
+        }
+
+        state
+            .imports
+            .update(id, |job| job.items.push(ItemResult { url, outcome }));
+        if (index + 1) % PROGRESS_LOG_EVERY == 0 {
+            info!(id, "imported {}/{} links", index + 1, total);
+        }
+    }
+
+    state.imports.finish(id);
+    info!(id, total, "finished import");
+}
+
+async fn import_item(state: &AppState, item: ImportItem, fetch_metadata: bool) -> ItemOutcome {
+    match Entry::exists_with_url(&state.pool, &save::normalize_url(&item.url)).await {
+        Ok(true) => return ItemOutcome::Duplicate,
+        Ok(false) => {}
+        Err(e) => return ItemOutcome::Failed(e.to_string()),
+    }
+
+    if let Some(entry) = item.entry {
+        return match save::restore_entry(state, *entry).await {
+            Ok(entry) => ItemOutcome::Imported(entry.id),
+            Err(e) if save::is_duplicate(&e) => ItemOutcome::Duplicate,
+            Err(e) => ItemOutcome::Failed(e.to_string()),
+        };
+    }
+
+    let request = SaveRequest {
+        fetch_metadata: fetch_metadata && item.body.is_none(),
+        url: item.url,
+        title: item.title,
+        body: item.body,
+        source_type: item.source_type,
+        tags: item.tags,
+        created_at: item.created_at,
+    };
+    match save::save_entry(state, request).await {
+        Ok(entry) => ItemOutcome::Imported(entry.id),
+        Err(e) if save::is_duplicate(&e) => ItemOutcome::Duplicate,
+        Err(e) => ItemOutcome::Failed(e.to_string()),
+    }
+}
+
+const CLI_USAGE: &str = "usage: laterfeed import [--format FORMAT] [--no-fetch] FILE";
+
+/// The `import` command: import an export file straight into the database, printing the
+/// outcome of each link. Pages aren't archived and images aren't cached, since that happens
+/// in the background after the command would have exited.
+pub async fn run_cli(
+    mut config: Config,
+    args: impl Iterator<Item = String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut format = None;
+    let mut fetch_metadata = true;
+    let mut path = None;
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let name = args.next().ok_or(CLI_USAGE)?;
+                format =
+                    Some(Format::parse(&name).ok_or_else(|| format!("unknown format `{name}`"))?);
+            }
+            "--no-fetch" => fetch_metadata = false,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(CLI_USAGE.into()),
+        }
+    }
+    let path = path.ok_or(CLI_USAGE)?;
+
+    let content = String::from_utf8_lossy(&tokio::fs::read(Path::new(&path)).await?).into_owned();
+    let format = format.unwrap_or_else(|| Format::detect(&content));
+    let items = parse(format, &content).map_err(|e| format!("invalid {format} export: {e}"))?;
+
+    config.archive_pages = Some(false);
+    config.cache_images = Some(false);
+    let state = init_state(config).await;
+    let job = state.imports.start(format, items.len());
+    run_import(state.clone(), job.id, items, fetch_metadata).await;
+
+    let job = state.imports.get(job.id).ok_or("import disappeared")?;
+    for item in &job.items {
+        match &item.outcome {
+            ItemOutcome::Imported(id) => println!("imported   {} (entry {id})", item.url),
+            ItemOutcome::Duplicate => println!("duplicate  {}", item.url),
+            ItemOutcome::Failed(error) => println!("failed     {}: {error}", item.url),
+        }
+    }
+    println!(
+        "{} imported, {} duplicates, {} failed",
+        job.count(|o| matches!(o, ItemOutcome::Imported(_))),
+        job.count(|o| *o == ItemOutcome::Duplicate),
+        job.count(|o| matches!(o, ItemOutcome::Failed(_))),
+    );
+
+    state.pool.close().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_recognizes_file_layouts() {
+        assert_eq!(
+            Format::detect("\u{feff} [{\"url\": \"x\"}]"),
+            Format::Wallabag
+        );
+        assert_eq!(Format::detect("{\"entries\": []}"), Format::Laterfeed);
+        assert_eq!(
+            Format::detect("<!DOCTYPE NETSCAPE-Bookmark-file-1>"),
+            Format::Netscape
+        );
+        assert_eq!(
+            Format::detect("URL,Title,Selection,Folder,Timestamp"),
+            Format::Instapaper
+        );
+    }
+
+    #[test]
+    fn item_new_skips_non_http_links_and_redundant_titles() {
+        assert!(ImportItem::new("javascript:alert(1)", None, None, vec![]).is_none());
+        assert!(ImportItem::new("place:sort=8", None, None, vec![]).is_none());
+
+        let item = ImportItem::new(
+            " https://example.com/a ",
+            Some("https://example.com/a"),
+            None,
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(item.url, "https://example.com/a");
+        assert_eq!(item.title, None);
+    }
+
+    #[test]
+    fn parse_datetime_accepts_rfc3339_and_wallabag_offsets() {
+        let expected = DateTime::from_timestamp(1_577_836_800, 0);
+        assert_eq!(parse_datetime("2020-01-01T00:00:00Z"), expected);
+        assert_eq!(parse_datetime("2020-01-01T02:00:00+0200"), expected);
+        assert_eq!(parse_datetime("yesterday"), None);
+    }
+
+    #[test]
+    fn parse_unix_time_accepts_seconds_millis_and_micros() {
+        let expected = DateTime::from_timestamp(1_577_836_800, 0);
+        assert_eq!(parse_unix_time("1577836800"), expected);
+        assert_eq!(parse_unix_time("1577836800000"), expected);
+        assert_eq!(parse_unix_time("1577836800000000"), expected);
+        assert_eq!(parse_unix_time("0"), None);
+        assert_eq!(parse_unix_time("soon"), None);
+    }
+
+    #[test]
+    fn jobs_record_results_and_drop_old_finished_jobs() {
+        let jobs = ImportJobs::default();
+        let job = jobs.start(Format::Pocket, 1);
+        jobs.update(job.id, |job| {
+            job.items.push(ItemResult {
+                url: "https://example.com".to_string(),
+                outcome: ItemOutcome::Duplicate,
+            })
+        });
+        assert_eq!(jobs.get(job.id).unwrap().items.len(), 1);
+
+        for _ in 0..=MAX_FINISHED_JOBS {
+            let job = jobs.start(Format::Pocket, 0);
+            jobs.finish(job.id);
+        }
+        // The first job never finished, so it is kept
+        assert!(jobs.get(job.id).is_some());
+        assert!(jobs.get(job.id + 1).is_none());
+    }
+}
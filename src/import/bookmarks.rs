@@ -0,0 +1,160 @@
+use scraper::{ElementRef, Html, Selector};
+
+use crate::import::{ImportItem, parse_unix_time};
+
+/// Parse a Netscape bookmarks file, as exported by browsers and Pocket. Folders the bookmark
+/// is nested in become tags, outermost first, followed by the bookmark's own `TAGS`.
+pub fn parse(html: &str) -> Vec<ImportItem> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+
+    document
+        .select(&selector)
+        .filter_map(|link| {
+            let el = link.value();
+            let title = link.text().collect::<String>();
+            let created_at = el
+                .attr("add_date")
+                .or_else(|| el.attr("time_added"))
+                .and_then(parse_unix_time);
+
+            let mut tags = folders(link);
+            tags.extend(
+                el.attr("tags")
+                    .into_iter()
+                    .flat_map(|tags| tags.split(','))
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string),
+            );
+
+            ImportItem::new(el.attr("href")?, Some(&title), created_at, tags)
+        })
+        .collect()
+}
+
+/// The names of the folders a bookmark is in, outermost first. A folder is a `DT` holding an
+/// `H3` with its name, followed by a `DL` of its bookmarks. The browser's own root folders,
+/// such as the bookmarks toolbar, are left out.
+fn folders(link: ElementRef) -> Vec<String> {
+    let mut folders: Vec<String> = link
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter_map(|ancestor| {
+            ancestor
+                .children()
+                .filter_map(ElementRef::wrap)
+                .find(|child| child.value().name() == "h3")
+        })
+        .filter(|heading| {
+            let el = heading.value();
+            el.attr("personal_toolbar_folder").is_none()
+                && el.attr("unfiled_bookmarks_folder").is_none()
+        })
+        .map(|heading| heading.text().collect::<String>().trim().to_string())
+        .collect();
+
+    folders.reverse();
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[test]
+    fn parse_reads_netscape_bookmarks_with_folders() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1600000000" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/toolbar" ADD_DATE="1600000001">On the toolbar</A>
+        <DT><H3>Reading</H3>
+        <DL><p>
+            <DT><H3>Rust</H3>
+            <DL><p>
+                <DT><A HREF="https://example.com/rust" ADD_DATE="1600000002" TAGS="lang,systems">Rust Book</A>
+            </DL><p>
+            <DT><A HREF="https://example.com/later" ADD_DATE="1600000003">Later</A>
+            <DT><A HREF="javascript:void(0)">Bookmarklet</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/root">Root</A>
+</DL><p>"#;
+
+        let items = parse(html);
+
+        let summary: Vec<(&str, Vec<&str>)> = items
+            .iter()
+            .map(|item| {
+                (
+                    item.url.as_str(),
+                    item.tags.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("https://example.com/toolbar", vec![]),
+                (
+                    "https://example.com/rust",
+                    vec!["Reading", "Rust", "lang", "systems"]
+                ),
+                ("https://example.com/later", vec!["Reading"]),
+                ("https://example.com/root", vec![]),
+            ]
+        );
+        assert_eq!(items[1].title.as_deref(), Some("Rust Book"));
+        assert_eq!(
+            items[1].created_at,
+            DateTime::from_timestamp(1_600_000_002, 0)
+        );
+        assert_eq!(items[3].created_at, None);
+    }
+
+    #[test]
+    fn parse_reads_pocket_html_export() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Pocket Export</title></head><body>
+<h1>Unread</h1>
+<ul>
+<li><a href="https://example.com/a" time_added="1577836800" tags="news,tech">Article A</a></li>
+</ul>
+<h1>Read Archive</h1>
+<ul>
+<li><a href="https://example.com/b" time_added="1577923200" tags="">https://example.com/b</a></li>
+</ul>
+</body></html>"#;
+
+        let items = parse(html);
+
+        assert_eq!(
+            items,
+            vec![
+                ImportItem {
+                    url: "https://example.com/a".to_string(),
+                    title: Some("Article A".to_string()),
+                    created_at: DateTime::from_timestamp(1_577_836_800, 0),
+                    tags: vec!["news".to_string(), "tech".to_string()],
+                    body: None,
+                    source_type: None,
+                    entry: None,
+                },
+                ImportItem {
+                    url: "https://example.com/b".to_string(),
+                    title: None,
+                    created_at: DateTime::from_timestamp(1_577_923_200, 0),
+                    tags: vec![],
+                    body: None,
+                    source_type: None,
+                    entry: None,
+                },
+            ]
+        );
+    }
+}
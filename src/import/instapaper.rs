@@ -0,0 +1,127 @@
+use crate::import::{ImportItem, ParseResult, parse_datetime, parse_unix_time};
+
+/// Instapaper's built-in folders, which say nothing about the link itself.
+const BUILTIN_FOLDERS: &[&str] = &["unread", "archive", "starred"];
+
+/// Parse a CSV export with a header row: Instapaper's `URL,Title,Selection,Folder,Timestamp`
+/// (plus `Tags` in newer exports), Pocket's `title,url,time_added,tags,status`, or our own CSV
+/// export. Columns are found by name, ignoring case.
+pub fn parse(content: &str) -> ParseResult<Vec<ImportItem>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers = reader.headers()?.clone();
+    let column = |names: &[&str]| {
+        headers.iter().position(|header| {
+            names
+                .iter()
+                .any(|name| header.trim().eq_ignore_ascii_case(name))
+        })
+    };
+    let url_column = column(&["url"]).ok_or("no URL column")?;
+    let title_column = column(&["title"]);
+    let time_column = column(&["timestamp", "time_added", "created_at"]);
+    let folder_column = column(&["folder"]);
+    let tags_column = column(&["tags"]);
+
+    let mut items = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let field = |column: Option<usize>| column.and_then(|column| record.get(column));
+
+        let mut tags: Vec<String> = field(folder_column)
+            .map(str::trim)
+            .filter(|folder| {
+                !folder.is_empty()
+                    && !BUILTIN_FOLDERS
+                        .iter()
+                        .any(|builtin| folder.eq_ignore_ascii_case(builtin))
+            })
+            .map(str::to_string)
+            .into_iter()
+            .collect();
+        tags.extend(field(tags_column).map(parse_tags).unwrap_or_default());
+
+        items.extend(ImportItem::new(
+            record.get(url_column).unwrap_or_default(),
+            field(title_column),
+            field(time_column)
+                .and_then(|time| parse_unix_time(time).or_else(|| parse_datetime(time))),
+            tags,
+        ));
+    }
+
+    Ok(items)
+}
+
+/// Parse a tags field: a JSON array of names, or names separated by `|` (Pocket) or commas.
+fn parse_tags(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let tags = match serde_json::from_str::<Vec<String>>(value) {
+        Ok(tags) => tags,
+        Err(_) => value.split(['|', ',']).map(str::to_string).collect(),
+    };
+
+    tags.into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[test]
+    fn parse_reads_instapaper_export() {
+        let csv = "URL,Title,Selection,Folder,Timestamp,Tags\n\
+            https://example.com/a,\"Hello, \"\"World\"\"\",,Unread,1577836800,\"[\"\"rust\"\"]\"\n\
+            https://example.com/b,B,\"Multi\nline\",Programming,1577923200,\n\
+            not a url,C,,Archive,1577923200,\n";
+
+        let items = parse(csv).unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                ImportItem {
+                    url: "https://example.com/a".to_string(),
+                    title: Some("Hello, \"World\"".to_string()),
+                    created_at: DateTime::from_timestamp(1_577_836_800, 0),
+                    tags: vec!["rust".to_string()],
+                    body: None,
+                    source_type: None,
+                    entry: None,
+                },
+                ImportItem {
+                    url: "https://example.com/b".to_string(),
+                    title: Some("B".to_string()),
+                    created_at: DateTime::from_timestamp(1_577_923_200, 0),
+                    tags: vec!["Programming".to_string()],
+                    body: None,
+                    source_type: None,
+                    entry: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_reads_pocket_csv_export() {
+        let csv = "title,url,time_added,tags,status\n\
+            Article,https://example.com/a,1577836800,news|tech,unread\n";
+
+        let items = parse(csv).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title.as_deref(), Some("Article"));
+        assert_eq!(items[0].tags, vec!["news", "tech"]);
+    }
+
+    #[test]
+    fn parse_requires_url_column() {
+        assert!(parse("Title,Folder\nA,Unread\n").is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::types::Json;
+
+use crate::dto;
+use crate::import::{ImportItem, ParseResult, parse_datetime};
+use crate::models::{Chapter, EntrySourceType, NewEntry, TranscriptSegment};
+
+/// An entry of a Wallabag or Omnivore export. Wallabag calls the saved time `created_at`, the
+/// tags `tags` and the body `content`; Omnivore `savedAt` and `labels`.
+#[derive(Deserialize)]
+struct ExportEntry {
+    url: Option<String>,
+    title: Option<String>,
+    #[serde(alias = "savedAt")]
+    created_at: Option<String>,
+    #[serde(default, alias = "labels")]
+    tags: Vec<Tag>,
+    #[serde(alias = "content")]
+    body: Option<String>,
+}
+
+/// An entry of a Laterfeed export, with everything stored about it. Fields missing from exports
+/// of older versions are left empty.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct LaterfeedEntry {
+    url: String,
+    title: Option<String>,
+    body: Option<String>,
+    source_type: Option<dto::EntrySourceType>,
+    source_type_inferred: bool,
+    author: Option<String>,
+    published_at: Option<DateTime<Utc>>,
+    site_name: Option<String>,
+    canonical_url: Option<String>,
+    image_url: Option<String>,
+    language: Option<String>,
+    word_count: Option<i64>,
+    duration_secs: Option<i64>,
+    author_url: Option<String>,
+    chapters: Vec<Chapter>,
+    transcript: Vec<TranscriptSegment>,
+    fetch_rule: Option<String>,
+    fetch_outcome: Option<dto::FetchOutcome>,
+    fetch_http_status: Option<i64>,
+    final_url: Option<String>,
+    fetch_duration_ms: Option<i64>,
+    tags: Vec<String>,
+    archive_path: Option<String>,
+    last_checked_at: Option<DateTime<Utc>>,
+    http_status: Option<i64>,
+    dead: bool,
+    created_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Wallabag and Omnivore export an array of entries; Laterfeed wraps its entries in an object.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Entries(Vec<ExportEntry>),
+    Laterfeed { entries: Vec<LaterfeedEntry> },
+}
+
+/// A tag, given by name or as an object, depending on the exporting version.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Tag {
+    Name(String),
+    Object {
+        #[serde(alias = "name")]
+        label: String,
+    },
+}
+
+/// Parse a Wallabag or Omnivore JSON export (an array of entries), or a Laterfeed one.
+pub fn parse(content: &str) -> ParseResult<Vec<ImportItem>> {
+    let entries = match serde_json::from_str(content)? {
+        Export::Entries(entries) => entries,
+        Export::Laterfeed { entries } => {
+            return Ok(entries.into_iter().filter_map(laterfeed_item).collect());
+        }
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let tags = entry
+                .tags
+                .into_iter()
+                .map(|tag| match tag {
+                    Tag::Name(name) | Tag::Object { label: name } => name,
+                })
+                .collect();
+
+            let item = ImportItem::new(
+                entry.url.as_deref()?,
+                entry.title.as_deref(),
+                entry.created_at.as_deref().and_then(parse_datetime),
+                tags,
+            )?;
+            Some(ImportItem {
+                body: entry.body.filter(|body| !body.trim().is_empty()),
+                ..item
+            })
+        })
+        .collect())
+}
+
+/// An item restoring an entry of a Laterfeed export as it was.
+fn laterfeed_item(entry: LaterfeedEntry) -> Option<ImportItem> {
+    let item = ImportItem::new(
+        &entry.url,
+        entry.title.as_deref(),
+        entry.created_at,
+        entry.tags.clone(),
+    )?;
+    let source_type = entry.source_type.map(EntrySourceType::from);
+
+    Some(ImportItem {
+        body: entry.body.clone(),
+        source_type,
+        entry: Some(Box::new(NewEntry {
+            title: entry.title.unwrap_or_else(|| item.url.clone()),
+            url: item.url.clone(),
+            body: entry.body,
+            source_type: source_type.unwrap_or_default(),
+            source_type_inferred: entry.source_type_inferred,
+            author: entry.author,
+            published_at: entry.published_at,
+            site_name: entry.site_name,
+            canonical_url: entry.canonical_url,
+            image_url: entry.image_url,
+            language: entry.language,
+            word_count: entry.word_count,
+            duration_secs: entry.duration_secs,
+            author_url: entry.author_url,
+            chapters: (!entry.chapters.is_empty()).then_some(Json(entry.chapters)),
+            transcript: (!entry.transcript.is_empty()).then_some(Json(entry.transcript)),
+            fetch_rule: entry.fetch_rule,
+            fetch_outcome: entry.fetch_outcome.map(Into::into),
+            fetch_http_status: entry.fetch_http_status,
+            final_url: entry.final_url,
+            fetch_duration_ms: entry.fetch_duration_ms,
+            tags: (!entry.tags.is_empty()).then_some(Json(entry.tags)),
+            archive_path: entry.archive_path,
+            last_checked_at: entry.last_checked_at,
+            http_status: entry.http_status,
+            dead: entry.dead,
+            created_at: entry.created_at,
+            deleted_at: entry.deleted_at,
+        })),
+        ..item
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[test]
+    fn parse_reads_wallabag_export() {
+        let json = r#"[
+            {
+                "id": 1,
+                "title": "Article",
+                "url": "https://example.com/a",
+                "is_archived": 0,
+                "created_at": "2020-01-01T02:00:00+0200",
+                "content": "<p>Saved</p>",
+                "tags": ["rust", {"id": 3, "label": "web", "slug": "web"}]
+            },
+            {"id": 2, "title": "No URL", "url": null}
+        ]"#;
+
+        let items = parse(json).unwrap();
+
+        assert_eq!(
+            items,
+            vec![ImportItem {
+                url: "https://example.com/a".to_string(),
+                title: Some("Article".to_string()),
+                created_at: DateTime::from_timestamp(1_577_836_800, 0),
+                tags: vec!["rust".to_string(), "web".to_string()],
+                body: Some("<p>Saved</p>".to_string()),
+                source_type: None,
+                entry: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_reads_omnivore_export() {
+        let json = r#"[{
+            "id": "f1",
+            "slug": "article",
+            "title": "Article",
+            "url": "https://example.com/a",
+            "state": "Archived",
+            "labels": ["Newsletter"],
+            "savedAt": "2020-01-01T00:00:00.000Z"
+        }]"#;
+
+        let items = parse(json).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].tags, vec!["Newsletter"]);
+        assert_eq!(
+            items[0].created_at,
+            DateTime::from_timestamp(1_577_836_800, 0)
+        );
+    }
+
+    #[test]
+    fn parse_reads_laterfeed_export() {
+        let json = r#"{"entries": [
+            {
+                "id": 1,
+                "url": "https://example.com/v",
+                "title": "Video",
+                "body": null,
+                "source_type": "video",
+                "chapters": [{"start_secs": 0, "title": "Intro"}],
+                "fetch_outcome": "http_error",
+                "fetch_http_status": 503,
+                "tags": ["talks"],
+                "archived": true,
+                "archive_path": "archive/1.html",
+                "dead": true,
+                "created_at": "2020-01-01T00:00:00Z",
+                "deleted_at": "2020-01-02T00:00:00Z"
+            }
+        ]}"#;
+
+        let items = parse(json).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].source_type, Some(EntrySourceType::Video));
+        assert_eq!(items[0].body, None);
+        assert_eq!(items[0].tags, vec!["talks"]);
+        assert_eq!(
+            items[0].entry.as_deref(),
+            Some(&NewEntry {
+                url: "https://example.com/v".to_string(),
+                title: "Video".to_string(),
+                source_type: EntrySourceType::Video,
+                chapters: Some(Json(vec![Chapter {
+                    start_secs: 0,
+                    title: "Intro".to_string(),
+                }])),
+                fetch_outcome: Some(crate::models::FetchOutcome::HttpError),
+                fetch_http_status: Some(503),
+                tags: Some(Json(vec!["talks".to_string()])),
+                archive_path: Some("archive/1.html".to_string()),
+                dead: true,
+                created_at: DateTime::from_timestamp(1_577_836_800, 0),
+                deleted_at: DateTime::from_timestamp(1_577_923_200, 0),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_json() {
+        assert!(parse("{not json").is_err());
+    }
+}
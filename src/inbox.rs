@@ -0,0 +1,398 @@
+mod smtp;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use mail_parser::{Message, MessageParser, MimeHeaders};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use scraper::{Html, Selector};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use crate::{
+    AppState,
+    models::Entry,
+    save::{self, SaveRequest},
+};
+
+/// Largest message accepted (10 MiB).
+const MAX_MESSAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// An email with at most this many words besides its links is taken as a list of links to
+/// save, rather than as something to read in itself.
+const LINK_EMAIL_MAX_WORDS: usize = 50;
+
+/// Links saved from one email at most.
+const MAX_LINKS_PER_EMAIL: usize = 50;
+
+/// What an email asks to save.
+#[derive(Debug, PartialEq)]
+enum Mail {
+    /// A few links, each saved as its own entry.
+    Links(Vec<String>),
+    /// Content to read, such as a newsletter, saved as a complete entry.
+    Newsletter {
+        /// `mid:` URL built from the Message-ID, which makes redeliveries duplicates.
+        url: String,
+        title: String,
+        body: String,
+    },
+}
+
+/// Senders whose mail is accepted: full addresses, or `@domain` for a whole domain.
+#[derive(Debug, Default)]
+struct Senders(Vec<String>);
+
+impl Senders {
+    fn parse(list: &str) -> Self {
+        Self(
+            list.split(',')
+                .map(|sender| sender.trim().to_ascii_lowercase())
+                .filter(|sender| !sender.is_empty())
+                .collect(),
+        )
+    }
+
+    fn allows(&self, address: &str) -> bool {
+        let address = address.to_ascii_lowercase();
+        !address.is_empty()
+            && self.0.iter().any(|sender| {
+                if sender.starts_with('@') {
+                    address.ends_with(sender.as_str())
+                } else {
+                    address == *sender
+                }
+            })
+    }
+}
+
+/// Whether a recipient carries the token as the plus tag of its local part, as in
+/// `save+token@host`.
+fn carries_token(recipient: &str, token: &str) -> bool {
+    let local = recipient
+        .rsplit_once('@')
+        .map_or(recipient, |(local, _)| local);
+    local.split_once('+').is_some_and(|(_, tag)| tag == token)
+}
+
+/// Starts the embedded SMTP server if `SMTP_PORT` is set. Returns the address it listens on, or
+/// `None` if it's disabled or couldn't be started.
+///
+/// Mail from the allowed senders, and to a recipient carrying `SMTP_RECIPIENT_TOKEN` if set, is
+/// saved: links in short emails become entries of their own, and newsletters are saved whole,
+/// with the subject as the title. Everything else is refused before the message is sent.
+pub async fn start_smtp_server(state: AppState) -> Option<SocketAddr> {
+    let Some(port) = state.config.smtp_port else {
+        info!("no SMTP port configured, email inbox disabled");
+        return None;
+    };
+
+    let senders = Arc::new(Senders::parse(
+        state
+            .config
+            .smtp_allowed_senders
+            .as_deref()
+            .unwrap_or_default(),
+    ));
+    if senders.0.is_empty() {
+        warn!("SMTP_ALLOWED_SENDERS is empty, all mail will be refused");
+    }
+
+    let token: Option<Arc<str>> = state
+        .config
+        .smtp_recipient_token
+        .as_deref()
+        .filter(|token| !token.is_empty())
+        .map(Arc::from);
+
+    let address = state.config.smtp_address.as_deref().unwrap_or("127.0.0.1");
+    let bound = async {
+        let listener = TcpListener::bind((address, port)).await?;
+        let local_addr = listener.local_addr()?;
+        Ok::<_, std::io::Error>((listener, local_addr))
+    };
+    let (listener, local_addr) = match bound.await {
+        Ok(bound) => bound,
+        Err(e) => {
+            error!("Failed to start SMTP server on {}:{}: {}", address, port, e);
+            return None;
+        }
+    };
+    info!("SMTP server listening on {}", local_addr);
+    if token.is_none() && !local_addr.ip().is_loopback() {
+        warn!(
+            "SMTP_RECIPIENT_TOKEN is not set, so anyone who can reach the SMTP server can save \
+             mail by giving an allowed sender"
+        );
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("Failed to accept SMTP connection: {}", e);
+                    continue;
+                }
+            };
+
+            let (state, senders, token) = (state.clone(), senders.clone(), token.clone());
+            tokio::spawn(async move {
+                let hostname = Url::parse(&state.config.base_url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .unwrap_or_else(|| "laterfeed".to_string());
+                let allows = |sender: &str| senders.allows(sender);
+                let allows_recipient = |recipient: &str| {
+                    token
+                        .as_deref()
+                        .is_none_or(|token| carries_token(recipient, token))
+                };
+                let policy = smtp::Policy {
+                    hostname: &hostname,
+                    max_message_bytes: MAX_MESSAGE_BYTES,
+                    allows_sender: &allows,
+                    allows_recipient: &allows_recipient,
+                };
+
+                let result = smtp::session(stream, &policy, |envelope| {
+                    info!("received email from {}", envelope.sender);
+                    tokio::spawn(save_email(state.clone(), envelope.data));
+                })
+                .await;
+                if let Err(e) = result {
+                    debug!("SMTP session with {} ended: {}", peer, e);
+                }
+            });
+        }
+    });
+
+    Some(local_addr)
+}
+
+/// Save what a received email asks to save.
+async fn save_email(state: AppState, data: Vec<u8>) {
+    let Some(mail) = MessageParser::default()
+        .parse(&data)
+        .as_ref()
+        .and_then(read_mail)
+    else {
+        info!("ignoring email without links or content");
+        return;
+    };
+
+    let requests = match mail {
+        Mail::Links(urls) => urls
+            .into_iter()
+            .map(|url| SaveRequest {
+                url,
+                fetch_metadata: true,
+                ..Default::default()
+            })
+            .collect(),
+        Mail::Newsletter { url, title, body } => vec![SaveRequest {
+            url,
+            title: Some(title),
+            body: Some(body),
+            ..Default::default()
+        }],
+    };
+
+    for request in requests {
+        let url = save::normalize_url(&request.url);
+        match Entry::exists_with_url(&state.pool, &url).await {
+            Ok(true) => {
+                debug!("skipping already saved {}", url);
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                warn!("Failed to save {} from email: {}", url, e);
+                continue;
+            }
+        }
+
+        match save::save_entry(&state, request).await {
+            Ok(entry) => info!(id = entry.id, "saved {} from email", entry.url),
+            Err(e) if save::is_duplicate(&e) => debug!("skipping already saved {}", url),
+            Err(e) => warn!("Failed to save {} from email: {}", url, e),
+        }
+    }
+}
+
+/// Decide what to save from an email: the links of a short one, or the whole of a longer one.
+fn read_mail(message: &Message) -> Option<Mail> {
+    let text = message.body_text(0).unwrap_or_default();
+    let (links, words) = links_and_words(&text);
+
+    if !links.is_empty() && words <= LINK_EMAIL_MAX_WORDS {
+        return Some(Mail::Links(
+            links.into_iter().take(MAX_LINKS_PER_EMAIL).collect(),
+        ));
+    }
+    if words == 0 {
+        return None;
+    }
+
+    // Plain text bodies come back converted to HTML
+    let html = message.body_html(0)?;
+    let body = if message
+        .html_part(0)
+        .is_some_and(|part| part.is_content_type("text", "html"))
+    {
+        html_body(&html)
+    } else {
+        html.into_owned()
+    };
+
+    let title = message
+        .subject()
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            let from = message.from()?.first()?;
+            Some(format!("Email from {}", from.name().or(from.address())?))
+        })
+        .unwrap_or_else(|| "Email".to_string());
+
+    Some(Mail::Newsletter {
+        url: message_url(message),
+        title,
+        body,
+    })
+}
+
+/// The distinct web links in a text, in order, and the number of other words.
+fn links_and_words(text: &str) -> (Vec<String>, usize) {
+    let mut links: Vec<String> = Vec::new();
+    let mut words = 0;
+    for word in text.split_whitespace() {
+        let Some(start) = word.find("http://").or_else(|| word.find("https://")) else {
+            words += 1;
+            continue;
+        };
+        let link = word[start..]
+            .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '>', '"', '\'']);
+        if let Ok(url) = Url::parse(link)
+            && url.host().is_some()
+        {
+            let link = url.to_string();
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+    }
+    (links, words)
+}
+
+/// The content of an HTML email's `<body>`, without its head and styles.
+fn html_body(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("body").unwrap();
+    document
+        .select(&selector)
+        .next()
+        .map(|body| body.inner_html().trim().to_string())
+        .unwrap_or_else(|| html.to_string())
+}
+
+/// A `mid:` URL (RFC 2392) for the message, from its Message-ID or else a hash of its content.
+fn message_url(message: &Message) -> String {
+    let id = match message.message_id() {
+        Some(id) => id.to_string(),
+        None => format!("{:x}@laterfeed", Sha256::digest(message.raw_message())),
+    };
+    format!("mid:{}", utf8_percent_encode(&id, NON_ALPHANUMERIC))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(raw: &str) -> Option<Mail> {
+        read_mail(&MessageParser::default().parse(raw.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn read_mail_takes_links_from_short_emails() {
+        let mail = read(
+            "From: Me <me@example.com>\r\n\
+             Subject: Fwd: read later\r\n\
+             \r\n\
+             Have a look at https://example.com/a, and\r\n\
+             <https://example.com/b>. Also https://example.com/a again.\r\n",
+        );
+
+        assert_eq!(
+            mail,
+            Some(Mail::Links(vec![
+                "https://example.com/a".to_string(),
+                "https://example.com/b".to_string(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn read_mail_saves_newsletters_whole() {
+        let words = "word ".repeat(60);
+        let mail = read(&format!(
+            "From: News <news@example.com>\r\n\
+             Subject: Weekly =?utf-8?q?caf=C3=A9?=\r\n\
+             Message-ID: <abc.123@example.com>\r\n\
+             MIME-Version: 1.0\r\n\
+             Content-Type: text/html; charset=utf-8\r\n\
+             \r\n\
+             <html><head><style>p {{}}</style></head><body><p>{words}</p>\
+             <a href=\"https://example.com/more\">More</a></body></html>\r\n"
+        ));
+
+        assert_eq!(
+            mail,
+            Some(Mail::Newsletter {
+                url: "mid:abc%2E123%40example%2Ecom".to_string(),
+                title: "Weekly café".to_string(),
+                body: format!("<p>{words}</p><a href=\"https://example.com/more\">More</a>"),
+            })
+        );
+    }
+
+    #[test]
+    fn read_mail_ignores_empty_emails() {
+        assert_eq!(read("Subject: Empty\r\n\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn senders_match_addresses_and_domains() {
+        let senders = Senders::parse("Me@Example.com, @work.org,");
+
+        assert!(senders.allows("me@example.com"));
+        assert!(senders.allows("someone@WORK.org"));
+        assert!(!senders.allows("other@example.com"));
+        assert!(!senders.allows("me@network.org"));
+        assert!(!senders.allows(""));
+    }
+
+    #[test]
+    fn carries_token_checks_the_plus_tag() {
+        assert!(carries_token("save+s3cret@example.com", "s3cret"));
+        assert!(!carries_token("save@example.com", "s3cret"));
+        assert!(!carries_token("save+other@example.com", "s3cret"));
+        assert!(!carries_token("save@s3cret", "s3cret"));
+    }
+
+    #[tokio::test]
+    async fn start_smtp_server_returns_none_when_the_port_is_taken() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let state = crate::init_state(crate::config::Config {
+            database_url: "sqlite::memory:".to_string(),
+            smtp_port: Some(taken.local_addr().unwrap().port()),
+            ..Default::default()
+        })
+        .await;
+
+        assert_eq!(start_smtp_server(state).await, None);
+    }
+}
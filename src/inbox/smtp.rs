@@ -0,0 +1,344 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Longest command line accepted, well above the 512 bytes RFC 5321 requires.
+const MAX_LINE_BYTES: u64 = 4096;
+
+/// Recipients accepted per message.
+const MAX_RECIPIENTS: usize = 100;
+
+/// How long a client may stay silent before the connection is dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A message received over SMTP, as sent by `MAIL FROM` and `DATA`.
+#[derive(Debug, PartialEq)]
+pub struct Envelope {
+    pub sender: String,
+    pub data: Vec<u8>,
+}
+
+/// Limits and checks applied to a session.
+pub struct Policy<'a> {
+    /// Name the server greets with.
+    pub hostname: &'a str,
+    pub max_message_bytes: usize,
+    /// Whether mail from this envelope sender is accepted.
+    pub allows_sender: &'a (dyn Fn(&str) -> bool + Sync),
+    /// Whether mail to this recipient is accepted.
+    pub allows_recipient: &'a (dyn Fn(&str) -> bool + Sync),
+}
+
+type SessionResult<T> = Result<T, std::io::Error>;
+
+/// Run one SMTP session, handing every accepted message to `deliver`. Only what a mail client
+/// or forwarding server needs is supported: no TLS, no authentication and no relaying, since
+/// every message is for us.
+pub async fn session<S>(
+    stream: S,
+    policy: &Policy<'_>,
+    mut deliver: impl FnMut(Envelope),
+) -> SessionResult<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+
+    reply(
+        &mut writer,
+        &format!("220 {} ESMTP laterfeed", policy.hostname),
+    )
+    .await?;
+
+    let mut sender: Option<String> = None;
+    let mut recipients = 0;
+    loop {
+        let Some(line) = read_line(&mut reader).await? else {
+            return Ok(());
+        };
+        let Ok(line) = String::from_utf8(line) else {
+            reply(&mut writer, "500 5.5.2 Invalid command").await?;
+            continue;
+        };
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (verb, args) = line.split_once(' ').unwrap_or((line, ""));
+
+        match verb.to_ascii_uppercase().as_str() {
+            "HELO" => reply(&mut writer, &format!("250 {}", policy.hostname)).await?,
+            "EHLO" => {
+                let greeting = format!(
+                    "250-{}\r\n250-8BITMIME\r\n250-SMTPUTF8\r\n250 SIZE {}",
+                    policy.hostname, policy.max_message_bytes
+                );
+                reply(&mut writer, &greeting).await?;
+            }
+            "MAIL" => {
+                let Some(address) = path_argument(args, "FROM:") else {
+                    reply(&mut writer, "501 5.5.4 Syntax: MAIL FROM:<address>").await?;
+                    continue;
+                };
+                if !(policy.allows_sender)(&address) {
+                    reply(&mut writer, "550 5.7.1 Sender not allowed").await?;
+                    continue;
+                }
+                sender = Some(address);
+                recipients = 0;
+                reply(&mut writer, "250 2.1.0 OK").await?;
+            }
+            "RCPT" => {
+                if sender.is_none() {
+                    reply(&mut writer, "503 5.5.1 MAIL first").await?;
+                    continue;
+                }
+                let Some(address) = path_argument(args, "TO:") else {
+                    reply(&mut writer, "501 5.5.4 Syntax: RCPT TO:<address>").await?;
+                    continue;
+                };
+                if !(policy.allows_recipient)(&address) {
+                    reply(&mut writer, "550 5.7.1 Recipient not allowed").await?;
+                } else if recipients >= MAX_RECIPIENTS {
+                    reply(&mut writer, "452 4.5.3 Too many recipients").await?;
+                } else {
+                    recipients += 1;
+                    reply(&mut writer, "250 2.1.5 OK").await?;
+                }
+            }
+            "DATA" => {
+                if recipients == 0 {
+                    reply(&mut writer, "503 5.5.1 RCPT first").await?;
+                    continue;
+                }
+                reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+
+                let Some(data) = read_data(&mut reader, policy.max_message_bytes).await? else {
+                    reply(&mut writer, "552 5.3.4 Message too big").await?;
+                    (sender, recipients) = (None, 0);
+                    continue;
+                };
+                deliver(Envelope {
+                    sender: sender.take().unwrap_or_default(),
+                    data,
+                });
+                recipients = 0;
+                reply(&mut writer, "250 2.0.0 OK").await?;
+            }
+            "RSET" => {
+                (sender, recipients) = (None, 0);
+                reply(&mut writer, "250 2.0.0 OK").await?;
+            }
+            "NOOP" => reply(&mut writer, "250 2.0.0 OK").await?,
+            "VRFY" => reply(&mut writer, "252 2.5.0 Cannot verify").await?,
+            "QUIT" => {
+                reply(&mut writer, "221 2.0.0 Bye").await?;
+                return Ok(());
+            }
+            _ => reply(&mut writer, "502 5.5.1 Command not implemented").await?,
+        }
+    }
+}
+
+async fn reply(writer: &mut (impl AsyncWrite + Unpin), line: &str) -> SessionResult<()> {
+    writer.write_all(format!("{line}\r\n").as_bytes()).await?;
+    writer.flush().await
+}
+
+/// Read a line, including its ending. `None` once the client is gone or idle for too long.
+async fn read_line(reader: &mut (impl AsyncBufReadExt + Unpin)) -> SessionResult<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    let read = tokio::time::timeout(
+        IDLE_TIMEOUT,
+        (&mut *reader)
+            .take(MAX_LINE_BYTES)
+            .read_until(b'\n', &mut line),
+    )
+    .await;
+
+    match read {
+        Ok(Ok(0)) | Err(_) => Ok(None),
+        Ok(Ok(_)) if !line.ends_with(b"\n") => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "line too long",
+        )),
+        Ok(Ok(_)) => Ok(Some(line)),
+        Ok(Err(e)) => Err(e),
+    }
+}
+
+/// Read a message up to the line holding a single `.`, undoing dot-stuffing. The whole message
+/// is always read so the session can go on, but `None` is returned if it was too big.
+async fn read_data(
+    reader: &mut (impl AsyncBufReadExt + Unpin),
+    max_bytes: usize,
+) -> SessionResult<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    let mut too_big = false;
+    loop {
+        let Some(line) = read_line(reader).await? else {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        };
+        if line == b".\r\n" || line == b".\n" {
+            return Ok((!too_big).then_some(data));
+        }
+
+        let line = line.strip_prefix(b".").unwrap_or(&line);
+        if data.len() + line.len() > max_bytes {
+            too_big = true;
+            data.clear();
+        } else if !too_big {
+            data.extend_from_slice(line);
+        }
+    }
+}
+
+/// The address of a `FROM:<address>` or `TO:<address>` argument, ignoring any parameters after
+/// it. The null sender `<>` gives an empty address.
+fn path_argument(args: &str, prefix: &str) -> Option<String> {
+    let args = args.trim_start();
+    if !args
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    {
+        return None;
+    }
+
+    let path = args[prefix.len()..].trim_start();
+    let path = path.split_whitespace().next().unwrap_or_default();
+    let address = path.strip_prefix('<')?.strip_suffix('>')?;
+    Some(address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(input: &str, allowed: &str) -> (String, Vec<Envelope>) {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let allows = |sender: &str| sender == allowed;
+        let allows_recipient = |recipient: &str| recipient != "other@laterfeed";
+        let policy = Policy {
+            hostname: "test",
+            max_message_bytes: 1024,
+            allows_sender: &allows,
+            allows_recipient: &allows_recipient,
+        };
+
+        let (mut client_reader, mut client_writer) = tokio::io::split(client);
+        client_writer.write_all(input.as_bytes()).await.unwrap();
+
+        let mut messages = Vec::new();
+        session(server, &policy, |envelope| messages.push(envelope))
+            .await
+            .unwrap();
+        drop(client_writer);
+
+        let mut output = String::new();
+        client_reader.read_to_string(&mut output).await.unwrap();
+        (output, messages)
+    }
+
+    fn codes(output: &str) -> Vec<&str> {
+        output
+            .lines()
+            .filter(|line| line.as_bytes().get(3) == Some(&b' '))
+            .map(|line| &line[..3])
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn session_receives_message() {
+        let (output, messages) = run(
+            "EHLO client\r\n\
+             MAIL FROM:<me@example.com> SIZE=100\r\n\
+             RCPT TO:<save@laterfeed>\r\n\
+             DATA\r\n\
+             Subject: Hi\r\n\
+             \r\n\
+             ..leading dot\r\n\
+             .\r\n\
+             QUIT\r\n",
+            "me@example.com",
+        )
+        .await;
+
+        assert_eq!(
+            codes(&output),
+            vec!["220", "250", "250", "250", "354", "250", "221"]
+        );
+        assert_eq!(
+            messages,
+            vec![Envelope {
+                sender: "me@example.com".to_string(),
+                data: b"Subject: Hi\r\n\r\n.leading dot\r\n".to_vec(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn session_rejects_unknown_senders_and_bad_order() {
+        let (output, messages) = run(
+            "HELO client\r\n\
+             RCPT TO:<save@laterfeed>\r\n\
+             MAIL FROM:<spam@example.org>\r\n\
+             DATA\r\n\
+             STARTTLS\r\n\
+             QUIT\r\n",
+            "me@example.com",
+        )
+        .await;
+
+        assert_eq!(
+            codes(&output),
+            vec!["220", "250", "503", "550", "503", "502", "221"]
+        );
+        assert!(messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_refuses_unknown_recipients() {
+        let (output, messages) = run(
+            "MAIL FROM:<me@example.com>\r\n\
+             RCPT TO:<other@laterfeed>\r\n\
+             DATA\r\n\
+             QUIT\r\n",
+            "me@example.com",
+        )
+        .await;
+
+        assert_eq!(codes(&output), vec!["220", "250", "550", "503", "221"]);
+        assert!(messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_refuses_oversized_messages() {
+        let body = "x".repeat(2000);
+        let (output, messages) = run(
+            &format!(
+                "MAIL FROM:<me@example.com>\r\nRCPT TO:<a@b>\r\nDATA\r\n{body}\r\n.\r\nQUIT\r\n"
+            ),
+            "me@example.com",
+        )
+        .await;
+
+        assert_eq!(
+            codes(&output),
+            vec!["220", "250", "250", "354", "552", "221"]
+        );
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn path_argument_reads_addresses() {
+        assert_eq!(
+            path_argument("FROM:<a@b.c> BODY=8BITMIME", "FROM:").as_deref(),
+            Some("a@b.c")
+        );
+        assert_eq!(
+            path_argument("from: <a@b.c>", "FROM:").as_deref(),
+            Some("a@b.c")
+        );
+        assert_eq!(path_argument("FROM:<>", "FROM:").as_deref(), Some(""));
+        assert_eq!(path_argument("TO:<a@b.c>", "FROM:"), None);
+        assert_eq!(path_argument("FROM:a@b.c", "FROM:"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use axum::{middleware, routing::get};
+use axum::{extract::DefaultBodyLimit, middleware, routing::get};
 use sqlx::migrate::MigrateDatabase;
 use sqlx::{Sqlite, SqlitePool, migrate, sqlite::SqlitePoolOptions};
 use tower_http::trace::TraceLayer;
@@ -14,15 +14,25 @@ use utoipa_scalar::{Scalar, Servable};
 
 use crate::config::Config;
 
+mod archive;
 mod auth;
 pub mod cleanup;
 pub mod config;
 mod dto;
 mod errors;
+mod events;
+mod export;
 mod feed;
 mod handlers;
+pub mod import;
+mod inbox;
+mod link_check;
+mod media;
 mod metadata;
 mod models;
+mod quick_save;
+mod save;
+mod webhooks;
 
 pub const COMMON_TAG: &str = "Common";
 pub const FEED_TAG: &str = "Feed";
@@ -56,9 +66,17 @@ pub type AppState = Arc<AppStateInner>;
 pub struct AppStateInner {
     pub config: Config,
     pub pool: SqlitePool,
+    pub fetcher: metadata::Fetcher,
+    pub imports: import::ImportJobs,
+    pub events: events::EventLog,
+    pub webhooks: webhooks::Queue,
 }
 
-pub async fn app(config: Config) -> (axum::Router, utoipa::openapi::OpenApi, SqlitePool) {
+/// Largest export file accepted by `POST /imports` (64 MiB).
+const MAX_IMPORT_BYTES: usize = 64 * 1024 * 1024;
+
+/// Connect to the database, creating and migrating it as needed, and set up the shared state.
+pub async fn init_state(config: Config) -> AppState {
     if !Sqlite::database_exists(&config.database_url)
         .await
         .unwrap_or(false)
@@ -80,14 +98,51 @@ pub async fn app(config: Config) -> (axum::Router, utoipa::openapi::OpenApi, Sql
         .await
         .expect("failed to migrate database");
 
-    let app_state = AppState::new(AppStateInner {
+    AppState::new(AppStateInner {
+        fetcher: metadata::Fetcher::new(&config).expect("invalid fetch configuration"),
+        webhooks: webhooks::Queue::new(&config),
         config,
-        pool: pool.clone(),
-    });
+        pool,
+        imports: import::ImportJobs::default(),
+        events: events::EventLog::default(),
+    })
+}
+
+/// Set up the shared state and build the router on it. Background tasks aren't started, see
+/// `start_background_tasks`.
+pub async fn app(config: Config) -> (axum::Router, utoipa::openapi::OpenApi, SqlitePool) {
+    let app_state = init_state(config).await;
+    let (router, api) = router(app_state.clone());
+    (router, api, app_state.pool.clone())
+}
+
+/// Start the retention cleanup, the dead-link checker, the SMTP inbox and webhook delivery,
+/// each only if configured.
+pub async fn start_background_tasks(state: &AppState) {
+    cleanup::start_cleanup_task(state.clone());
+    link_check::start_link_check_task(state.clone());
+    inbox::start_smtp_server(state.clone()).await;
+    webhooks::start_delivery_task(state.clone());
+}
 
+/// Build the router and the API documentation on the shared state.
+pub fn router(app_state: AppState) -> (axum::Router, utoipa::openapi::OpenApi) {
     let authenticated_routes = OpenApiRouter::new()
         .routes(routes!(handlers::add_entry))
+        .routes(routes!(handlers::add_entries))
         .routes(routes!(handlers::delete_entry))
+        .routes(routes!(handlers::list_trash, handlers::purge_trash))
+        .routes(routes!(handlers::restore_entry))
+        .routes(routes!(handlers::get_import))
+        .routes(routes!(handlers::export_entries))
+        .routes(routes!(handlers::create_webhook, handlers::list_webhooks))
+        .routes(routes!(handlers::delete_webhook))
+        .routes(routes!(handlers::list_webhook_deliveries))
+        .merge(
+            OpenApiRouter::new()
+                .routes(routes!(handlers::start_import))
+                .layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             auth::auth_guard,
@@ -97,12 +152,28 @@ pub async fn app(config: Config) -> (axum::Router, utoipa::openapi::OpenApi, Sql
         .route("/health", get(handlers::health))
         .routes(routes!(handlers::get_feed))
         .routes(routes!(handlers::list_entries))
+        .routes(routes!(handlers::stream_events))
+        .routes(routes!(handlers::get_entry))
+        .routes(routes!(handlers::lookup_entry))
+        .routes(routes!(handlers::quick_save, handlers::quick_save_form))
+        .routes(routes!(handlers::get_entry_archive))
+        .routes(routes!(handlers::get_media))
         .merge(authenticated_routes)
         .with_state(app_state)
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &axum::extract::Request| {
+                // Like the default span, but without the quick save token
+                tracing::debug_span!(
+                    "request",
+                    method = %request.method(),
+                    uri = %quick_save::redact_token(request.uri()),
+                    version = ?request.version(),
+                )
+            }),
+        )
         .split_for_parts();
 
     let router = router.merge(Scalar::with_url("/docs", api.clone()));
 
-    (router, api, pool)
+    (router, api)
 }
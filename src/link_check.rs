@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{debug, error, info};
+use url::Url;
+
+use crate::AppState;
+use crate::metadata::{FetchResult, LinkCheck};
+use crate::models::Entry;
+
+/// How often the checker looks for links that are due.
+const LINK_CHECK_TICK_SECS: u64 = 600; // 10 minutes
+
+/// Links checked per tick. Any others that are due wait for the next tick.
+const LINK_CHECK_BATCH_SIZE: i64 = 20;
+
+/// Pause between two checks, on top of the per-host fetch limits.
+const LINK_CHECK_PAUSE: Duration = Duration::from_secs(5);
+
+/// Starts the background dead-link checker if `LINK_CHECK_INTERVAL_HOURS` is set.
+///
+/// Saved links are re-checked once per interval, a few at a time, recording the status they
+/// answer with and whether they appear dead.
+pub fn start_link_check_task(state: AppState) {
+    let Some(hours) = state.config.link_check_interval_hours.filter(|&h| h > 0) else {
+        info!("no link check interval configured, dead-link checker disabled");
+        return;
+    };
+
+    info!(
+        interval_hours = hours,
+        "starting background dead-link checker"
+    );
+
+    tokio::spawn(async move {
+        let recheck_after = chrono::Duration::hours(i64::try_from(hours).unwrap_or(i64::MAX));
+        let mut interval = tokio::time::interval(Duration::from_secs(LINK_CHECK_TICK_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let checked_before = Utc::now() - recheck_after;
+            let due = match Entry::fetch_due_for_link_check(
+                &state.pool,
+                checked_before,
+                LINK_CHECK_BATCH_SIZE,
+            )
+            .await
+            {
+                Ok(due) => due,
+                Err(e) => {
+                    error!(error = %e, "failed to load links to check");
+                    continue;
+                }
+            };
+
+            for (id, url) in due {
+                let result = match Url::parse(&url) {
+                    Ok(url) => state.fetcher.check_link(&url).await,
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(ref e) = result {
+                    debug!("Could not check {}: {}", url, e);
+                }
+
+                let (http_status, dead) = verdict(&result);
+                if dead == Some(true) {
+                    info!(id, http_status, "link appears dead: {}", url);
+                }
+                if let Err(e) =
+                    Entry::record_link_check(&state.pool, id, Utc::now(), http_status, dead).await
+                {
+                    error!(error = %e, id, "failed to record link check");
+                }
+
+                tokio::time::sleep(LINK_CHECK_PAUSE).await;
+            }
+        }
+    });
+}
+
+/// The status to record for a check and whether the link is dead. Errors that say nothing
+/// about the link itself (timeouts, blocked fetches, ...) leave the dead flag unchanged.
+fn verdict(result: &FetchResult<LinkCheck>) -> (Option<i64>, Option<bool>) {
+    match result {
+        Ok(LinkCheck::Status(status)) => {
+            (Some(i64::from(*status)), Some(matches!(status, 404 | 410)))
+        }
+        Ok(LinkCheck::Unresolvable) => (None, Some(true)),
+        Err(_) => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdict_marks_gone_and_unresolvable_links_dead() {
+        assert_eq!(
+            verdict(&Ok(LinkCheck::Status(200))),
+            (Some(200), Some(false))
+        );
+        assert_eq!(
+            verdict(&Ok(LinkCheck::Status(500))),
+            (Some(500), Some(false))
+        );
+        assert_eq!(
+            verdict(&Ok(LinkCheck::Status(404))),
+            (Some(404), Some(true))
+        );
+        assert_eq!(
+            verdict(&Ok(LinkCheck::Status(410))),
+            (Some(410), Some(true))
+        );
+        assert_eq!(verdict(&Ok(LinkCheck::Unresolvable)), (None, Some(true)));
+        assert_eq!(verdict(&Err("timed out".into())), (None, None));
+    }
+}
@@ -3,8 +3,8 @@ use tokio::signal;
 use tracing::info;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
-use laterfeed::{app, cleanup};
 use laterfeed::config::Config;
+use laterfeed::{import, init_state, router, start_background_tasks};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = envy::from_env::<Config>()?;
@@ -18,27 +18,39 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .init();
 
-    let retention_days = config.retention_days;
-    let max_entries = config.max_entries;
-
-    tokio::runtime::Builder::new_multi_thread()
+    let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
-        .build()?
-        .block_on(async {
-            let (router, _, pool) = app(config).await;
+        .build()?;
+
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None | Some("serve") => {}
+        Some("import") => return runtime.block_on(import::run_cli(config, args)),
+        Some(command) => return Err(format!("unknown command `{command}`").into()),
+    }
 
-            cleanup::start_cleanup_task(pool.clone(), retention_days, max_entries);
+    runtime.block_on(async {
+        let state = init_state(config).await;
+        let (router, _) = router(state.clone());
+        start_background_tasks(&state).await;
 
-            let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await.unwrap();
-            info!("listening on {}", listener.local_addr().unwrap());
-            axum::serve(listener, router)
-                .with_graceful_shutdown(shutdown_signal())
-                .await
-                .unwrap();
+        let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await.unwrap();
+        info!("listening on {}", listener.local_addr().unwrap());
+        axum::serve(listener, router)
+            .with_graceful_shutdown({
+                let state = state.clone();
+                async move {
+                    shutdown_signal().await;
+                    // Event streams never end by themselves
+                    state.events.close();
+                }
+            })
+            .await
+            .unwrap();
 
-            info!("shutting down, closing database connection pool");
-            pool.close().await;
-        });
+        info!("shutting down, closing database connection pool");
+        state.pool.close().await;
+    });
 
     Ok(())
 }
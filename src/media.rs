@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use lol_html::{RewriteStrSettings, element, rewrite_str};
+use scraper::{Html, Selector};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::AppState;
+use crate::events;
+use crate::metadata::{FetchResult, Fetcher};
+use crate::models::Entry;
+
+/// Directory under the data directory that cached images are stored in.
+const MEDIA_SUBDIR: &str = "media";
+
+/// Default largest image that is cached (5 MiB). Larger ones stay hotlinked.
+const DEFAULT_MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Images cached per entry. Anything beyond stays hotlinked.
+const MAX_IMAGES_PER_ENTRY: usize = 50;
+
+/// Path prefix that cached images are served under.
+const MEDIA_PATH: &str = "/media/";
+
+/// How long a cached image is kept before it can be removed as an orphan. Images are stored
+/// before the body that refers to them is saved, so newer files may not be referenced yet.
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Directory that cached images are stored in.
+pub fn media_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(MEDIA_SUBDIR)
+}
+
+/// Whether a string is a media hash (a lowercase hex SHA-256 digest), and so safe to use as a
+/// file name.
+pub fn is_media_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// The MIME type of an image we are willing to cache and serve, detected from its contents.
+/// SVG is deliberately left out since it can carry scripts.
+pub fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some("image/webp")
+    } else if matches!(bytes.get(4..12), Some(b"ftypavif" | b"ftypavis")) {
+        Some("image/avif")
+    } else {
+        None
+    }
+}
+
+/// Cache the images in an entry's body in the background and point the body at the copies.
+/// Failures are logged; images that couldn't be cached stay hotlinked.
+pub async fn cache_entry_images(state: AppState, id: i64, page_url: String, body: String) {
+    let max_bytes = state
+        .config
+        .max_image_bytes
+        .filter(|&max| max > 0)
+        .unwrap_or(DEFAULT_MAX_IMAGE_BYTES);
+    let Ok(page_url) = Url::parse(&page_url) else {
+        return;
+    };
+
+    let data_dir = state.config.data_dir();
+    let cached = cache_images(
+        &state.fetcher,
+        &data_dir,
+        &body,
+        &page_url,
+        &state.config.base_url,
+        max_bytes,
+    )
+    .await;
+    if cached.is_empty() {
+        return;
+    }
+
+    let replacements: HashMap<String, String> = cached
+        .into_iter()
+        .map(|(src, hash)| (src, media_url(&state.config.base_url, &hash)))
+        .collect();
+
+    let result = match rewrite_images(&body, &replacements) {
+        Ok(rewritten) => Entry::set_body(&state.pool, id, &rewritten)
+            .await
+            .map_err(Into::into),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(true) => {
+            info!(
+                id,
+                count = replacements.len(),
+                "cached images of {}",
+                page_url
+            );
+            events::publish_updated(&state, id).await;
+        }
+        Ok(false) => {}
+        Err(e) => warn!("Failed to rewrite images of {}: {}", page_url, e),
+    }
+}
+
+fn media_url(base_url: &str, hash: &str) -> String {
+    format!("{}{MEDIA_PATH}{hash}", base_url.trim_end_matches('/'))
+}
+
+/// Download and store the images in a body. Returns the hash of each cached image, keyed by
+/// its `src` attribute as written in the body.
+async fn cache_images(
+    fetcher: &Fetcher,
+    data_dir: &Path,
+    body: &str,
+    page_url: &Url,
+    base_url: &str,
+    max_bytes: usize,
+) -> HashMap<String, String> {
+    let mut cached = HashMap::new();
+
+    for src in image_sources(body, base_url)
+        .into_iter()
+        .take(MAX_IMAGES_PER_ENTRY)
+    {
+        let Some(url) = page_url
+            .join(&src)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+        else {
+            continue;
+        };
+
+        match cache_image(fetcher, data_dir, &url, max_bytes).await {
+            Ok(hash) => {
+                cached.insert(src, hash);
+            }
+            Err(e) => debug!("Not caching image {}: {}", url, e),
+        }
+    }
+
+    cached
+}
+
+/// Download an image and store it under its SHA-256 hash, returning the hash.
+async fn cache_image(
+    fetcher: &Fetcher,
+    data_dir: &Path,
+    url: &Url,
+    max_bytes: usize,
+) -> FetchResult<String> {
+    let (bytes, _) = fetcher.fetch_bytes_limited(url, max_bytes).await?;
+    if sniff_image_type(&bytes).is_none() {
+        return Err("not a supported image type".into());
+    }
+
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let dir = media_dir(data_dir);
+    let path = dir.join(&hash);
+
+    if tokio::fs::try_exists(&path).await? {
+        // Keep it from being removed as an orphan before the new body refers to it
+        let file = tokio::fs::File::options().write(true).open(&path).await?;
+        file.into_std().await.set_modified(SystemTime::now())?;
+    } else {
+        let partial = dir.join(format!("{hash}.part"));
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(&partial, &bytes).await?;
+        tokio::fs::rename(&partial, &path).await?;
+    }
+
+    Ok(hash)
+}
+
+/// The distinct `src` attributes of the images in a body, in document order, leaving out
+/// those already served from our `/media`.
+fn image_sources(body: &str, base_url: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(body);
+    let selector = Selector::parse("img[src]").unwrap();
+    let cached = media_url(base_url, "");
+    let mut seen = HashSet::new();
+
+    fragment
+        .select(&selector)
+        .filter_map(|el| el.value().attr("src"))
+        .map(|src| src.trim().to_string())
+        .filter(|src| !src.is_empty() && !src.starts_with("data:") && !src.starts_with(&cached))
+        .filter(|src| seen.insert(src.clone()))
+        .collect()
+}
+
+/// Point `img` elements at their cached copies. `srcset` is dropped from rewritten images so
+/// readers don't pick a hotlinked variant instead.
+fn rewrite_images(body: &str, replacements: &HashMap<String, String>) -> FetchResult<String> {
+    Ok(rewrite_str(
+        body,
+        RewriteStrSettings::new().append_element_content_handler(element!("img[src]", |el| {
+            let src = el.get_attribute("src").unwrap_or_default();
+            if let Some(replacement) = replacements.get(src.trim()) {
+                el.set_attribute("src", replacement)?;
+                el.remove_attribute("srcset");
+            }
+            Ok(())
+        })),
+    )?)
+}
+
+/// Delete cached images that no entry body refers to anymore, except those stored within
+/// [`ORPHAN_GRACE_PERIOD`]. Returns the number of files removed.
+pub async fn remove_orphans(
+    pool: &SqlitePool,
+    data_dir: &Path,
+    base_url: &str,
+) -> std::io::Result<u64> {
+    let dir = media_dir(data_dir);
+    let mut files = match tokio::fs::read_dir(&dir).await {
+        Ok(files) => files,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let bodies = Entry::bodies_with_media(pool)
+        .await
+        .map_err(std::io::Error::other)?;
+    let prefix = media_url(base_url, "");
+    let referenced: HashSet<&str> = bodies
+        .iter()
+        .flat_map(|body| media_hashes(body, &prefix))
+        .collect();
+
+    let mut removed = 0;
+    while let Some(file) = files.next_entry().await? {
+        let name = file.file_name();
+        let name = name.to_string_lossy();
+        if !is_media_hash(&name) || referenced.contains(name.as_ref()) {
+            continue;
+        }
+        let modified = file.metadata().await?.modified()?;
+        if modified.elapsed().unwrap_or_default() >= ORPHAN_GRACE_PERIOD {
+            tokio::fs::remove_file(file.path()).await?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// The media hashes referenced in a body, as URLs starting with `prefix`.
+fn media_hashes<'a>(body: &'a str, prefix: &'a str) -> impl Iterator<Item = &'a str> {
+    body.match_indices(prefix).filter_map(|(start, _)| {
+        let hash = body.get(start + prefix.len()..start + prefix.len() + 64)?;
+        is_media_hash(hash).then_some(hash)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn is_media_hash_rejects_paths() {
+        assert!(is_media_hash(HASH));
+        assert!(!is_media_hash("../../etc/passwd"));
+        assert!(!is_media_hash(&HASH.to_uppercase()));
+        assert!(!is_media_hash(&HASH[1..]));
+    }
+
+    #[test]
+    fn sniff_image_type_detects_supported_formats() {
+        assert_eq!(
+            sniff_image_type(b"\x89PNG\r\n\x1a\n\0\0"),
+            Some("image/png")
+        );
+        assert_eq!(sniff_image_type(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(sniff_image_type(b"GIF89a"), Some("image/gif"));
+        assert_eq!(
+            sniff_image_type(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(sniff_image_type(b"\0\0\0\x20ftypavif"), Some("image/avif"));
+        assert_eq!(sniff_image_type(b"<svg xmlns="), None);
+        assert_eq!(sniff_image_type(b"<html>"), None);
+    }
+
+    #[test]
+    fn image_sources_skips_data_uris_and_cached_images() {
+        let body = format!(
+            r#"<p><img src="https://cdn.example.com/a.png"><img src=" /b.jpg "></p>
+            <img src="https://cdn.example.com/a.png"><img src="data:image/png;base64,AA">
+            <img src="http://localhost:8000/media/{HASH}">
+            <img src="https://cdn.example.com/media/2024/x.jpg">"#
+        );
+
+        assert_eq!(
+            image_sources(&body, "http://localhost:8000/"),
+            vec![
+                "https://cdn.example.com/a.png",
+                "/b.jpg",
+                "https://cdn.example.com/media/2024/x.jpg"
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrite_images_replaces_cached_sources() {
+        let body = r#"<p><img src="/a.png" srcset="/a@2x.png 2x" alt="A"><img src="/b.png"></p>"#;
+        let replacements = HashMap::from([(
+            "/a.png".to_string(),
+            media_url("http://localhost:8000/", HASH),
+        )]);
+
+        assert_eq!(
+            rewrite_images(body, &replacements).unwrap(),
+            format!(
+                r#"<p><img src="http://localhost:8000/media/{HASH}" alt="A"><img src="/b.png"></p>"#
+            )
+        );
+    }
+
+    #[test]
+    fn media_hashes_finds_references() {
+        let body = format!(
+            r#"<img src="http://localhost/media/{HASH}"><a href="/media/not-a-hash">x</a>
+            <img src="https://cdn.example.com/media/{}">"#,
+            "b".repeat(64)
+        );
+        assert_eq!(
+            media_hashes(&body, "http://localhost/media/").collect::<Vec<_>>(),
+            vec![HASH]
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_orphans_keeps_recent_files() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let data_dir = std::env::temp_dir().join(format!("laterfeed-media-{}", std::process::id()));
+        let dir = media_dir(&data_dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let old = "a".repeat(64);
+        std::fs::write(dir.join(&old), b"GIF89a").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join(&old))
+            .unwrap()
+            .set_modified(SystemTime::now() - ORPHAN_GRACE_PERIOD * 2)
+            .unwrap();
+        std::fs::write(dir.join(HASH), b"GIF89a").unwrap();
+
+        assert_eq!(
+            remove_orphans(&pool, &data_dir, "http://localhost")
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(!dir.join(&old).exists());
+        assert!(dir.join(HASH).exists());
+
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
@@ -1,434 +1,356 @@
-use std::time::Duration;
+use std::error::Error;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
-use scraper::{Html, Selector};
+use chrono::{DateTime, NaiveDate, Utc};
+use scraper::Html;
 use url::Url;
 
+use crate::metadata::extractors::ExtractorRegistry;
+use crate::metadata::guard::find_blocked;
+use crate::models::{Chapter, EntrySourceType, FetchOutcome, TranscriptSegment};
+
+mod extractors;
+mod fetch;
+mod guard;
+mod html;
+mod oembed;
+mod polite;
+mod resource;
+mod rules;
+
+pub use fetch::{CaptionSettings, Document, Fetcher, LinkCheck};
+pub use guard::{GuardedResolver, UrlGuard};
+
+pub type FetchResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+#[derive(Default)]
 pub struct PageMetadata {
     pub title: Option<String>,
     pub body: Option<String>,
+    /// Source type inferred from the page, if it gave any signal.
+    pub source_type: Option<EntrySourceType>,
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub site_name: Option<String>,
+    pub canonical_url: Option<String>,
+    /// Lead image (og:image / twitter:image), as an absolute URL.
+    pub image_url: Option<String>,
+    /// BCP 47 language tag, e.g. `en` or `pt-BR`.
+    pub language: Option<String>,
+    /// Number of words in the extracted body text.
+    pub word_count: Option<i64>,
+    /// Length of a video, in seconds.
+    pub duration_secs: Option<i64>,
+    /// Page of the author, e.g. a video's channel.
+    pub author_url: Option<String>,
+    /// Chapters of a video, in order.
+    pub chapters: Vec<Chapter>,
+    /// Transcript of a video, in order.
+    pub transcript: Vec<TranscriptSegment>,
+    /// Name of the per-host fetch rule applied when fetching the page.
+    pub fetch_rule: Option<String>,
+    /// URL the page was served from after redirects, when the page itself was downloaded.
+    pub final_url: Option<String>,
+    /// Whether the URL turned out not to be an HTML page.
+    pub non_html: bool,
 }
 
-#[derive(serde::Deserialize)]
-struct YouTubeOEmbed {
-    title: String,
+/// How fetching a page's metadata went.
+#[derive(Debug)]
+pub struct FetchReport {
+    pub outcome: FetchOutcome,
+    /// Status of the response, for [`FetchOutcome::HttpError`].
+    pub http_status: Option<u16>,
+    pub duration: Duration,
 }
 
-/// Fetch metadata (title and body content) from a URL by downloading and parsing the HTML.
-/// Uses YouTube oEmbed API for YouTube URLs to reliably extract video titles.
-/// Returns `None` values on any failure (network error, parse error, missing elements).
-pub async fn fetch_metadata(url: &str) -> PageMetadata {
-    if is_youtube_url(url) {
-        return fetch_youtube_metadata(url).await;
-    }
+/// Hosts whose pages are videos unless the page itself says otherwise.
+const VIDEO_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "vimeo.com",
+    "dailymotion.com",
+    "dai.ly",
+    "twitch.tv",
+    "tiktok.com",
+    "rumble.com",
+    "odysee.com",
+    "nebula.tv",
+];
+
+static REGISTRY: LazyLock<ExtractorRegistry> = LazyLock::new(ExtractorRegistry::builtin);
+
+/// Fetch metadata (title, body content and source type) from a URL.
+/// Dispatches to the first site-specific extractor that matches the URL, falling back to the
+/// generic HTML scraper when none matches or the site-specific extractor fails.
+/// Returns `None` values on any failure (network error, parse error, missing elements), along
+/// with a report of what went wrong.
+pub async fn fetch_metadata(fetcher: &Fetcher, url: &str) -> (PageMetadata, FetchReport) {
+    let started = Instant::now();
+    let Ok(parsed) = Url::parse(url) else {
+        tracing::warn!("Failed to fetch metadata from {}: invalid URL", url);
+        let report = FetchReport {
+            outcome: FetchOutcome::ParseError,
+            http_status: None,
+            duration: started.elapsed(),
+        };
+        return (PageMetadata::default(), report);
+    };
 
-    match fetch_metadata_inner(url).await {
-        Ok(meta) => meta,
+    let result = REGISTRY.extract(fetcher, &parsed).await;
+    let duration = started.elapsed();
+
+    let (mut meta, report) = match result {
+        Ok(mut meta) => {
+            meta.fetch_rule = fetcher.rule_name(&parsed).map(str::to_string);
+            let outcome = if meta.non_html {
+                FetchOutcome::NonHtml
+            } else {
+                FetchOutcome::Success
+            };
+            let report = FetchReport {
+                outcome,
+                http_status: None,
+                duration,
+            };
+            (meta, report)
+        }
         Err(e) => {
             tracing::warn!("Failed to fetch metadata from {}: {}", url, e);
-            PageMetadata {
-                title: None,
-                body: None,
-            }
+            let (outcome, http_status) = classify_error(e.as_ref());
+            let report = FetchReport {
+                outcome,
+                http_status,
+                duration,
+            };
+            (PageMetadata::default(), report)
         }
-    }
-}
-
-/// YouTube pages often return broken titles (e.g. "- YouTube") when scraped directly.
-/// Use the oEmbed API for the title and regular HTML scrape for body, in parallel.
-async fn fetch_youtube_metadata(url: &str) -> PageMetadata {
-    let (oembed_result, page_result) = tokio::join!(
-        fetch_youtube_oembed(url),
-        fetch_metadata_inner(url),
-    );
-
-    let (page_title, body) = match page_result {
-        Ok(meta) => (meta.title, meta.body),
-        Err(_) => (None, None),
     };
 
-    let title = match oembed_result {
-        Ok(oembed) => Some(oembed.title),
-        Err(e) => {
-            tracing::warn!("Failed to fetch YouTube oEmbed for {}: {}", url, e);
-            page_title
-        }
-    };
+    if meta.source_type.is_none() {
+        meta.source_type = source_type_from_host(&parsed);
+    }
+    if meta.word_count.is_none() {
+        meta.word_count = meta.body.as_deref().map(word_count);
+    }
 
-    PageMetadata { title, body }
+    (meta, report)
 }
 
-async fn fetch_youtube_oembed(url: &str) -> Result<YouTubeOEmbed, Box<dyn std::error::Error + Send + Sync>> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()?;
-
-    let mut oembed_url = Url::parse("https://www.youtube.com/oembed")?;
-    oembed_url.query_pairs_mut()
-        .append_pair("url", url)
-        .append_pair("format", "json");
+/// Work out why a fetch failed from its error chain, along with the HTTP status if the server
+/// answered with an error. Anything that isn't a network problem is taken as a failure to make
+/// sense of the response.
+fn classify_error(error: &(dyn Error + 'static)) -> (FetchOutcome, Option<u16>) {
+    if find_blocked(error).is_some() {
+        return (FetchOutcome::Blocked, None);
+    }
 
-    let response = client
-        .get(oembed_url.as_str())
-        .send()
-        .await?
-        .error_for_status()?;
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() {
+                return (FetchOutcome::Timeout, None);
+            }
+            if let Some(status) = e.status() {
+                return (FetchOutcome::HttpError, Some(status.as_u16()));
+            }
+            if e.is_connect() || e.is_request() || e.is_redirect() || e.is_body() {
+                return (FetchOutcome::NetworkError, None);
+            }
+        }
+        if error.downcast_ref::<std::io::Error>().is_some() {
+            return (FetchOutcome::NetworkError, None);
+        }
+        current = error.source();
+    }
 
-    let text = response.text().await?;
-    Ok(serde_json::from_str(&text)?)
+    (FetchOutcome::ParseError, None)
 }
 
-fn is_youtube_url(url_str: &str) -> bool {
-    let Ok(parsed) = Url::parse(url_str) else {
-        return false;
-    };
-
-    matches!(
-        parsed.host_str(),
-        Some(
-            "youtube.com"
-                | "www.youtube.com"
-                | "m.youtube.com"
-                | "music.youtube.com"
-                | "youtu.be"
-        )
-    )
+/// Infer the source type from the URL alone, for well-known video hosts.
+fn source_type_from_host(url: &Url) -> Option<EntrySourceType> {
+    let host = url.host_str()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    VIDEO_HOSTS
+        .iter()
+        .any(|video_host| {
+            host == *video_host
+                || host
+                    .strip_suffix(video_host)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+        .then_some(EntrySourceType::Video)
 }
 
-async fn fetch_metadata_inner(url: &str) -> Result<PageMetadata, Box<dyn std::error::Error + Send + Sync>> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()?;
-
-    let response = client
-        .get(url)
-        .header("User-Agent", "Laterfeed/1.0")
-        .send()
-        .await?
-        .error_for_status()?;
-
-    let html = response.text().await?;
-    let document = Html::parse_document(&html);
-
-    let title = extract_title(&document);
-    let body = extract_body(&document);
-
-    Ok(PageMetadata { title, body })
+/// Return the trimmed string if it contains anything other than whitespace.
+fn non_empty(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
 }
 
-fn extract_title(document: &Html) -> Option<String> {
-    // Try <meta property="og:title"> first, then fall back to <title>
-    let og_title_selector = Selector::parse(r#"meta[property="og:title"]"#).ok()?;
-    if let Some(element) = document.select(&og_title_selector).next()
-        && let Some(content) = element.value().attr("content")
-    {
-        let trimmed = content.trim();
-        if !trimmed.is_empty() {
-            return Some(trimmed.to_string());
-        }
-    }
-
-    let title_selector = Selector::parse("title").ok()?;
-    document
-        .select(&title_selector)
-        .next()
-        .map(|el| el.text().collect::<String>().trim().to_string())
-        .filter(|t| !t.is_empty())
+/// Count the words in an HTML fragment's text content.
+fn word_count(html: &str) -> i64 {
+    let fragment = Html::parse_fragment(html);
+    let count = fragment
+        .root_element()
+        .text()
+        .flat_map(str::split_whitespace)
+        .count();
+    i64::try_from(count).unwrap_or(i64::MAX)
 }
 
-/// Extract the page body content as HTML.
-/// Tries `<article>` first, then falls back to og:description / meta description.
-fn extract_body(document: &Html) -> Option<String> {
-    // Try to get the inner HTML of an <article> element
-    if let Ok(article_selector) = Selector::parse("article")
-        && let Some(article) = document.select(&article_selector).next()
-    {
-        let html = article.inner_html();
-        let trimmed = html.trim();
-        if !trimmed.is_empty() {
-            return Some(trimmed.to_string());
-        }
-    }
+/// Parse a publication date as found in meta tags and APIs: RFC 3339, or a bare date taken
+/// as midnight UTC.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
 
-    // Fall back to og:description
-    if let Ok(og_desc_selector) = Selector::parse(r#"meta[property="og:description"]"#)
-        && let Some(element) = document.select(&og_desc_selector).next()
-        && let Some(content) = element.value().attr("content")
-    {
-        let trimmed = content.trim();
-        if !trimmed.is_empty() {
-            return Some(trimmed.to_string());
-        }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
     }
 
-    // Fall back to <meta name="description">
-    if let Ok(desc_selector) = Selector::parse(r#"meta[name="description"]"#)
-        && let Some(element) = document.select(&desc_selector).next()
-        && let Some(content) = element.value().attr("content")
-    {
-        let trimmed = content.trim();
-        if !trimmed.is_empty() {
-            return Some(trimmed.to_string());
+    // ISO 8601 without the seconds or timezone, e.g. `2024-05-01T10:00`
+    let date = value.get(..10).unwrap_or(value);
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+}
+
+/// Escape text for inclusion in HTML content or attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
         }
     }
+    escaped
+}
 
-    None
+/// Convert plain text into HTML paragraphs, one per blank-line separated block.
+fn text_to_html(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("<p>{}</p>", escape_html(p).replace('\n', "<br>")))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn parse(html: &str) -> Html {
-        Html::parse_document(html)
-    }
-
-    // --- is_youtube_url tests ---
-
-    #[test]
-    fn is_youtube_url_www() {
-        assert!(is_youtube_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_no_www() {
-        assert!(is_youtube_url("https://youtube.com/watch?v=dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_mobile() {
-        assert!(is_youtube_url("https://m.youtube.com/watch?v=dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_music() {
-        assert!(is_youtube_url("https://music.youtube.com/watch?v=dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_short_link() {
-        assert!(is_youtube_url("https://youtu.be/dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_shorts() {
-        assert!(is_youtube_url("https://www.youtube.com/shorts/dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_embed() {
-        assert!(is_youtube_url("https://www.youtube.com/embed/dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_live() {
-        assert!(is_youtube_url("https://www.youtube.com/live/dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_http() {
-        assert!(is_youtube_url("http://www.youtube.com/watch?v=dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_with_extra_params() {
-        assert!(is_youtube_url(
-            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=120&list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
-        ));
-    }
-
-    #[test]
-    fn is_youtube_url_rejects_non_youtube() {
-        assert!(!is_youtube_url("https://example.com/watch?v=dQw4w9WgXcQ"));
-    }
-
-    #[test]
-    fn is_youtube_url_rejects_similar_domains() {
-        assert!(!is_youtube_url("https://notyoutube.com/watch?v=abc"));
-        assert!(!is_youtube_url("https://youtube.com.evil.com/watch?v=abc"));
-    }
-
-    #[test]
-    fn is_youtube_url_rejects_invalid_url() {
-        assert!(!is_youtube_url("not a url"));
-    }
+    use crate::metadata::guard::Blocked;
 
     #[test]
-    fn is_youtube_url_channel_page() {
-        assert!(is_youtube_url("https://www.youtube.com/@somechannel"));
-    }
-
-    // --- extract_title tests ---
-
-    #[test]
-    fn extract_title_from_og_title() {
-        let doc = parse(r#"<html><head><meta property="og:title" content="OG Title"></head></html>"#);
-        assert_eq!(extract_title(&doc), Some("OG Title".to_string()));
-    }
-
-    #[test]
-    fn extract_title_from_title_tag() {
-        let doc = parse("<html><head><title>Page Title</title></head></html>");
-        assert_eq!(extract_title(&doc), Some("Page Title".to_string()));
-    }
-
-    #[test]
-    fn extract_title_og_takes_precedence_over_title_tag() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:title" content="OG Title">
-            <title>Page Title</title>
-            </head></html>"#,
+    fn classify_error_detects_blocked_and_parse_failures() {
+        let blocked: Box<dyn Error + Send + Sync> = Box::new(Blocked::Robots);
+        assert_eq!(
+            classify_error(blocked.as_ref()),
+            (FetchOutcome::Blocked, None)
         );
-        assert_eq!(extract_title(&doc), Some("OG Title".to_string()));
-    }
 
-    #[test]
-    fn extract_title_falls_back_to_title_when_og_empty() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:title" content="">
-            <title>Fallback Title</title>
-            </head></html>"#,
-        );
-        assert_eq!(extract_title(&doc), Some("Fallback Title".to_string()));
-    }
+        let json = serde_json::from_str::<u32>("{").unwrap_err();
+        assert_eq!(classify_error(&json), (FetchOutcome::ParseError, None));
 
-    #[test]
-    fn extract_title_falls_back_to_title_when_og_whitespace() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:title" content="   ">
-            <title>Fallback Title</title>
-            </head></html>"#,
-        );
-        assert_eq!(extract_title(&doc), Some("Fallback Title".to_string()));
+        let io = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert_eq!(classify_error(&io), (FetchOutcome::NetworkError, None));
     }
 
-    #[test]
-    fn extract_title_none_when_no_title() {
-        let doc = parse("<html><head></head><body>Hello</body></html>");
-        assert_eq!(extract_title(&doc), None);
-    }
+    #[tokio::test]
+    async fn fetch_metadata_records_rule_only_on_success() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
 
-    #[test]
-    fn extract_title_none_when_empty_title_tag() {
-        let doc = parse("<html><head><title>   </title></head></html>");
-        assert_eq!(extract_title(&doc), None);
-    }
+        let rules_file = std::env::temp_dir().join(format!("laterfeed-rules-meta-{port}.json"));
+        std::fs::write(
+            &rules_file,
+            r#"[{"name": "local", "hosts": ["127.0.0.1"]}]"#,
+        )
+        .unwrap();
+        let fetcher = Fetcher::new(&crate::config::Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            fetch_rules_file: Some(rules_file.to_string_lossy().into_owned()),
+            fetch_host_delay_ms: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        std::fs::remove_file(rules_file).unwrap();
 
-    #[test]
-    fn extract_title_trims_whitespace() {
-        let doc = parse(r#"<html><head><meta property="og:title" content="  Trimmed  "></head></html>"#);
-        assert_eq!(extract_title(&doc), Some("Trimmed".to_string()));
+        let (meta, report) = fetch_metadata(&fetcher, &format!("http://127.0.0.1:{port}/")).await;
+        assert_eq!(report.outcome, FetchOutcome::NetworkError);
+        assert_eq!(meta.fetch_rule, None);
     }
 
-    // --- extract_body tests ---
-
     #[test]
-    fn extract_body_from_article_element() {
-        let doc = parse(
-            r#"<html><body><article><p>Article content</p></article></body></html>"#,
-        );
+    fn escape_html_escapes_special_characters() {
         assert_eq!(
-            extract_body(&doc),
-            Some("<p>Article content</p>".to_string())
+            escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
         );
     }
 
     #[test]
-    fn extract_body_article_takes_precedence_over_description() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:description" content="OG Desc">
-            </head><body><article><p>Article content</p></article></body></html>"#,
-        );
+    fn text_to_html_splits_paragraphs_and_lines() {
         assert_eq!(
-            extract_body(&doc),
-            Some("<p>Article content</p>".to_string())
+            text_to_html("First line\nsecond line\n\n\n<b>Two</b>\n"),
+            "<p>First line<br>second line</p><p>&lt;b&gt;Two&lt;/b&gt;</p>"
         );
     }
 
     #[test]
-    fn extract_body_falls_back_to_og_description() {
-        let doc = parse(
-            r#"<html><head><meta property="og:description" content="OG Desc"></head><body><p>Some text</p></body></html>"#,
-        );
-        assert_eq!(extract_body(&doc), Some("OG Desc".to_string()));
-    }
+    fn source_type_from_host_detects_video_hosts() {
+        let detect = |url: &str| source_type_from_host(&Url::parse(url).unwrap());
 
-    #[test]
-    fn extract_body_falls_back_to_meta_description() {
-        let doc = parse(
-            r#"<html><head><meta name="description" content="Meta Desc"></head><body><p>Some text</p></body></html>"#,
+        assert_eq!(
+            detect("https://www.youtube.com/watch?v=abc"),
+            Some(EntrySourceType::Video)
         );
-        assert_eq!(extract_body(&doc), Some("Meta Desc".to_string()));
-    }
-
-    #[test]
-    fn extract_body_og_description_takes_precedence_over_meta_description() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:description" content="OG Desc">
-            <meta name="description" content="Meta Desc">
-            </head></html>"#,
+        assert_eq!(
+            detect("https://m.twitch.tv/videos/1"),
+            Some(EntrySourceType::Video)
         );
-        assert_eq!(extract_body(&doc), Some("OG Desc".to_string()));
+        assert_eq!(detect("https://dai.ly/x8abc"), Some(EntrySourceType::Video));
+        assert_eq!(detect("https://example.com/video"), None);
+        assert_eq!(detect("https://notvimeo.com/1"), None);
     }
 
     #[test]
-    fn extract_body_falls_back_when_og_empty() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:description" content="">
-            <meta name="description" content="Fallback Desc">
-            </head></html>"#,
+    fn word_count_counts_text_only() {
+        assert_eq!(
+            word_count("<p>Hello <b>big</b>\n world</p><img src=\"x\"><p>again</p>"),
+            4
         );
-        assert_eq!(extract_body(&doc), Some("Fallback Desc".to_string()));
+        assert_eq!(word_count(""), 0);
     }
 
     #[test]
-    fn extract_body_falls_back_when_og_whitespace() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:description" content="   ">
-            <meta name="description" content="Fallback Desc">
-            </head></html>"#,
+    fn parse_date_accepts_rfc3339_and_bare_dates() {
+        assert_eq!(
+            parse_date("2024-05-01T10:00:00+02:00").map(|d| d.to_rfc3339()),
+            Some("2024-05-01T08:00:00+00:00".to_string())
         );
-        assert_eq!(extract_body(&doc), Some("Fallback Desc".to_string()));
-    }
-
-    #[test]
-    fn extract_body_none_when_missing() {
-        let doc = parse("<html><head></head><body>Hello</body></html>");
-        assert_eq!(extract_body(&doc), None);
-    }
-
-    #[test]
-    fn extract_body_none_when_both_descriptions_empty() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:description" content="">
-            <meta name="description" content="">
-            </head></html>"#,
+        assert_eq!(
+            parse_date("2024-05-01").map(|d| d.to_rfc3339()),
+            Some("2024-05-01T00:00:00+00:00".to_string())
         );
-        assert_eq!(extract_body(&doc), None);
-    }
-
-    #[test]
-    fn extract_body_trims_whitespace() {
-        let doc = parse(
-            r#"<html><head><meta property="og:description" content="  Trimmed  "></head></html>"#,
+        assert_eq!(
+            parse_date("2024-05-01T10:00").map(|d| d.to_rfc3339()),
+            Some("2024-05-01T00:00:00+00:00".to_string())
         );
-        assert_eq!(extract_body(&doc), Some("Trimmed".to_string()));
+        assert_eq!(parse_date("yesterday"), None);
     }
 
     #[test]
-    fn extract_body_empty_article_falls_back_to_description() {
-        let doc = parse(
-            r#"<html><head>
-            <meta property="og:description" content="OG Desc">
-            </head><body><article>   </article></body></html>"#,
-        );
-        assert_eq!(extract_body(&doc), Some("OG Desc".to_string()));
+    fn non_empty_trims_and_rejects_whitespace() {
+        assert_eq!(non_empty("  hi "), Some("hi".to_string()));
+        assert_eq!(non_empty("   "), None);
     }
 }
@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use url::Url;
+
+use crate::metadata::{FetchResult, Fetcher, PageMetadata, html::HtmlExtractor};
+
+mod arxiv;
+mod github;
+mod hackernews;
+mod vimeo;
+mod wikipedia;
+mod youtube;
+
+/// A source of page metadata for a particular kind of URL.
+///
+/// Site-specific extractors live in their own module under `extractors/` and are registered in
+/// [`ExtractorRegistry::builtin`].
+#[async_trait]
+pub trait Extractor: Send + Sync {
+    /// Short identifier used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Whether this extractor knows how to handle the URL.
+    fn matches(&self, url: &Url) -> bool;
+
+    /// Fetch and extract metadata for a URL that this extractor matches.
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata>;
+}
+
+/// Ordered list of site-specific extractors, with the generic HTML scraper as the fallback.
+pub struct ExtractorRegistry {
+    extractors: Vec<Box<dyn Extractor>>,
+    fallback: Box<dyn Extractor>,
+}
+
+impl ExtractorRegistry {
+    pub fn builtin() -> Self {
+        Self {
+            extractors: vec![
+                Box::new(youtube::YouTube),
+                Box::new(vimeo::Vimeo),
+                Box::new(github::GitHub),
+                Box::new(arxiv::Arxiv),
+                Box::new(wikipedia::Wikipedia),
+                Box::new(hackernews::HackerNews),
+            ],
+            fallback: Box::new(HtmlExtractor),
+        }
+    }
+
+    /// The first registered extractor matching the URL, if any.
+    fn find(&self, url: &Url) -> Option<&dyn Extractor> {
+        self.extractors
+            .iter()
+            .find(|e| e.matches(url))
+            .map(|e| e.as_ref())
+    }
+
+    /// Extract metadata with the matching site-specific extractor, falling back to the generic
+    /// HTML scraper if there is none or it fails.
+    pub async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        if let Some(extractor) = self.find(url) {
+            match extractor.extract(fetcher, url).await {
+                Ok(meta) => return Ok(meta),
+                Err(e) => tracing::warn!(
+                    "{} extractor failed for {}, falling back to HTML: {}",
+                    extractor.name(),
+                    url,
+                    e
+                ),
+            }
+        }
+
+        self.fallback.extract(fetcher, url).await
+    }
+}
+
+/// Whether the URL's host is exactly one of the given hosts.
+fn host_is(url: &Url, hosts: &[&str]) -> bool {
+    url.host_str().is_some_and(|host| hosts.contains(&host))
+}
+
+/// The non-empty path segments of a URL.
+fn path_segments(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_name(url: &str) -> Option<&'static str> {
+        let registry = ExtractorRegistry::builtin();
+        registry.find(&Url::parse(url).unwrap()).map(|e| e.name())
+    }
+
+    #[test]
+    fn registry_dispatches_to_site_extractors() {
+        assert_eq!(
+            find_name("https://www.youtube.com/watch?v=abc"),
+            Some("youtube")
+        );
+        assert_eq!(find_name("https://vimeo.com/76979871"), Some("vimeo"));
+        assert_eq!(
+            find_name("https://github.com/rust-lang/rust"),
+            Some("github")
+        );
+        assert_eq!(find_name("https://arxiv.org/abs/1706.03762"), Some("arxiv"));
+        assert_eq!(
+            find_name("https://en.wikipedia.org/wiki/Rust"),
+            Some("wikipedia")
+        );
+        assert_eq!(
+            find_name("https://news.ycombinator.com/item?id=1"),
+            Some("hackernews")
+        );
+    }
+
+    #[test]
+    fn registry_has_no_match_for_generic_pages() {
+        assert_eq!(find_name("https://example.com/article"), None);
+    }
+
+    #[test]
+    fn path_segments_skips_empty_segments() {
+        let url = Url::parse("https://example.com//a/b/").unwrap();
+        assert_eq!(path_segments(&url), vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use atom_syndication::Feed;
+use chrono::Utc;
+use url::Url;
+
+use crate::metadata::{
+    FetchResult, Fetcher, PageMetadata, escape_html,
+    extractors::{Extractor, host_is, path_segments},
+    non_empty, text_to_html,
+};
+use crate::models::EntrySourceType;
+
+/// arXiv abstract and PDF pages, via the arXiv export API.
+pub struct Arxiv;
+
+#[async_trait]
+impl Extractor for Arxiv {
+    fn name(&self) -> &'static str {
+        "arxiv"
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        paper_id(url).is_some()
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let id = paper_id(url).ok_or("unsupported arXiv URL")?;
+
+        let mut api_url = Url::parse("https://export.arxiv.org/api/query")?;
+        api_url.query_pairs_mut().append_pair("id_list", &id);
+
+        let xml = fetcher.fetch_text(&api_url).await?;
+        parse_api_response(&xml)
+    }
+}
+
+/// The paper identifier from an `/abs/` or `/pdf/` URL, e.g. `1706.03762v5` or `hep-th/9901001`.
+fn paper_id(url: &Url) -> Option<String> {
+    if !host_is(url, &["arxiv.org", "www.arxiv.org", "export.arxiv.org"]) {
+        return None;
+    }
+
+    let id = match path_segments(url).as_slice() {
+        ["abs" | "pdf", id] => id.to_string(),
+        ["abs" | "pdf", archive, number] => format!("{archive}/{number}"),
+        _ => return None,
+    };
+
+    Some(id.trim_end_matches(".pdf").to_string())
+}
+
+fn parse_api_response(xml: &str) -> FetchResult<PageMetadata> {
+    let feed: Feed = xml.parse()?;
+    let entry = feed
+        .entries
+        .first()
+        .ok_or("arXiv API returned no entries")?;
+
+    let title = non_empty(&collapse_whitespace(&entry.title.value));
+
+    let authors = entry
+        .authors
+        .iter()
+        .map(|a| a.name.trim())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut body = String::new();
+    if !authors.is_empty() {
+        body.push_str(&format!("<p><em>{}</em></p>", escape_html(&authors)));
+    }
+    if let Some(summary) = entry.summary.as_ref() {
+        body.push_str(&text_to_html(&collapse_whitespace(&summary.value)));
+    }
+
+    Ok(PageMetadata {
+        title,
+        body: non_empty(&body),
+        source_type: Some(EntrySourceType::Article),
+        author: non_empty(&authors),
+        published_at: entry.published.map(|d| d.with_timezone(&Utc)),
+        site_name: Some("arXiv".to_string()),
+        language: Some("en".to_string()),
+        ..Default::default()
+    })
+}
+
+/// arXiv wraps titles and abstracts at fixed widths; join them back into single lines.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(url: &str) -> Option<String> {
+        paper_id(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn paper_id_from_abs_and_pdf_urls() {
+        assert_eq!(
+            id("https://arxiv.org/abs/1706.03762"),
+            Some("1706.03762".to_string())
+        );
+        assert_eq!(
+            id("https://arxiv.org/abs/1706.03762v5"),
+            Some("1706.03762v5".to_string())
+        );
+        assert_eq!(
+            id("https://arxiv.org/pdf/1706.03762.pdf"),
+            Some("1706.03762".to_string())
+        );
+        assert_eq!(
+            id("https://arxiv.org/abs/hep-th/9901001"),
+            Some("hep-th/9901001".to_string())
+        );
+    }
+
+    #[test]
+    fn paper_id_rejects_other_pages() {
+        assert_eq!(id("https://arxiv.org/list/cs.AI/recent"), None);
+        assert_eq!(id("https://example.com/abs/1706.03762"), None);
+    }
+
+    #[test]
+    fn parse_api_response_extracts_title_authors_and_abstract() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>ArXiv Query</title>
+              <id>http://arxiv.org/api/x</id>
+              <updated>2024-01-01T00:00:00Z</updated>
+              <entry>
+                <id>http://arxiv.org/abs/1706.03762v5</id>
+                <updated>2023-08-02T00:41:18Z</updated>
+                <published>2017-06-12T17:57:34Z</published>
+                <title>Attention Is All
+                  You Need</title>
+                <summary>  The dominant sequence transduction
+                  models are complex.
+                </summary>
+                <author><name>Ashish Vaswani</name></author>
+                <author><name>Noam Shazeer</name></author>
+              </entry>
+            </feed>"#;
+
+        let meta = parse_api_response(xml).unwrap();
+
+        assert_eq!(meta.title.as_deref(), Some("Attention Is All You Need"));
+        assert_eq!(
+            meta.body.as_deref(),
+            Some(
+                "<p><em>Ashish Vaswani, Noam Shazeer</em></p>\
+                 <p>The dominant sequence transduction models are complex.</p>"
+            )
+        );
+        assert_eq!(meta.author.as_deref(), Some("Ashish Vaswani, Noam Shazeer"));
+        assert_eq!(
+            meta.published_at.map(|d| d.to_rfc3339()),
+            Some("2017-06-12T17:57:34+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_api_response_errors_without_entries() {
+        let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Empty</title>
+            <id>x</id><updated>2024-01-01T00:00:00Z</updated></feed>"#;
+
+        assert!(parse_api_response(xml).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use crate::metadata::{
+    FetchResult, Fetcher, PageMetadata,
+    extractors::{Extractor, host_is, path_segments},
+    non_empty, text_to_html,
+};
+use crate::models::EntrySourceType;
+
+const API_BASE: &str = "https://api.github.com";
+
+/// First path segments on github.com that are site sections rather than owners.
+const RESERVED_OWNERS: &[&str] = &[
+    "about",
+    "apps",
+    "collections",
+    "enterprise",
+    "explore",
+    "features",
+    "login",
+    "marketplace",
+    "notifications",
+    "orgs",
+    "pricing",
+    "pulls",
+    "search",
+    "settings",
+    "sponsors",
+    "topics",
+    "trending",
+];
+
+#[derive(Debug, PartialEq)]
+enum Target {
+    Repo {
+        owner: String,
+        repo: String,
+    },
+    Issue {
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+}
+
+#[derive(serde::Deserialize)]
+struct Repo {
+    full_name: String,
+    description: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct Issue {
+    title: String,
+    number: u64,
+    body_html: Option<String>,
+    user: Option<User>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Deserialize)]
+struct User {
+    login: String,
+}
+
+/// GitHub repositories, issues and pull requests, via the REST API.
+pub struct GitHub;
+
+#[async_trait]
+impl Extractor for GitHub {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        parse_target(url).is_some()
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let target = parse_target(url).ok_or("unsupported GitHub URL")?;
+
+        match target {
+            Target::Repo { owner, repo } => {
+                let api_url = Url::parse(&format!("{API_BASE}/repos/{owner}/{repo}"))?;
+                let repo: Repo = fetcher
+                    .fetch_json(&api_url, "application/vnd.github+json")
+                    .await?;
+                Ok(repo_metadata(repo))
+            }
+            Target::Issue {
+                owner,
+                repo,
+                number,
+            } => {
+                let api_url =
+                    Url::parse(&format!("{API_BASE}/repos/{owner}/{repo}/issues/{number}"))?;
+                let issue: Issue = fetcher
+                    .fetch_json(&api_url, "application/vnd.github.html+json")
+                    .await?;
+                Ok(issue_metadata(issue, &owner, &repo))
+            }
+        }
+    }
+}
+
+fn parse_target(url: &Url) -> Option<Target> {
+    if !host_is(url, &["github.com", "www.github.com"]) {
+        return None;
+    }
+
+    match path_segments(url).as_slice() {
+        [owner, repo] if !RESERVED_OWNERS.contains(owner) => Some(Target::Repo {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        }),
+        [owner, repo, "issues" | "pull", number, ..] if !RESERVED_OWNERS.contains(owner) => {
+            Some(Target::Issue {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                number: number.parse().ok()?,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn repo_metadata(repo: Repo) -> PageMetadata {
+    let description = repo.description.as_deref().and_then(non_empty);
+
+    let title = match &description {
+        Some(description) => format!("{}: {}", repo.full_name, description),
+        None => repo.full_name,
+    };
+
+    PageMetadata {
+        title: Some(title),
+        body: description.map(|d| text_to_html(&d)),
+        source_type: Some(EntrySourceType::Article),
+        site_name: Some("GitHub".to_string()),
+        ..Default::default()
+    }
+}
+
+fn issue_metadata(issue: Issue, owner: &str, repo: &str) -> PageMetadata {
+    PageMetadata {
+        title: Some(format!(
+            "{} · #{} · {}/{}",
+            issue.title.trim(),
+            issue.number,
+            owner,
+            repo
+        )),
+        body: issue.body_html.as_deref().and_then(non_empty),
+        source_type: Some(EntrySourceType::Article),
+        author: issue.user.map(|u| u.login),
+        published_at: issue.created_at,
+        site_name: Some("GitHub".to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(url: &str) -> Option<Target> {
+        parse_target(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn parse_target_repo() {
+        assert_eq!(
+            target("https://github.com/rust-lang/rust"),
+            Some(Target::Repo {
+                owner: "rust-lang".to_string(),
+                repo: "rust".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_target_issue_and_pull_request() {
+        assert_eq!(
+            target("https://github.com/rust-lang/rust/issues/123"),
+            Some(Target::Issue {
+                owner: "rust-lang".to_string(),
+                repo: "rust".to_string(),
+                number: 123
+            })
+        );
+        assert_eq!(
+            target("https://github.com/rust-lang/rust/pull/456/files"),
+            Some(Target::Issue {
+                owner: "rust-lang".to_string(),
+                repo: "rust".to_string(),
+                number: 456
+            })
+        );
+    }
+
+    #[test]
+    fn parse_target_rejects_other_pages() {
+        assert_eq!(target("https://github.com/rust-lang"), None);
+        assert_eq!(
+            target("https://github.com/rust-lang/rust/blob/main/README.md"),
+            None
+        );
+        assert_eq!(target("https://github.com/settings/profile"), None);
+        assert_eq!(target("https://github.com/rust-lang/rust/issues/new"), None);
+        assert_eq!(target("https://gitlab.com/rust-lang/rust"), None);
+    }
+
+    #[test]
+    fn repo_metadata_includes_description_in_title() {
+        let meta = repo_metadata(Repo {
+            full_name: "rust-lang/rust".to_string(),
+            description: Some("Empowering everyone".to_string()),
+        });
+
+        assert_eq!(
+            meta.title.as_deref(),
+            Some("rust-lang/rust: Empowering everyone")
+        );
+        assert_eq!(meta.body.as_deref(), Some("<p>Empowering everyone</p>"));
+    }
+
+    #[test]
+    fn issue_metadata_formats_title() {
+        let meta = issue_metadata(
+            Issue {
+                title: "Crash on startup ".to_string(),
+                number: 7,
+                body_html: Some("<p>Steps</p>".to_string()),
+                user: Some(User {
+                    login: "octocat".to_string(),
+                }),
+                created_at: None,
+            },
+            "owner",
+            "repo",
+        );
+
+        assert_eq!(
+            meta.title.as_deref(),
+            Some("Crash on startup · #7 · owner/repo")
+        );
+        assert_eq!(meta.body.as_deref(), Some("<p>Steps</p>"));
+        assert_eq!(meta.author.as_deref(), Some("octocat"));
+    }
+}
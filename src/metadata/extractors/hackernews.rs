@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use chrono::DateTime;
+use url::Url;
+
+use crate::metadata::{
+    FetchResult, Fetcher, PageMetadata, escape_html,
+    extractors::{Extractor, host_is},
+    non_empty,
+};
+use crate::models::EntrySourceType;
+
+#[derive(serde::Deserialize)]
+struct Item {
+    title: Option<String>,
+    by: Option<String>,
+    text: Option<String>,
+    url: Option<String>,
+    /// Unix timestamp of submission.
+    time: Option<i64>,
+}
+
+/// Hacker News stories and comments, via the Firebase API.
+pub struct HackerNews;
+
+#[async_trait]
+impl Extractor for HackerNews {
+    fn name(&self) -> &'static str {
+        "hackernews"
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        item_id(url).is_some()
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let id = item_id(url).ok_or("unsupported Hacker News URL")?;
+
+        let api_url = Url::parse(&format!(
+            "https://hacker-news.firebaseio.com/v0/item/{id}.json"
+        ))?;
+        let item: Option<Item> = fetcher.fetch_json(&api_url, "application/json").await?;
+
+        Ok(item_metadata(item.ok_or("Hacker News item not found")?))
+    }
+}
+
+fn item_id(url: &Url) -> Option<u64> {
+    if !host_is(url, &["news.ycombinator.com"]) || url.path() != "/item" {
+        return None;
+    }
+
+    url.query_pairs()
+        .find(|(key, _)| key == "id")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+fn item_metadata(item: Item) -> PageMetadata {
+    let title = item
+        .title
+        .as_deref()
+        .and_then(non_empty)
+        .or_else(|| item.by.as_ref().map(|by| format!("Comment by {by}")));
+
+    let mut body = String::new();
+    if let Some(link) = item.url.as_deref().and_then(non_empty) {
+        let link = escape_html(&link);
+        body.push_str(&format!(r#"<p><a href="{link}">{link}</a></p>"#));
+    }
+    if let Some(text) = item.text.as_deref().and_then(non_empty) {
+        body.push_str(&format!("<p>{text}</p>"));
+    }
+
+    PageMetadata {
+        title,
+        body: non_empty(&body),
+        source_type: Some(EntrySourceType::Article),
+        author: item.by,
+        published_at: item.time.and_then(|t| DateTime::from_timestamp(t, 0)),
+        site_name: Some("Hacker News".to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(url: &str) -> Option<u64> {
+        item_id(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn item_id_from_item_urls() {
+        assert_eq!(id("https://news.ycombinator.com/item?id=8863"), Some(8863));
+        assert_eq!(
+            id("https://news.ycombinator.com/item?p=2&id=8863"),
+            Some(8863)
+        );
+    }
+
+    #[test]
+    fn item_id_rejects_other_pages() {
+        assert_eq!(id("https://news.ycombinator.com/news"), None);
+        assert_eq!(id("https://news.ycombinator.com/item?id=abc"), None);
+        assert_eq!(id("https://example.com/item?id=8863"), None);
+    }
+
+    #[test]
+    fn item_metadata_for_link_story() {
+        let meta = item_metadata(Item {
+            title: Some("My YC app".to_string()),
+            by: Some("dhouston".to_string()),
+            text: None,
+            url: Some("http://www.getdropbox.com/u/2/screencast.html".to_string()),
+            time: Some(1175714200),
+        });
+
+        assert_eq!(meta.title.as_deref(), Some("My YC app"));
+        assert_eq!(meta.author.as_deref(), Some("dhouston"));
+        assert_eq!(
+            meta.published_at.map(|d| d.to_rfc3339()),
+            Some("2007-04-04T19:16:40+00:00".to_string())
+        );
+        assert_eq!(
+            meta.body.as_deref(),
+            Some(
+                r#"<p><a href="http://www.getdropbox.com/u/2/screencast.html">http://www.getdropbox.com/u/2/screencast.html</a></p>"#
+            )
+        );
+    }
+
+    #[test]
+    fn item_metadata_for_comment() {
+        let meta = item_metadata(Item {
+            title: None,
+            by: Some("pg".to_string()),
+            text: Some("Nice <i>work</i>".to_string()),
+            url: None,
+            time: None,
+        });
+
+        assert_eq!(meta.title.as_deref(), Some("Comment by pg"));
+        assert_eq!(meta.body.as_deref(), Some("<p>Nice <i>work</i></p>"));
+    }
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use url::Url;
+
+use crate::metadata::{
+    FetchResult, Fetcher, PageMetadata,
+    extractors::{Extractor, host_is, path_segments},
+    oembed,
+};
+
+/// Vimeo videos, via the public oEmbed endpoint.
+pub struct Vimeo;
+
+#[async_trait]
+impl Extractor for Vimeo {
+    fn name(&self) -> &'static str {
+        "vimeo"
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        host_is(url, &["vimeo.com", "www.vimeo.com", "player.vimeo.com"])
+            && path_segments(url)
+                .iter()
+                .any(|s| s.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let endpoint = oembed::provider_endpoint(url).ok_or("no oEmbed provider for URL")?;
+        let embed = oembed::fetch(fetcher, &endpoint).await?;
+
+        Ok(oembed::merge(PageMetadata::default(), embed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(url: &str) -> bool {
+        Vimeo.matches(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn matches_video_pages() {
+        assert!(matches("https://vimeo.com/76979871"));
+        assert!(matches("https://player.vimeo.com/video/76979871"));
+        assert!(matches("https://vimeo.com/channels/staffpicks/76979871"));
+    }
+
+    #[test]
+    fn rejects_non_video_pages() {
+        assert!(!matches("https://vimeo.com/"));
+        assert!(!matches("https://vimeo.com/watch"));
+        assert!(!matches("https://example.com/76979871"));
+    }
+}
@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use url::Url;
+
+use crate::metadata::{
+    FetchResult, Fetcher, PageMetadata,
+    extractors::{Extractor, path_segments},
+    non_empty,
+};
+use crate::models::EntrySourceType;
+
+#[derive(serde::Deserialize)]
+struct Summary {
+    title: String,
+    extract_html: Option<String>,
+    lang: Option<String>,
+    thumbnail: Option<Thumbnail>,
+    content_urls: Option<ContentUrls>,
+}
+
+#[derive(serde::Deserialize)]
+struct Thumbnail {
+    source: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ContentUrls {
+    desktop: PageUrls,
+}
+
+#[derive(serde::Deserialize)]
+struct PageUrls {
+    page: String,
+}
+
+/// Wikipedia articles, via the REST page summary API.
+pub struct Wikipedia;
+
+#[async_trait]
+impl Extractor for Wikipedia {
+    fn name(&self) -> &'static str {
+        "wikipedia"
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        article(url).is_some()
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let (language, title) = article(url).ok_or("unsupported Wikipedia URL")?;
+
+        let api_url = Url::parse(&format!(
+            "https://{language}.wikipedia.org/api/rest_v1/page/summary/{title}"
+        ))?;
+        let summary: Summary = fetcher.fetch_json(&api_url, "application/json").await?;
+
+        Ok(PageMetadata {
+            title: non_empty(&summary.title),
+            body: summary.extract_html.as_deref().and_then(non_empty),
+            source_type: Some(EntrySourceType::Article),
+            site_name: Some("Wikipedia".to_string()),
+            canonical_url: summary.content_urls.map(|urls| urls.desktop.page),
+            image_url: summary.thumbnail.map(|thumbnail| thumbnail.source),
+            language: summary.lang.or_else(|| Some(language.to_string())),
+            ..Default::default()
+        })
+    }
+}
+
+/// The language subdomain and (still percent-encoded) article title of a `/wiki/` URL.
+fn article(url: &Url) -> Option<(&str, &str)> {
+    let host = url.host_str()?;
+    let subdomain = host
+        .strip_suffix(".m.wikipedia.org")
+        .or_else(|| host.strip_suffix(".wikipedia.org"))?;
+
+    if subdomain.is_empty() || subdomain.contains('.') {
+        return None;
+    }
+
+    match path_segments(url).as_slice() {
+        ["wiki", title] if !title.contains(':') && !title.contains("%3A") => {
+            Some((subdomain, *title))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn article_from_desktop_and_mobile_urls() {
+        let url = Url::parse("https://en.wikipedia.org/wiki/Rust_(programming_language)").unwrap();
+        assert_eq!(article(&url), Some(("en", "Rust_(programming_language)")));
+
+        let url = Url::parse("https://de.m.wikipedia.org/wiki/K%C3%B6ln").unwrap();
+        assert_eq!(article(&url), Some(("de", "K%C3%B6ln")));
+    }
+
+    #[test]
+    fn article_rejects_special_pages_and_other_hosts() {
+        let url = Url::parse("https://en.wikipedia.org/wiki/Special:Random").unwrap();
+        assert_eq!(article(&url), None);
+
+        let url = Url::parse("https://en.wikipedia.org/w/index.php?title=Rust").unwrap();
+        assert_eq!(article(&url), None);
+
+        let url = Url::parse("https://wikipedia.org/wiki/Rust").unwrap();
+        assert_eq!(article(&url), None);
+
+        let url = Url::parse("https://en.wikipedia.org.evil.com/wiki/Rust").unwrap();
+        assert_eq!(article(&url), None);
+    }
+}
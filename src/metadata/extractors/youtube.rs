@@ -0,0 +1,680 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+use crate::metadata::{
+    CaptionSettings, FetchResult, Fetcher, PageMetadata,
+    extractors::{Extractor, host_is},
+    html, non_empty, oembed, parse_date, text_to_html,
+};
+use crate::models::{Chapter, EntrySourceType, TranscriptSegment};
+
+const YOUTUBE_HOSTS: &[&str] = &[
+    "youtube.com",
+    "www.youtube.com",
+    "m.youtube.com",
+    "music.youtube.com",
+    "youtu.be",
+];
+
+/// Marks the start of the player response JSON embedded in watch pages.
+const PLAYER_RESPONSE_MARKER: &str = "ytInitialPlayerResponse = ";
+
+/// Fewest timestamped lines in a description that YouTube (and we) treat as chapters.
+const MIN_CHAPTERS: usize = 3;
+
+/// YouTube pages often return broken titles (e.g. "- YouTube") when scraped directly.
+/// Use the oEmbed API for the title and embed, and regular HTML scrape for body, in parallel.
+/// The watch page also embeds the player response, which has the video's details and caption
+/// tracks for the transcript.
+pub struct YouTube;
+
+#[async_trait]
+impl Extractor for YouTube {
+    fn name(&self) -> &'static str {
+        "youtube"
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        host_is(url, YOUTUBE_HOSTS)
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let endpoint = oembed::provider_endpoint(url).ok_or("no oEmbed provider for URL")?;
+        let (oembed_result, page_result) =
+            tokio::join!(oembed::fetch(fetcher, &endpoint), fetcher.fetch_text(url));
+
+        let (page, player) = match page_result {
+            Ok(html) => (html::parse_page(&html, url), player_response(&html)),
+            Err(_) => (PageMetadata::default(), None),
+        };
+        let has_player = player.is_some();
+        let page = match player {
+            Some(player) => {
+                let transcript = match fetch_transcript(fetcher, &player).await {
+                    Ok(transcript) => transcript,
+                    Err(e) => {
+                        tracing::warn!("Failed to fetch YouTube captions for {}: {}", url, e);
+                        Vec::new()
+                    }
+                };
+                PageMetadata {
+                    transcript,
+                    ..with_player_response(page, player)
+                }
+            }
+            None => page,
+        };
+
+        match oembed_result {
+            Ok(mut embed) => {
+                // The channel is shown alongside the duration instead of as a byline
+                if has_player {
+                    embed.author_name = None;
+                }
+                Ok(oembed::merge(page, embed))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch YouTube oEmbed for {}: {}", url, e);
+                Ok(page)
+            }
+        }
+    }
+}
+
+/// The parts of a watch page's `ytInitialPlayerResponse` that we use.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct PlayerResponse {
+    video_details: Option<VideoDetails>,
+    microformat: Option<Microformat>,
+    captions: Option<Captions>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Captions {
+    player_captions_tracklist_renderer: Option<CaptionTracklist>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct CaptionTracklist {
+    caption_tracks: Vec<CaptionTrack>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptionTrack {
+    base_url: String,
+    language_code: String,
+    /// `asr` for automatically generated captions.
+    kind: Option<String>,
+}
+
+/// Captions in YouTube's `json3` format.
+#[derive(Deserialize)]
+struct Json3 {
+    #[serde(default)]
+    events: Vec<Json3Event>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Event {
+    #[serde(default)]
+    t_start_ms: i64,
+    #[serde(default)]
+    segs: Vec<Json3Segment>,
+}
+
+#[derive(Deserialize)]
+struct Json3Segment {
+    #[serde(default)]
+    utf8: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct VideoDetails {
+    title: Option<String>,
+    length_seconds: Option<String>,
+    channel_id: Option<String>,
+    author: Option<String>,
+    short_description: Option<String>,
+    thumbnail: Thumbnails,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct Microformat {
+    player_microformat_renderer: Option<MicroformatRenderer>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MicroformatRenderer {
+    upload_date: Option<String>,
+    publish_date: Option<String>,
+    owner_profile_url: Option<String>,
+    thumbnail: Thumbnails,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Thumbnails {
+    thumbnails: Vec<Thumbnail>,
+}
+
+#[derive(Deserialize)]
+struct Thumbnail {
+    url: String,
+    #[serde(default)]
+    width: u32,
+}
+
+/// Find and parse the player response in a watch page.
+fn player_response(html: &str) -> Option<PlayerResponse> {
+    let start = html.find(PLAYER_RESPONSE_MARKER)? + PLAYER_RESPONSE_MARKER.len();
+
+    // The JSON is followed by more script, so only parse the first value
+    serde_json::Deserializer::from_str(&html[start..])
+        .into_iter::<PlayerResponse>()
+        .next()?
+        .ok()
+}
+
+/// Download the transcript of the caption track in the configured language, if there is one.
+async fn fetch_transcript(
+    fetcher: &Fetcher,
+    player: &PlayerResponse,
+) -> FetchResult<Vec<TranscriptSegment>> {
+    let settings = fetcher.captions();
+    let tracks = player
+        .captions
+        .as_ref()
+        .and_then(|captions| captions.player_captions_tracklist_renderer.as_ref())
+        .map(|tracklist| tracklist.caption_tracks.as_slice())
+        .unwrap_or_default();
+
+    let Some(track) = select_track(tracks, &settings.language) else {
+        return Ok(Vec::new());
+    };
+    let url = caption_url(track, settings)?;
+    let captions: Json3 = fetcher.fetch_json(&url, "application/json").await?;
+
+    Ok(transcript_segments(captions))
+}
+
+/// The caption track to use for a language: manual captions before automatically generated
+/// ones, and an exact language match (`en-GB`) before a regional variant (`en` for `en-GB`).
+fn select_track<'a>(tracks: &'a [CaptionTrack], language: &str) -> Option<&'a CaptionTrack> {
+    let primary = |code: &str| code.split(['-', '_']).next().unwrap_or(code).to_lowercase();
+
+    tracks
+        .iter()
+        .filter(|track| primary(&track.language_code) == primary(language))
+        .min_by_key(|track| {
+            (
+                track.kind.as_deref() == Some("asr"),
+                !track.language_code.eq_ignore_ascii_case(language),
+            )
+        })
+}
+
+/// The URL to download a track from: the configured endpoint with the track's (signed) query,
+/// asking for the `json3` format.
+fn caption_url(track: &CaptionTrack, settings: &CaptionSettings) -> FetchResult<Url> {
+    let track_url = Url::parse(&track.base_url)?;
+    let mut url = settings.base_url.clone();
+
+    url.query_pairs_mut()
+        .extend_pairs(track_url.query_pairs().filter(|(key, _)| key != "fmt"))
+        .append_pair("fmt", "json3");
+    Ok(url)
+}
+
+/// Turn caption events into transcript lines, skipping the empty ones that only break lines.
+fn transcript_segments(captions: Json3) -> Vec<TranscriptSegment> {
+    captions
+        .events
+        .into_iter()
+        .filter_map(|event| {
+            let text: String = event.segs.iter().map(|seg| seg.utf8.as_str()).collect();
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+            (!text.is_empty()).then_some(TranscriptSegment {
+                start_secs: event.t_start_ms / 1000,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Fill in what the player response knows about the video: duration, channel, upload date,
+/// best thumbnail, the full description with links, and chapters from the description.
+fn with_player_response(page: PageMetadata, player: PlayerResponse) -> PageMetadata {
+    let details = player.video_details.unwrap_or_default();
+    let microformat = player
+        .microformat
+        .and_then(|m| m.player_microformat_renderer)
+        .unwrap_or_default();
+
+    let description = details.short_description.as_deref().and_then(non_empty);
+    let channel_url = microformat
+        .owner_profile_url
+        .as_deref()
+        .and_then(non_empty)
+        .or_else(|| {
+            let channel_id = details.channel_id.as_deref().and_then(non_empty)?;
+            Some(format!("https://www.youtube.com/channel/{channel_id}"))
+        });
+    let thumbnail = details
+        .thumbnail
+        .thumbnails
+        .into_iter()
+        .chain(microformat.thumbnail.thumbnails)
+        .max_by_key(|thumbnail| thumbnail.width)
+        .map(|thumbnail| thumbnail.url);
+
+    PageMetadata {
+        title: details.title.as_deref().and_then(non_empty).or(page.title),
+        body: description
+            .as_deref()
+            .map(|d| linkify(&text_to_html(d)))
+            .or(page.body),
+        source_type: Some(EntrySourceType::Video),
+        author: details
+            .author
+            .as_deref()
+            .and_then(non_empty)
+            .or(page.author),
+        author_url: channel_url.or(page.author_url),
+        published_at: microformat
+            .upload_date
+            .or(microformat.publish_date)
+            .as_deref()
+            .and_then(parse_date)
+            .or(page.published_at),
+        image_url: thumbnail.or(page.image_url),
+        duration_secs: details
+            .length_seconds
+            .and_then(|secs| secs.parse().ok())
+            .filter(|&secs| secs > 0)
+            .or(page.duration_secs),
+        chapters: description
+            .as_deref()
+            .map(parse_chapters)
+            .unwrap_or_default(),
+        ..page
+    }
+}
+
+/// Turn the bare URLs in escaped HTML text into links.
+fn linkify(html: &str) -> String {
+    let mut linked = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        linked.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+
+        // A URL ends at whitespace or markup, and trailing punctuation is usually prose
+        let mut end = candidate
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .unwrap_or(candidate.len());
+        for entity in ["&quot;", "&#39;", "&lt;", "&gt;"] {
+            if let Some(index) = candidate[..end].find(entity) {
+                end = end.min(index);
+            }
+        }
+        let url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+
+        linked.push_str(&format!(r#"<a href="{url}">{url}</a>"#));
+        rest = &candidate[url.len()..];
+    }
+
+    linked.push_str(rest);
+    linked
+}
+
+/// Chapters listed in a video description as lines starting with a timestamp. Like YouTube,
+/// only accepted if there are at least [`MIN_CHAPTERS`], starting at 0:00 and in order.
+fn parse_chapters(description: &str) -> Vec<Chapter> {
+    let chapters: Vec<Chapter> = description.lines().filter_map(parse_chapter).collect();
+
+    let valid = chapters.len() >= MIN_CHAPTERS
+        && chapters[0].start_secs == 0
+        && chapters
+            .windows(2)
+            .all(|pair| pair[0].start_secs < pair[1].start_secs);
+    if valid { chapters } else { Vec::new() }
+}
+
+/// Parse a chapter line such as `1:23 Title`, `- (01:02:03) - Title` or `[0:00] Intro`.
+fn parse_chapter(line: &str) -> Option<Chapter> {
+    let line = line.trim().trim_start_matches(['-', '*', '•']).trim_start();
+    let (timestamp, title) = line.split_once(char::is_whitespace)?;
+    let start_secs = parse_timestamp(timestamp.trim_matches(['(', ')', '[', ']']))?;
+    let title = title
+        .trim()
+        .trim_start_matches(['-', '–', '—', ':', '|'])
+        .trim();
+
+    (!title.is_empty()).then(|| Chapter {
+        start_secs,
+        title: title.to_string(),
+    })
+}
+
+/// Parse `M:SS`, `MM:SS` or `H:MM:SS` into seconds.
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+
+    let mut secs = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() || part.len() > 2 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let value: i64 = part.parse().ok()?;
+        if i > 0 && value >= 60 {
+            return None;
+        }
+        secs = secs * 60 + value;
+    }
+    Some(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_youtube_url(url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| YouTube.matches(&url))
+    }
+
+    #[test]
+    fn is_youtube_url_www() {
+        assert!(is_youtube_url(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        ));
+    }
+
+    #[test]
+    fn is_youtube_url_no_www() {
+        assert!(is_youtube_url("https://youtube.com/watch?v=dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_mobile() {
+        assert!(is_youtube_url("https://m.youtube.com/watch?v=dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_music() {
+        assert!(is_youtube_url(
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ"
+        ));
+    }
+
+    #[test]
+    fn is_youtube_url_short_link() {
+        assert!(is_youtube_url("https://youtu.be/dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_shorts() {
+        assert!(is_youtube_url("https://www.youtube.com/shorts/dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_embed() {
+        assert!(is_youtube_url("https://www.youtube.com/embed/dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_live() {
+        assert!(is_youtube_url("https://www.youtube.com/live/dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_http() {
+        assert!(is_youtube_url("http://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_with_extra_params() {
+        assert!(is_youtube_url(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=120&list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf"
+        ));
+    }
+
+    #[test]
+    fn is_youtube_url_rejects_non_youtube() {
+        assert!(!is_youtube_url("https://example.com/watch?v=dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_youtube_url_rejects_similar_domains() {
+        assert!(!is_youtube_url("https://notyoutube.com/watch?v=abc"));
+        assert!(!is_youtube_url("https://youtube.com.evil.com/watch?v=abc"));
+    }
+
+    #[test]
+    fn is_youtube_url_rejects_invalid_url() {
+        assert!(!is_youtube_url("not a url"));
+    }
+
+    #[test]
+    fn is_youtube_url_channel_page() {
+        assert!(is_youtube_url("https://www.youtube.com/@somechannel"));
+    }
+
+    const WATCH_PAGE: &str = r#"<html><head><title>Talk - YouTube</title></head><body><script>
+        var ytInitialPlayerResponse = {"videoDetails": {"videoId": "abc", "title": "A Talk",
+        "lengthSeconds": "3725", "channelId": "UC123", "author": "Conf Channel",
+        "shortDescription": "Slides: https://example.com/slides.\n\n0:00 Intro\n1:30 - Background\n1:02:03 Q&A",
+        "thumbnail": {"thumbnails": [{"url": "https://i.ytimg.com/vi/abc/default.jpg", "width": 120},
+        {"url": "https://i.ytimg.com/vi/abc/hqdefault.jpg", "width": 480}]}},
+        "microformat": {"playerMicroformatRenderer": {"uploadDate": "2024-05-01T10:00:00-07:00",
+        "ownerProfileUrl": "http://www.youtube.com/@conf",
+        "thumbnail": {"thumbnails": [{"url": "https://i.ytimg.com/vi/abc/maxresdefault.jpg", "width": 1280}]}}}};
+        var meta = document.createElement('meta');</script></body></html>"#;
+
+    #[test]
+    fn player_response_fills_in_video_details() {
+        let player = player_response(WATCH_PAGE).unwrap();
+        let meta = with_player_response(PageMetadata::default(), player);
+
+        assert_eq!(meta.title.as_deref(), Some("A Talk"));
+        assert_eq!(meta.source_type, Some(EntrySourceType::Video));
+        assert_eq!(meta.author.as_deref(), Some("Conf Channel"));
+        assert_eq!(
+            meta.author_url.as_deref(),
+            Some("http://www.youtube.com/@conf")
+        );
+        assert_eq!(
+            meta.published_at.map(|d| d.to_rfc3339()),
+            Some("2024-05-01T17:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            meta.image_url.as_deref(),
+            Some("https://i.ytimg.com/vi/abc/maxresdefault.jpg")
+        );
+        assert_eq!(meta.duration_secs, Some(3725));
+        assert_eq!(
+            meta.body.as_deref(),
+            Some(
+                r#"<p>Slides: <a href="https://example.com/slides">https://example.com/slides</a>.</p><p>0:00 Intro<br>1:30 - Background<br>1:02:03 Q&amp;A</p>"#
+            )
+        );
+        assert_eq!(
+            meta.chapters,
+            vec![
+                Chapter {
+                    start_secs: 0,
+                    title: "Intro".to_string()
+                },
+                Chapter {
+                    start_secs: 90,
+                    title: "Background".to_string()
+                },
+                Chapter {
+                    start_secs: 3723,
+                    title: "Q&A".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn player_response_missing_from_page() {
+        assert!(player_response("<html><body>Nothing here</body></html>").is_none());
+    }
+
+    #[test]
+    fn parse_chapters_requires_zero_start_and_order() {
+        assert!(parse_chapters("1:00 One\n2:00 Two\n3:00 Three").is_empty());
+        assert!(parse_chapters("0:00 One\n3:00 Two\n2:00 Three").is_empty());
+        assert!(parse_chapters("0:00 One\n1:00 Two").is_empty());
+        assert_eq!(
+            parse_chapters("Intro text\n[0:00] One\n(1:00) Two\n• 2:00 | Three").len(),
+            3
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_formats() {
+        assert_eq!(parse_timestamp("0:00"), Some(0));
+        assert_eq!(parse_timestamp("12:34"), Some(754));
+        assert_eq!(parse_timestamp("1:02:03"), Some(3723));
+        assert_eq!(parse_timestamp("1:60"), None);
+        assert_eq!(parse_timestamp("123"), None);
+        assert_eq!(parse_timestamp("a:bc"), None);
+    }
+
+    #[test]
+    fn linkify_stops_at_punctuation_and_entities() {
+        assert_eq!(
+            linkify("See (https://a.example/x?y=1&amp;z=2), or &quot;http://b.example&quot;"),
+            r#"See (<a href="https://a.example/x?y=1&amp;z=2">https://a.example/x?y=1&amp;z=2</a>), or &quot;<a href="http://b.example">http://b.example</a>&quot;"#
+        );
+    }
+
+    fn track(language_code: &str, kind: Option<&str>) -> CaptionTrack {
+        CaptionTrack {
+            base_url: format!("https://www.youtube.com/api/timedtext?v=abc&lang={language_code}"),
+            language_code: language_code.to_string(),
+            kind: kind.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn select_track_prefers_manual_captions_in_language() {
+        let tracks = [
+            track("en", Some("asr")),
+            track("de", None),
+            track("en-GB", None),
+            track("en", None),
+        ];
+
+        let pick = |language: &str| {
+            select_track(&tracks, language).map(|t| (t.language_code.as_str(), t.kind.is_some()))
+        };
+        assert_eq!(pick("en"), Some(("en", false)));
+        assert_eq!(pick("en-GB"), Some(("en-GB", false)));
+        assert_eq!(pick("de"), Some(("de", false)));
+        assert_eq!(pick("fr"), None);
+
+        let tracks = [track("de", None), track("en", Some("asr"))];
+        assert_eq!(
+            select_track(&tracks, "en").and_then(|t| t.kind.as_deref()),
+            Some("asr")
+        );
+    }
+
+    #[test]
+    fn transcript_segments_join_and_skip_empty_events() {
+        let captions: Json3 = serde_json::from_str(
+            r#"{"events": [
+                {"tStartMs": 0, "dDurationMs": 2000},
+                {"tStartMs": 1200, "segs": [{"utf8": "Hello"}, {"utf8": " there\n"}]},
+                {"tStartMs": 3400, "aAppend": 1, "segs": [{"utf8": "\n"}]},
+                {"tStartMs": 65500, "segs": [{"utf8": "everyone"}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            transcript_segments(captions),
+            vec![
+                TranscriptSegment {
+                    start_secs: 1,
+                    text: "Hello there".to_string()
+                },
+                TranscriptSegment {
+                    start_secs: 65,
+                    text: "everyone".to_string()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_transcript_from_configured_endpoint() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Only answer requests for the English track in json3
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+
+            let body = r#"{"events": [{"tStartMs": 0, "segs": [{"utf8": "Hi"}]}]}"#;
+            let response = if request
+                .starts_with("GET /api/timedtext?v=abc&lang=en&kind=asr&fmt=json3 ")
+            {
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string()
+            };
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let fetcher = Fetcher::new(&crate::config::Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            youtube_captions_url: Some(format!("http://127.0.0.1:{port}/api/timedtext")),
+            ..Default::default()
+        })
+        .unwrap();
+        let player: PlayerResponse = serde_json::from_str(
+            r#"{"captions": {"playerCaptionsTracklistRenderer": {"captionTracks": [
+                {"baseUrl": "https://www.youtube.com/api/timedtext?v=abc&lang=de", "languageCode": "de"},
+                {"baseUrl": "https://www.youtube.com/api/timedtext?v=abc&lang=en&kind=asr&fmt=srv3",
+                 "languageCode": "en", "kind": "asr"}
+            ]}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fetch_transcript(&fetcher, &player).await.unwrap(),
+            vec![TranscriptSegment {
+                start_secs: 0,
+                text: "Hi".to_string()
+            }]
+        );
+    }
+}
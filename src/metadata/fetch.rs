@@ -0,0 +1,902 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, LOCATION, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::OwnedSemaphorePermit;
+use url::Url;
+
+use crate::config::Config;
+use crate::metadata::FetchResult;
+use crate::metadata::guard::{Blocked, GuardedResolver, UrlGuard, find_blocked};
+use crate::metadata::polite::{HostLimiter, RobotsCache, parse_retry_after};
+use crate::metadata::rules::FetchRules;
+
+/// Default cap on how much of a response body is downloaded (5 MiB).
+const DEFAULT_MAX_BODY_BYTES: usize = 5 * 1024 * 1024;
+
+/// How far into an HTML document to look for a `<meta>` charset declaration.
+const META_CHARSET_PRESCAN_BYTES: usize = 1024;
+
+/// Default maximum number of redirects followed per request.
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Default timeout for a whole request, including reading the body.
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+const DEFAULT_USER_AGENT: &str = "Laterfeed/1.0";
+
+/// Where YouTube caption tracks are downloaded from by default.
+const DEFAULT_YOUTUBE_CAPTIONS_URL: &str = "https://www.youtube.com/api/timedtext";
+
+/// Default language of captions to fetch as transcripts.
+const DEFAULT_TRANSCRIPT_LANGUAGE: &str = "en";
+
+/// Sent instead of the default user agent when `FETCH_BROWSER_USER_AGENT` is enabled, for sites
+/// that block unknown clients.
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36";
+
+/// Downloads resources for the metadata pipeline.
+/// Holds a single HTTP client so connections are pooled across fetches.
+pub struct Fetcher {
+    client: reqwest::Client,
+    guard: Arc<UrlGuard>,
+    limiter: HostLimiter,
+    robots: Option<RobotsCache>,
+    max_body_bytes: usize,
+    max_redirects: usize,
+    /// Whether requests go through `FETCH_PROXY`, which resolves host names itself.
+    proxied: bool,
+    captions: CaptionSettings,
+    rules: FetchRules,
+}
+
+/// Where YouTube caption tracks are downloaded from, and in which language.
+pub struct CaptionSettings {
+    /// Endpoint that caption tracks are requested from, with the track's own query.
+    pub base_url: Url,
+    /// Language of the captions, e.g. `en` or `pt-BR`.
+    pub language: String,
+}
+
+/// A response along with its slot in the per-host concurrency limit, which is held until the
+/// body has been read.
+type Response = (reqwest::Response, OwnedSemaphorePermit);
+
+/// The outcome of checking whether a saved link still works.
+#[derive(Debug, PartialEq)]
+pub enum LinkCheck {
+    /// The server answered with this status.
+    Status(u16),
+    /// The host name no longer resolves.
+    Unresolvable,
+}
+
+/// A downloaded resource, classified by its Content-Type.
+pub enum Document {
+    Html(String),
+    Text(String),
+    /// Anything else (images, PDFs, media, ...). The body is never downloaded.
+    Other {
+        content_type: String,
+        content_length: Option<u64>,
+    },
+}
+
+impl Fetcher {
+    /// Build a fetcher from the `FETCH_*` settings. Fails on an invalid proxy URL, headers,
+    /// captions URL or rules file.
+    pub fn new(config: &Config) -> FetchResult<Self> {
+        let guard = Arc::new(UrlGuard::new(config));
+
+        Ok(Self {
+            client: build_client(config, guard.clone())?,
+            guard,
+            limiter: HostLimiter::new(config),
+            robots: RobotsCache::new(config),
+            max_body_bytes: config
+                .max_fetch_bytes
+                .filter(|&max| max > 0)
+                .unwrap_or(DEFAULT_MAX_BODY_BYTES),
+            max_redirects: config.fetch_max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS),
+            proxied: config.fetch_proxy.as_deref().is_some_and(|p| !p.is_empty()),
+            captions: CaptionSettings {
+                base_url: Url::parse(
+                    config
+                        .youtube_captions_url
+                        .as_deref()
+                        .unwrap_or(DEFAULT_YOUTUBE_CAPTIONS_URL),
+                )?,
+                language: config
+                    .transcript_language
+                    .clone()
+                    .filter(|language| !language.trim().is_empty())
+                    .unwrap_or_else(|| DEFAULT_TRANSCRIPT_LANGUAGE.to_string()),
+            },
+            rules: match config.fetch_rules_file.as_deref().filter(|p| !p.is_empty()) {
+                Some(path) => FetchRules::load(Path::new(path))?,
+                None => FetchRules::default(),
+            },
+        })
+    }
+
+    /// The name of the fetch rule applied to requests for a URL, if any.
+    pub fn rule_name(&self, url: &Url) -> Option<&str> {
+        self.rules.find(url).map(|rule| rule.name.as_str())
+    }
+
+    /// Where YouTube captions are fetched from.
+    pub fn captions(&self) -> &CaptionSettings {
+        &self.captions
+    }
+
+    /// Send a GET request, refusing URLs (and redirects) that point at non-public addresses.
+    async fn get(&self, url: &Url, accept: Option<&str>) -> FetchResult<Response> {
+        self.request(Method::GET, url, accept).await
+    }
+
+    /// Send a request, refusing URLs (and redirects) that point at non-public addresses.
+    async fn request(
+        &self,
+        method: Method,
+        url: &Url,
+        accept: Option<&str>,
+    ) -> FetchResult<Response> {
+        let result = self.send(method, url, accept).await;
+
+        if let Err(e) = &result
+            && let Some(blocked) = find_blocked(e.as_ref())
+        {
+            tracing::warn!("Blocked fetch of {}: {}", url, blocked);
+            return Err(Box::new(blocked.clone()));
+        }
+        result
+    }
+
+    /// Send a request within the per-host limits, checking robots.txt first if enabled.
+    async fn send(&self, method: Method, url: &Url, accept: Option<&str>) -> FetchResult<Response> {
+        self.guard.check_url(url)?;
+        if !self.robots_allowed(url).await {
+            return Err(Box::new(Blocked::Robots));
+        }
+
+        let (response, permit) = self.follow(method, url, accept).await?;
+        Ok((response.error_for_status()?, permit))
+    }
+
+    /// Send a request and follow its redirects. Every hop is checked by the guard and gets the
+    /// headers of its own fetch rule only, so a rule's secrets never follow a redirect to
+    /// another host.
+    async fn follow(
+        &self,
+        method: Method,
+        url: &Url,
+        accept: Option<&str>,
+    ) -> FetchResult<Response> {
+        let mut url = url.clone();
+
+        for _ in 0..=self.max_redirects {
+            if self.proxied {
+                // The proxy resolves host names, so the resolver never sees them
+                self.guard.check_url_resolved(&url).await?;
+            } else {
+                self.guard.check_url(&url)?;
+            }
+            let (response, permit) = self.send_once(method.clone(), &url, accept).await?;
+            match redirect_target(&response) {
+                Some(next) => url = next,
+                None => return Ok((response, permit)),
+            }
+        }
+
+        // Counts as a network error, like the redirect errors of the HTTP client
+        Err(std::io::Error::other(format!("too many redirects from {url}")).into())
+    }
+
+    /// Send a single request, without following redirects.
+    /// A 429 or 503 with a short enough `Retry-After` is retried once after waiting it out.
+    async fn send_once(
+        &self,
+        method: Method,
+        url: &Url,
+        accept: Option<&str>,
+    ) -> FetchResult<Response> {
+        let host = url.host_str().unwrap_or_default();
+        let mut retried = false;
+
+        loop {
+            let permit = self.limiter.acquire(host).await;
+
+            let mut request = self.client.request(method.clone(), url.as_str());
+            if let Some(accept) = accept {
+                request = request.header("Accept", accept);
+            }
+            if let Some(rule) = self.rules.find(url) {
+                tracing::debug!("Applying fetch rule {} to {}", rule.name, url);
+                request = request.headers(rule.headers.clone());
+            }
+            let response = request.send().await?;
+
+            if matches!(
+                response.status(),
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+            ) && let Some(wait) = retry_after(&response)
+            {
+                let max_wait = self.limiter.max_retry_after;
+                self.limiter.back_off(host, wait.min(max_wait));
+
+                if !retried && wait <= max_wait {
+                    tracing::debug!("{} asked us to retry after {:?}", host, wait);
+                    retried = true;
+                    continue;
+                }
+            }
+
+            return Ok((response, permit));
+        }
+    }
+
+    /// Whether robots.txt lets us fetch a URL. Always true unless `FETCH_RESPECT_ROBOTS` is set.
+    /// A robots.txt that is missing or can't be fetched allows everything.
+    async fn robots_allowed(&self, url: &Url) -> bool {
+        let Some(robots) = &self.robots else {
+            return true;
+        };
+
+        let origin = url.origin().ascii_serialization();
+        let robot = match robots.get(&origin) {
+            Some(robot) => robot,
+            None => {
+                let txt = match url.join("/robots.txt") {
+                    Ok(robots_url) => self.fetch_robots_txt(&robots_url).await,
+                    Err(_) => None,
+                };
+                robots.insert(&origin, txt.as_deref())
+            }
+        };
+
+        robot.is_none_or(|robot| robot.allowed(url.as_str()))
+    }
+
+    async fn fetch_robots_txt(&self, robots_url: &Url) -> Option<Vec<u8>> {
+        if self.guard.check_url(robots_url).is_err() {
+            return None;
+        }
+
+        let (response, _permit) = self.follow(Method::GET, robots_url, None).await.ok()?;
+        let response = response.error_for_status().ok()?;
+        let (bytes, _) = self.read_body(response).await.ok()?;
+        Some(bytes)
+    }
+
+    /// Download a resource, only reading the body if it is HTML or text.
+    /// Also returns the URL it was served from, after redirects.
+    pub async fn fetch_document(&self, url: &Url) -> FetchResult<(Document, Url)> {
+        let (response, _permit) = self.get(url, None).await?;
+        let content_type = content_type(&response);
+        let final_url = response.url().clone();
+
+        let document = match classify(content_type.as_deref()) {
+            Kind::Html => {
+                let (bytes, charset) = self.read_body(response).await?;
+                Document::Html(decode(&bytes, charset.as_deref(), true))
+            }
+            Kind::Text => {
+                let (bytes, charset) = self.read_body(response).await?;
+                Document::Text(decode(&bytes, charset.as_deref(), false))
+            }
+            Kind::Other => Document::Other {
+                content_length: response.content_length(),
+                content_type: content_type.unwrap_or_default(),
+            },
+        };
+
+        Ok((document, final_url))
+    }
+
+    /// Download a resource and return its body as text, whatever its Content-Type.
+    pub async fn fetch_text(&self, url: &Url) -> FetchResult<String> {
+        let (response, _permit) = self.get(url, None).await?;
+        let is_html = matches!(classify(content_type(&response).as_deref()), Kind::Html);
+        let (bytes, charset) = self.read_body(response).await?;
+
+        Ok(decode(&bytes, charset.as_deref(), is_html))
+    }
+
+    /// Download a JSON document and deserialize it.
+    pub async fn fetch_json<T: DeserializeOwned>(&self, url: &Url, accept: &str) -> FetchResult<T> {
+        let (response, _permit) = self.get(url, Some(accept)).await?;
+        let (bytes, _) = self.read_body(response).await?;
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Check whether a URL still works, without downloading its body. Tries a HEAD request
+    /// first and falls back to GET for servers that don't support HEAD.
+    pub async fn check_link(&self, url: &Url) -> FetchResult<LinkCheck> {
+        let result = match self.status(Method::HEAD, url).await {
+            Ok(StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED) => {
+                self.status(Method::GET, url).await
+            }
+            result => result,
+        };
+
+        match result {
+            Ok(status) => Ok(LinkCheck::Status(status.as_u16())),
+            Err(e) if is_connect_error(e.as_ref()) && !resolves(url).await => {
+                Ok(LinkCheck::Unresolvable)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The status a request is answered with, including error statuses.
+    async fn status(&self, method: Method, url: &Url) -> FetchResult<StatusCode> {
+        match self.request(method, url, None).await {
+            Ok((response, _permit)) => Ok(response.status()),
+            Err(e) => match e.downcast_ref::<reqwest::Error>().and_then(|e| e.status()) {
+                Some(status) => Ok(status),
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Download a resource as raw bytes, along with its media type.
+    pub async fn fetch_bytes(&self, url: &Url) -> FetchResult<(Vec<u8>, Option<String>)> {
+        let (response, _permit) = self.get(url, None).await?;
+        let content_type = content_type(&response);
+        let (bytes, _) = self.read_body(response).await?;
+
+        Ok((bytes, content_type))
+    }
+
+    /// Download a resource of at most `max_bytes`, along with its media type.
+    /// Fails as soon as the body turns out to be larger, rather than truncating it.
+    pub async fn fetch_bytes_limited(
+        &self,
+        url: &Url,
+        max_bytes: usize,
+    ) -> FetchResult<(Vec<u8>, Option<String>)> {
+        let (mut response, _permit) = self.get(url, None).await?;
+        let too_large = || format!("{url} is larger than {max_bytes} bytes");
+
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_bytes as u64)
+        {
+            return Err(too_large().into());
+        }
+
+        let content_type = content_type(&response);
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if bytes.len() + chunk.len() > max_bytes {
+                return Err(too_large().into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        Ok((bytes, content_type))
+    }
+
+    /// Stream the body into memory, stopping at the size limit.
+    /// Returns the bytes read and the charset declared in the Content-Type header, if any.
+    async fn read_body(
+        &self,
+        mut response: reqwest::Response,
+    ) -> FetchResult<(Vec<u8>, Option<String>)> {
+        let charset = content_type_charset(&response);
+        let mut bytes = Vec::new();
+
+        while let Some(chunk) = response.chunk().await? {
+            let remaining = self.max_body_bytes - bytes.len();
+            if chunk.len() >= remaining {
+                bytes.extend_from_slice(&chunk[..remaining]);
+                tracing::debug!(
+                    "Response from {} exceeds {} bytes, truncating",
+                    response.url(),
+                    self.max_body_bytes
+                );
+                break;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        Ok((bytes, charset))
+    }
+}
+
+/// Redirects are followed by [`Fetcher::follow`], which checks each hop.
+fn build_client(config: &Config, guard: Arc<UrlGuard>) -> FetchResult<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(
+            config.fetch_timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS),
+        ))
+        .user_agent(user_agent(config))
+        .default_headers(default_headers(config.fetch_headers.as_deref())?)
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(GuardedResolver(guard));
+
+    if let Some(secs) = config.fetch_connect_timeout_secs {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(proxy) = config.fetch_proxy.as_deref().filter(|p| !p.is_empty()) {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Where a response redirects to, if it is a redirect with a usable `Location`.
+fn redirect_target(response: &reqwest::Response) -> Option<Url> {
+    if !matches!(
+        response.status(),
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+/// The configured user agent, then the browser-like one if opted in, then our own.
+fn user_agent(config: &Config) -> &str {
+    match config
+        .fetch_user_agent
+        .as_deref()
+        .filter(|ua| !ua.is_empty())
+    {
+        Some(user_agent) => user_agent,
+        None if config.fetch_browser_user_agent.unwrap_or(false) => BROWSER_USER_AGENT,
+        None => DEFAULT_USER_AGENT,
+    }
+}
+
+/// Parse extra headers sent with every request, given as a JSON object of names to values.
+fn default_headers(json: Option<&str>) -> FetchResult<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    let Some(json) = json.filter(|json| !json.trim().is_empty()) else {
+        return Ok(headers);
+    };
+
+    let values: HashMap<String, String> =
+        serde_json::from_str(json).map_err(|e| format!("invalid FETCH_HEADERS: {e}"))?;
+    for (name, value) in values {
+        headers.insert(
+            HeaderName::try_from(name.as_str())?,
+            HeaderValue::try_from(value)?,
+        );
+    }
+    Ok(headers)
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, chrono::Utc::now())
+}
+
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Html,
+    Text,
+    Other,
+}
+
+/// The media type of a response, lowercased and without parameters.
+fn content_type(response: &reqwest::Response) -> Option<String> {
+    let value = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?;
+    let essence = value.split(';').next()?.trim().to_ascii_lowercase();
+    (!essence.is_empty()).then_some(essence)
+}
+
+fn content_type_charset(response: &reqwest::Response) -> Option<String> {
+    let value = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?;
+    charset_param(value)
+}
+
+/// Classify a media type. A missing Content-Type is treated as HTML, as browsers would sniff it.
+fn classify(content_type: Option<&str>) -> Kind {
+    match content_type {
+        None | Some("text/html" | "application/xhtml+xml") => Kind::Html,
+        Some(t) if t.starts_with("text/") => Kind::Text,
+        Some(t) if t == "application/json" || t.ends_with("+json") || t.ends_with("+xml") => {
+            Kind::Text
+        }
+        Some("application/xml") => Kind::Text,
+        Some(_) => Kind::Other,
+    }
+}
+
+/// The `charset` parameter of a Content-Type value.
+fn charset_param(value: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']).to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// Decode a body using, in order of precedence, its byte order mark, the charset from the
+/// Content-Type header and (for HTML) a `<meta>` charset declaration. Defaults to UTF-8.
+fn decode(bytes: &[u8], header_charset: Option<&str>, is_html: bool) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| header_charset.and_then(|label| Encoding::for_label(label.as_bytes())))
+        .or_else(|| {
+            is_html
+                .then(|| meta_charset(bytes))
+                .flatten()
+                .and_then(|label| Encoding::for_label(label.as_bytes()))
+        })
+        .unwrap_or(UTF_8);
+
+    // A UTF-16 meta declaration in an ASCII-compatible document is always wrong
+    let encoding = encoding.output_encoding();
+
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// Look for `<meta charset="...">` or `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// near the start of an HTML document.
+fn meta_charset(bytes: &[u8]) -> Option<String> {
+    let head = &bytes[..bytes.len().min(META_CHARSET_PRESCAN_BYTES)];
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+
+    head.match_indices("<meta").find_map(|(start, _)| {
+        let tag = &head[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let (_, rest) = tag.split_once("charset=")?;
+        let label: String = rest
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| !matches!(c, '"' | '\'' | ';' | ' ' | '/' | '>'))
+            .collect();
+        (!label.is_empty()).then_some(label)
+    })
+}
+
+fn is_connect_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect())
+}
+
+/// Whether a URL's host still resolves to any address.
+async fn resolves(url: &Url) -> bool {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    tokio::net::lookup_host((host, port))
+        .await
+        .is_ok_and(|mut addrs| addrs.next().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_content_types() {
+        assert_eq!(classify(None), Kind::Html);
+        assert_eq!(classify(Some("text/html")), Kind::Html);
+        assert_eq!(classify(Some("application/xhtml+xml")), Kind::Html);
+        assert_eq!(classify(Some("text/plain")), Kind::Text);
+        assert_eq!(classify(Some("application/atom+xml")), Kind::Text);
+        assert_eq!(classify(Some("application/json")), Kind::Text);
+        assert_eq!(classify(Some("application/pdf")), Kind::Other);
+        assert_eq!(classify(Some("image/png")), Kind::Other);
+    }
+
+    #[test]
+    fn charset_param_parses_quoted_and_unquoted_values() {
+        assert_eq!(
+            charset_param("text/html; charset=ISO-8859-1"),
+            Some("ISO-8859-1".to_string())
+        );
+        assert_eq!(
+            charset_param(r#"text/html;Charset="utf-8""#),
+            Some("utf-8".to_string())
+        );
+        assert_eq!(charset_param("text/html"), None);
+    }
+
+    #[test]
+    fn decode_uses_header_charset() {
+        let bytes = b"caf\xe9";
+        assert_eq!(decode(bytes, Some("iso-8859-1"), false), "café");
+    }
+
+    #[test]
+    fn decode_uses_meta_charset_for_html() {
+        let bytes = b"<html><head><meta charset=\"windows-1252\"><title>caf\xe9</title>";
+        assert!(decode(bytes, None, true).contains("café"));
+
+        let bytes =
+            b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-15\">\xa4";
+        assert!(decode(bytes, None, true).ends_with('€'));
+    }
+
+    #[test]
+    fn decode_ignores_meta_charset_for_plain_text() {
+        let bytes = "<meta charset=\"windows-1252\"> café".as_bytes();
+        assert!(decode(bytes, None, false).ends_with("café"));
+    }
+
+    #[test]
+    fn decode_prefers_bom_over_header() {
+        let bytes = b"\xef\xbb\xbfcaf\xc3\xa9";
+        assert_eq!(decode(bytes, Some("iso-8859-1"), false), "café");
+    }
+
+    #[test]
+    fn decode_defaults_to_utf8() {
+        assert_eq!(decode("naïve".as_bytes(), None, true), "naïve");
+    }
+
+    #[test]
+    fn meta_charset_ignores_tags_without_charset() {
+        assert_eq!(
+            meta_charset(b"<meta name=\"description\" content=\"x\"><meta charset=utf-8>"),
+            Some("utf-8".to_string())
+        );
+        assert_eq!(meta_charset(b"<html><head><title>x</title>"), None);
+    }
+
+    #[tokio::test]
+    async fn fetch_refuses_loopback_addresses() {
+        let fetcher = Fetcher::new(&Config::default()).unwrap();
+        let url = Url::parse("http://127.0.0.1:9/").unwrap();
+
+        let error = fetcher.fetch_text(&url).await.unwrap_err();
+        assert!(error.downcast_ref::<Blocked>().is_some());
+
+        let url = Url::parse("http://localhost:9/").unwrap();
+        let error = fetcher.fetch_text(&url).await.unwrap_err();
+        assert!(error.downcast_ref::<Blocked>().is_some());
+    }
+
+    #[tokio::test]
+    async fn fetch_refuses_redirects_to_non_public_addresses() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/latest/meta-data/\r\nContent-Length: 0\r\n\r\n",
+                )
+                .await;
+        });
+
+        let fetcher = Fetcher::new(&Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap();
+
+        let error = fetcher.fetch_text(&url).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Blocked>(),
+            Some(Blocked::NonPublic { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetch_through_proxy_checks_resolved_addresses() {
+        let fetcher = Fetcher::new(&Config {
+            fetch_proxy: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = Url::parse("http://localhost:8080/").unwrap();
+
+        let error = fetcher.fetch_text(&url).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Blocked>(),
+            Some(Blocked::NonPublic { .. })
+        ));
+    }
+
+    #[test]
+    fn user_agent_precedence() {
+        assert_eq!(user_agent(&Config::default()), DEFAULT_USER_AGENT);
+
+        let config = Config {
+            fetch_browser_user_agent: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(user_agent(&config), BROWSER_USER_AGENT);
+
+        let config = Config {
+            fetch_user_agent: Some("MyBot/2.0".to_string()),
+            fetch_browser_user_agent: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(user_agent(&config), "MyBot/2.0");
+    }
+
+    #[test]
+    fn default_headers_from_json() {
+        let headers =
+            default_headers(Some(r#"{"Accept-Language": "en-US,en;q=0.9", "DNT": "1"}"#)).unwrap();
+
+        assert_eq!(headers["accept-language"], "en-US,en;q=0.9");
+        assert_eq!(headers["dnt"], "1");
+        assert!(default_headers(None).unwrap().is_empty());
+        assert!(default_headers(Some("Accept-Language: en")).is_err());
+        assert!(default_headers(Some(r#"{"Bad Header": "x"}"#)).is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_configured_user_agent_and_headers() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Echo the request back as the response body
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {n}\r\n\r\n"
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.write_all(&buf[..n]).await.unwrap();
+        });
+
+        let fetcher = Fetcher::new(&Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            fetch_user_agent: Some("MyBot/2.0".to_string()),
+            fetch_headers: Some(r#"{"X-Test": "yes"}"#.to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap();
+
+        let request = fetcher.fetch_text(&url).await.unwrap().to_ascii_lowercase();
+        assert!(request.contains("user-agent: mybot/2.0"));
+        assert!(request.contains("x-test: yes"));
+    }
+
+    #[tokio::test]
+    async fn fetch_applies_matching_rule() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Echo the request back as the response body
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {n}\r\n\r\n"
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.write_all(&buf[..n]).await.unwrap();
+        });
+
+        let rules_file = std::env::temp_dir().join(format!("laterfeed-rules-{port}.json"));
+        std::fs::write(
+            &rules_file,
+            r#"[{"name": "local", "hosts": ["127.0.0.1"],
+                "headers": {"Authorization": "Bearer s3cret"}, "cookies": {"session": "abc"}}]"#,
+        )
+        .unwrap();
+        let fetcher = Fetcher::new(&Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            fetch_rules_file: Some(rules_file.to_string_lossy().into_owned()),
+            ..Default::default()
+        })
+        .unwrap();
+        std::fs::remove_file(rules_file).unwrap();
+
+        let url = Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap();
+        assert_eq!(fetcher.rule_name(&url), Some("local"));
+
+        let request = fetcher.fetch_text(&url).await.unwrap().to_ascii_lowercase();
+        assert!(request.contains("authorization: bearer s3cret"));
+        assert!(request.contains("cookie: session=abc"));
+    }
+
+    #[tokio::test]
+    async fn fetch_retries_after_429_with_retry_after() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for response in [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await;
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let fetcher = Fetcher::new(&Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            fetch_host_delay_ms: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap();
+
+        let start = std::time::Instant::now();
+        assert_eq!(fetcher.fetch_text(&url).await.unwrap(), "ok");
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn check_link_falls_back_to_get_and_reports_status() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 1024];
+                let n = socket.read(&mut buf).await.unwrap();
+                let response: &[u8] = if buf[..n].starts_with(b"HEAD") {
+                    b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 410 Gone\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                };
+                socket.write_all(response).await.unwrap();
+            }
+        });
+
+        let fetcher = Fetcher::new(&Config {
+            fetch_allowlist: Some("127.0.0.1".to_string()),
+            fetch_host_delay_ms: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap();
+
+        assert_eq!(
+            fetcher.check_link(&url).await.unwrap(),
+            LinkCheck::Status(410)
+        );
+    }
+
+    #[tokio::test]
+    async fn check_link_detects_unresolvable_hosts() {
+        let fetcher = Fetcher::new(&Config::default()).unwrap();
+        let url = Url::parse("https://laterfeed-test.invalid/").unwrap();
+
+        assert_eq!(
+            fetcher.check_link(&url).await.unwrap(),
+            LinkCheck::Unresolvable
+        );
+    }
+}
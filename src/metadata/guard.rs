@@ -0,0 +1,488 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use url::{Host, Url};
+
+use crate::config::Config;
+
+/// Why a URL was refused.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum Blocked {
+    #[error("unsupported URL scheme `{0}`")]
+    Scheme(String),
+
+    #[error("host {0} is on the fetch denylist")]
+    Denied(String),
+
+    #[error("host {host} resolves to non-public address {addr}")]
+    NonPublic { host: String, addr: IpAddr },
+
+    #[error("disallowed by robots.txt")]
+    Robots,
+}
+
+/// An entry in the fetch allowlist or denylist.
+#[derive(Debug, PartialEq)]
+enum Rule {
+    /// A host name, matching the host itself and all of its subdomains.
+    Host(String),
+    /// An IP address or CIDR range.
+    Net(IpNet),
+}
+
+impl Rule {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if value.is_empty() {
+            return None;
+        }
+
+        if let Ok(net) = value.parse::<IpNet>() {
+            return Some(Rule::Net(net));
+        }
+        if let Ok(ip) = value.parse::<IpAddr>() {
+            return Some(Rule::Net(IpNet::from(ip)));
+        }
+
+        let host = value.trim_start_matches("*.").trim_start_matches('.');
+        Some(Rule::Host(host.to_string()))
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        match self {
+            Rule::Host(rule) => {
+                host == rule
+                    || host
+                        .strip_suffix(rule.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            Rule::Net(_) => false,
+        }
+    }
+
+    fn matches_ip(&self, ip: IpAddr) -> bool {
+        match self {
+            Rule::Net(net) => net.contains(&ip),
+            Rule::Host(_) => false,
+        }
+    }
+}
+
+/// Decides which hosts and addresses the server may fetch from, to keep saved URLs from
+/// reaching loopback, link-local, private or multicast addresses.
+#[derive(Debug, Default)]
+pub struct UrlGuard {
+    /// Hosts and networks that may be fetched even though they aren't public.
+    allow: Vec<Rule>,
+    /// Hosts and networks that are never fetched.
+    deny: Vec<Rule>,
+    /// Host of `FETCH_PROXY`, which may be connected to but not fetched from.
+    proxy: Option<Rule>,
+}
+
+impl UrlGuard {
+    pub fn new(config: &Config) -> Self {
+        // The proxy itself is usually on the local network, and has to be reachable
+        let proxy = config
+            .fetch_proxy
+            .as_deref()
+            .and_then(|proxy| Url::parse(proxy).ok())
+            .and_then(|proxy| proxy.host_str().and_then(Rule::parse));
+
+        Self {
+            allow: parse_rules(config.fetch_allowlist.as_deref()),
+            deny: parse_rules(config.fetch_denylist.as_deref()),
+            proxy,
+        }
+    }
+
+    /// A guard for clients that connect directly, without going through `FETCH_PROXY`.
+    pub fn direct(config: &Config) -> Self {
+        Self {
+            proxy: None,
+            ..Self::new(config)
+        }
+    }
+
+    /// Check everything that can be checked without DNS: the scheme, host rules and IP literals.
+    /// Host names that pass still need their resolved addresses checked.
+    pub fn check_url(&self, url: &Url) -> Result<(), Blocked> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(Blocked::Scheme(url.scheme().to_string()));
+        }
+
+        match url.host() {
+            Some(Host::Domain(domain)) => self.check_domain(domain).map(|_| ()),
+            Some(Host::Ipv4(ip)) => self.check_ip(&ip.to_string(), IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => self.check_ip(&ip.to_string(), IpAddr::V6(ip)),
+            None => Err(Blocked::Scheme(url.scheme().to_string())),
+        }
+    }
+
+    /// Like [`check_url`](Self::check_url), but also checks the addresses a host name resolves
+    /// to now, for URLs that are stored to be requested later. Names that don't resolve are
+    /// left to [`GuardedResolver`] when connecting.
+    pub async fn check_url_resolved(&self, url: &Url) -> Result<(), Blocked> {
+        self.check_url(url)?;
+        if let Some(Host::Domain(domain)) = url.host()
+            && let Ok(addrs) = tokio::net::lookup_host((domain, 0)).await
+        {
+            self.filter_addrs(domain, addrs)?;
+        }
+        Ok(())
+    }
+
+    /// Check a host name against the host rules.
+    /// Returns whether it is explicitly allowed, in which case its addresses aren't checked.
+    fn check_domain(&self, domain: &str) -> Result<bool, Blocked> {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+
+        if self.deny.iter().any(|rule| rule.matches_host(&domain)) {
+            return Err(Blocked::Denied(domain));
+        }
+        Ok(self.allow.iter().any(|rule| rule.matches_host(&domain)))
+    }
+
+    /// Check an address that `host` resolved to (or is).
+    fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), Blocked> {
+        if self.deny.iter().any(|rule| rule.matches_ip(ip)) {
+            return Err(Blocked::Denied(host.to_string()));
+        }
+        if self.allow.iter().any(|rule| rule.matches_ip(ip)) || is_public(ip) {
+            return Ok(());
+        }
+
+        Err(Blocked::NonPublic {
+            host: host.to_string(),
+            addr: ip,
+        })
+    }
+
+    /// Keep only the addresses of `host` that may be connected to.
+    /// Fails with the first reason an address was refused when none are left.
+    fn filter_addrs(
+        &self,
+        host: &str,
+        addrs: impl IntoIterator<Item = SocketAddr>,
+    ) -> Result<Vec<SocketAddr>, Blocked> {
+        let is_proxy = self.proxy.as_ref().is_some_and(|proxy| {
+            proxy.matches_host(&host.trim_end_matches('.').to_ascii_lowercase())
+        });
+        if is_proxy {
+            return Ok(addrs.into_iter().collect());
+        }
+        let allowed = self.check_domain(host)?;
+        let mut refused = None;
+
+        let addrs: Vec<SocketAddr> = addrs
+            .into_iter()
+            .filter(|addr| {
+                allowed
+                    || match self.check_ip(host, addr.ip()) {
+                        Ok(()) => true,
+                        Err(e) => {
+                            refused.get_or_insert(e);
+                            false
+                        }
+                    }
+            })
+            .collect();
+
+        match refused {
+            Some(e) if addrs.is_empty() => Err(e),
+            _ => Ok(addrs),
+        }
+    }
+}
+
+fn parse_rules(value: Option<&str>) -> Vec<Rule> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(Rule::parse)
+        .collect()
+}
+
+/// Whether an address is routable on the public internet.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+/// The IPv4 address that an IPv6 address reaches: IPv4-mapped (`::ffff:0:0/96`),
+/// IPv4-compatible (`::/96`), NAT64 (`64:ff9b::/96`) and 6to4 (`2002::/16`) addresses all lead
+/// to one.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    // Also covers `::` and `::1`, which land in 0.0.0.0/8
+    if let Some(ip) = ip.to_ipv4() {
+        return Some(ip);
+    }
+
+    let segments = ip.segments();
+    let octets = ip.octets();
+    match segments {
+        [0x64, 0xff9b, 0, 0, 0, 0, ..] => Some(Ipv4Addr::new(
+            octets[12], octets[13], octets[14], octets[15],
+        )),
+        [0x2002, ..] => Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5])),
+        _ => None,
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        // "This network" (0.0.0.0/8)
+        || a == 0
+        // Carrier-grade NAT (100.64.0.0/10)
+        || (a == 100 && (b & 0xc0) == 64)
+        // Benchmarking (198.18.0.0/15)
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved (240.0.0.0/4)
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local (fc00::/7)
+        || (first & 0xfe00) == 0xfc00
+        // Link-local (fe80::/10) and deprecated site-local (fec0::/10)
+        || (first & 0xffc0) == 0xfe80
+        || (first & 0xffc0) == 0xfec0)
+}
+
+/// DNS resolver that drops addresses the guard refuses, so the check also covers redirects
+/// and can't be bypassed by a host name that resolves differently on a second lookup.
+pub struct GuardedResolver(pub Arc<UrlGuard>);
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let guard = self.0.clone();
+
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs = tokio::net::lookup_host((host, 0)).await?;
+            let addrs: Addrs = Box::new(guard.filter_addrs(host, addrs)?.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Find a `Blocked` error anywhere in an error's source chain.
+pub fn find_blocked<'a>(error: &'a (dyn std::error::Error + 'static)) -> Option<&'a Blocked> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(blocked) = error.downcast_ref::<Blocked>() {
+            return Some(blocked);
+        }
+        current = error.source();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(allow: &str, deny: &str) -> UrlGuard {
+        UrlGuard::new(&Config {
+            fetch_allowlist: Some(allow.to_string()),
+            fetch_denylist: Some(deny.to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn check(guard: &UrlGuard, url: &str) -> Result<(), Blocked> {
+        guard.check_url(&Url::parse(url).unwrap())
+    }
+
+    fn addr(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 0)
+    }
+
+    #[test]
+    fn non_public_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "224.0.0.1",
+            "0.0.0.0",
+            "100.64.0.1",
+            "198.18.0.1",
+            "198.19.255.255",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "::10.0.0.1",
+            // NAT64 of 10.0.0.1 and 6to4 of 127.0.0.1
+            "64:ff9b::a00:1",
+            "2002:7f00:1::",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip} should not be public");
+        }
+
+        for ip in [
+            "93.184.216.34",
+            "1.1.1.1",
+            "198.20.0.1",
+            "::1.1.1.1",
+            "2606:4700::1111",
+            "64:ff9b::101:101",
+            "2002:101:101::1",
+        ] {
+            assert!(is_public(ip.parse().unwrap()), "{ip} should be public");
+        }
+    }
+
+    #[test]
+    fn check_url_blocks_ip_literals_and_other_schemes() {
+        let guard = UrlGuard::default();
+
+        assert!(matches!(
+            check(&guard, "http://127.0.0.1:8080/admin"),
+            Err(Blocked::NonPublic { .. })
+        ));
+        assert!(matches!(
+            check(&guard, "http://[::1]/"),
+            Err(Blocked::NonPublic { .. })
+        ));
+        assert!(matches!(
+            check(&guard, "http://169.254.169.254/latest/meta-data/"),
+            Err(Blocked::NonPublic { .. })
+        ));
+        assert_eq!(
+            check(&guard, "file:///etc/passwd"),
+            Err(Blocked::Scheme("file".to_string()))
+        );
+        assert_eq!(check(&guard, "https://example.com/"), Ok(()));
+        assert_eq!(check(&guard, "http://1.1.1.1/"), Ok(()));
+    }
+
+    #[test]
+    fn allowlist_permits_private_hosts_and_networks() {
+        let guard = guard("wiki.internal, 10.0.0.0/8", "");
+
+        assert_eq!(check(&guard, "http://10.1.2.3/"), Ok(()));
+        assert!(check(&guard, "http://192.168.1.1/").is_err());
+        assert_eq!(
+            guard.filter_addrs("docs.wiki.internal", [addr("192.168.1.5")]),
+            Ok(vec![addr("192.168.1.5")])
+        );
+    }
+
+    #[test]
+    fn denylist_wins_over_allowlist() {
+        let guard = guard(
+            "example.com, 93.184.216.0/24",
+            "*.tracker.example.com, 93.184.216.34",
+        );
+
+        assert_eq!(
+            check(&guard, "https://ads.tracker.example.com/"),
+            Err(Blocked::Denied("ads.tracker.example.com".to_string()))
+        );
+        assert!(matches!(
+            check(&guard, "http://93.184.216.34/"),
+            Err(Blocked::Denied(_))
+        ));
+        assert_eq!(check(&guard, "https://www.example.com/"), Ok(()));
+        assert_eq!(check(&guard, "https://notexample.com/"), Ok(()));
+    }
+
+    #[test]
+    fn filter_addrs_drops_non_public_addresses() {
+        let guard = UrlGuard::default();
+
+        assert_eq!(
+            guard.filter_addrs("mixed.example", [addr("10.0.0.1"), addr("1.1.1.1")]),
+            Ok(vec![addr("1.1.1.1")])
+        );
+        assert_eq!(
+            guard.filter_addrs("internal.example", [addr("10.0.0.1")]),
+            Err(Blocked::NonPublic {
+                host: "internal.example".to_string(),
+                addr: "10.0.0.1".parse().unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn proxy_host_is_connected_to_but_not_fetched() {
+        let guard = UrlGuard::new(&Config {
+            fetch_proxy: Some("http://proxy.lan:3128".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            guard.filter_addrs("proxy.lan", [addr("10.0.0.5")]),
+            Ok(vec![addr("10.0.0.5")])
+        );
+        assert!(guard.filter_addrs("other.lan", [addr("10.0.0.5")]).is_err());
+
+        let guard = UrlGuard::new(&Config {
+            fetch_proxy: Some("http://10.0.0.5:3128".to_string()),
+            ..Default::default()
+        });
+        assert!(check(&guard, "http://10.0.0.5/").is_err());
+        assert!(check(&guard, "http://10.0.0.6/").is_err());
+
+        let guard = UrlGuard::direct(&Config {
+            fetch_proxy: Some("http://proxy.lan:3128".to_string()),
+            ..Default::default()
+        });
+        assert!(guard.filter_addrs("proxy.lan", [addr("10.0.0.5")]).is_err());
+    }
+
+    #[tokio::test]
+    async fn check_url_resolved_checks_resolved_addresses() {
+        let strict = guard("", "");
+        let url = Url::parse("http://localhost:8080/hook").unwrap();
+
+        assert!(strict.check_url(&url).is_ok());
+        assert!(matches!(
+            strict.check_url_resolved(&url).await,
+            Err(Blocked::NonPublic { .. })
+        ));
+        assert!(
+            guard("localhost", "")
+                .check_url_resolved(&url)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn parse_rules_skips_empty_entries() {
+        assert_eq!(
+            parse_rules(Some(" 10.0.0.1, ,.Example.com ")),
+            vec![
+                Rule::Net("10.0.0.1/32".parse().unwrap()),
+                Rule::Host("example.com".to_string()),
+            ]
+        );
+        assert!(parse_rules(None).is_empty());
+    }
+}
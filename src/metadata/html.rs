@@ -0,0 +1,550 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::metadata::{
+    FetchResult, Fetcher, PageMetadata, extractors::Extractor, fetch::Document, non_empty, oembed,
+    parse_date, resource::resource_metadata,
+};
+use crate::models::EntrySourceType;
+
+/// Generic extractor that scrapes the title and body from a page's HTML.
+/// Matches every URL, so it is used as the fallback when no site-specific extractor applies.
+/// Non-HTML resources (images, PDFs, plain text, ...) get metadata derived from the file itself.
+pub struct HtmlExtractor;
+
+#[async_trait]
+impl Extractor for HtmlExtractor {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn matches(&self, _url: &Url) -> bool {
+        true
+    }
+
+    async fn extract(&self, fetcher: &Fetcher, url: &Url) -> FetchResult<PageMetadata> {
+        let (html, final_url) = match fetcher.fetch_document(url).await? {
+            (Document::Html(html), final_url) => (html, final_url),
+            (other, final_url) => {
+                return Ok(PageMetadata {
+                    final_url: Some(final_url.into()),
+                    ..resource_metadata(url, &other)
+                });
+            }
+        };
+        let (mut page, discovered) = parse_page_and_oembed(&html, url);
+        page.final_url = Some(final_url.into());
+
+        // Known providers first, then whatever endpoint the page advertises
+        let Some(endpoint) = oembed::provider_endpoint(url).or(discovered) else {
+            return Ok(page);
+        };
+
+        match oembed::fetch(fetcher, &endpoint).await {
+            Ok(embed) => Ok(oembed::merge(page, embed)),
+            Err(e) => {
+                tracing::warn!("Failed to fetch oEmbed {} for {}: {}", endpoint, url, e);
+                Ok(page)
+            }
+        }
+    }
+}
+
+/// Parse a downloaded HTML page into its metadata. Relative URLs are resolved against `url`.
+pub fn parse_page(html: &str, url: &Url) -> PageMetadata {
+    page_metadata(&Html::parse_document(html), url)
+}
+
+/// Parse a downloaded HTML page, also returning its advertised oEmbed endpoint if any.
+fn parse_page_and_oembed(html: &str, url: &Url) -> (PageMetadata, Option<Url>) {
+    let document = Html::parse_document(html);
+    (
+        page_metadata(&document, url),
+        oembed::discover(&document, url),
+    )
+}
+
+fn page_metadata(document: &Html, url: &Url) -> PageMetadata {
+    PageMetadata {
+        title: extract_title(document),
+        body: extract_body(document),
+        source_type: detect_source_type(document),
+        author: extract_author(document),
+        published_at: extract_published_at(document),
+        site_name: meta_content(
+            document,
+            r#"meta[property="og:site_name"], meta[name="application-name"]"#,
+        ),
+        canonical_url: extract_canonical_url(document, url),
+        image_url: extract_image_url(document, url),
+        language: extract_language(document),
+        ..Default::default()
+    }
+}
+
+/// Read the `content` attribute of the first `<meta>` tag matching the selector, if non-empty.
+fn meta_content(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    document
+        .select(&selector)
+        .filter_map(|el| el.value().attr("content"))
+        .find_map(non_empty)
+}
+
+/// Read the `href` of the first `<link>` matching the selector, resolved against the page URL.
+fn link_href(document: &Html, selector: &str, url: &Url) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    document
+        .select(&selector)
+        .filter_map(|el| el.value().attr("href"))
+        .find_map(|href| resolve_url(href, url))
+}
+
+/// Resolve a possibly-relative URL found in a page to an absolute http(s) URL.
+fn resolve_url(value: &str, base: &Url) -> Option<String> {
+    let resolved = base.join(value.trim()).ok()?;
+    matches!(resolved.scheme(), "http" | "https").then(|| resolved.to_string())
+}
+
+fn extract_author(document: &Html) -> Option<String> {
+    if let Some(author) = meta_content(document, r#"meta[name="author"]"#) {
+        return Some(author);
+    }
+
+    // article:author is often a profile URL rather than a name
+    if let Some(author) = meta_content(document, r#"meta[property="article:author"]"#)
+        && Url::parse(&author).is_err()
+    {
+        return Some(author);
+    }
+
+    let selector = Selector::parse(r#"[rel~="author"], [itemprop="author"]"#).ok()?;
+    document
+        .select(&selector)
+        .map(|el| el.text().collect::<String>())
+        .find_map(|text| non_empty(&text))
+}
+
+fn extract_published_at(document: &Html) -> Option<DateTime<Utc>> {
+    let from_meta = meta_content(
+        document,
+        r#"meta[property="article:published_time"], meta[itemprop="datePublished"], meta[name="date"], meta[name="dc.date"], meta[name="DC.date.issued"]"#,
+    );
+    if let Some(date) = from_meta.as_deref().and_then(parse_date) {
+        return Some(date);
+    }
+
+    let selector = Selector::parse("time[datetime]").ok()?;
+    document
+        .select(&selector)
+        .filter_map(|el| el.value().attr("datetime"))
+        .find_map(parse_date)
+}
+
+fn extract_canonical_url(document: &Html, url: &Url) -> Option<String> {
+    link_href(document, r#"link[rel~="canonical"]"#, url).or_else(|| {
+        meta_content(document, r#"meta[property="og:url"]"#).and_then(|og| resolve_url(&og, url))
+    })
+}
+
+fn extract_image_url(document: &Html, url: &Url) -> Option<String> {
+    meta_content(
+        document,
+        r#"meta[property="og:image:secure_url"], meta[property="og:image"], meta[property="og:image:url"], meta[name="twitter:image"], meta[name="twitter:image:src"]"#,
+    )
+    .and_then(|image| resolve_url(&image, url))
+}
+
+/// The page language from `<html lang>`, falling back to Content-Language and og:locale.
+fn extract_language(document: &Html) -> Option<String> {
+    let html_lang = Selector::parse("html[lang]").ok().and_then(|selector| {
+        document
+            .select(&selector)
+            .filter_map(|el| el.value().attr("lang"))
+            .find_map(non_empty)
+    });
+
+    html_lang
+        .or_else(|| meta_content(document, r#"meta[http-equiv="content-language" i]"#))
+        .or_else(|| meta_content(document, r#"meta[property="og:locale"]"#))
+        .map(|lang| lang.replace('_', "-"))
+}
+
+/// Infer the source type from OpenGraph tags.
+/// A `video.*` `og:type` or any `og:video` tag marks a video; any other `og:type` an article.
+fn detect_source_type(document: &Html) -> Option<EntrySourceType> {
+    let og_type = meta_content(document, r#"meta[property="og:type"]"#);
+    let has_og_video = meta_content(
+        document,
+        r#"meta[property="og:video"], meta[property="og:video:url"], meta[property="og:video:secure_url"]"#,
+    )
+    .is_some();
+
+    if has_og_video || og_type.as_deref().is_some_and(|t| t.starts_with("video")) {
+        return Some(EntrySourceType::Video);
+    }
+
+    og_type.map(|_| EntrySourceType::Article)
+}
+
+fn extract_title(document: &Html) -> Option<String> {
+    // Try <meta property="og:title"> first, then fall back to <title>
+    let og_title_selector = Selector::parse(r#"meta[property="og:title"]"#).ok()?;
+    if let Some(element) = document.select(&og_title_selector).next()
+        && let Some(content) = element.value().attr("content")
+    {
+        let trimmed = content.trim();
+        if !trimmed.is_empty() {
+            return Some(trimmed.to_string());
+        }
+    }
+
+    let title_selector = Selector::parse("title").ok()?;
+    document
+        .select(&title_selector)
+        .next()
+        .map(|el| el.text().collect::<String>().trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Extract the page body content as HTML.
+/// Tries `<article>` first, then falls back to og:description / meta description.
+fn extract_body(document: &Html) -> Option<String> {
+    // Try to get the inner HTML of an <article> element
+    if let Ok(article_selector) = Selector::parse("article")
+        && let Some(article) = document.select(&article_selector).next()
+    {
+        let html = article.inner_html();
+        let trimmed = html.trim();
+        if !trimmed.is_empty() {
+            return Some(trimmed.to_string());
+        }
+    }
+
+    // Fall back to og:description
+    if let Ok(og_desc_selector) = Selector::parse(r#"meta[property="og:description"]"#)
+        && let Some(element) = document.select(&og_desc_selector).next()
+        && let Some(content) = element.value().attr("content")
+    {
+        let trimmed = content.trim();
+        if !trimmed.is_empty() {
+            return Some(trimmed.to_string());
+        }
+    }
+
+    // Fall back to <meta name="description">
+    if let Ok(desc_selector) = Selector::parse(r#"meta[name="description"]"#)
+        && let Some(element) = document.select(&desc_selector).next()
+        && let Some(content) = element.value().attr("content")
+    {
+        let trimmed = content.trim();
+        if !trimmed.is_empty() {
+            return Some(trimmed.to_string());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(html: &str) -> Html {
+        Html::parse_document(html)
+    }
+
+    // --- extract_title tests ---
+
+    #[test]
+    fn extract_title_from_og_title() {
+        let doc =
+            parse(r#"<html><head><meta property="og:title" content="OG Title"></head></html>"#);
+        assert_eq!(extract_title(&doc), Some("OG Title".to_string()));
+    }
+
+    #[test]
+    fn extract_title_from_title_tag() {
+        let doc = parse("<html><head><title>Page Title</title></head></html>");
+        assert_eq!(extract_title(&doc), Some("Page Title".to_string()));
+    }
+
+    #[test]
+    fn extract_title_og_takes_precedence_over_title_tag() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:title" content="OG Title">
+            <title>Page Title</title>
+            </head></html>"#,
+        );
+        assert_eq!(extract_title(&doc), Some("OG Title".to_string()));
+    }
+
+    #[test]
+    fn extract_title_falls_back_to_title_when_og_empty() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:title" content="">
+            <title>Fallback Title</title>
+            </head></html>"#,
+        );
+        assert_eq!(extract_title(&doc), Some("Fallback Title".to_string()));
+    }
+
+    #[test]
+    fn extract_title_falls_back_to_title_when_og_whitespace() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:title" content="   ">
+            <title>Fallback Title</title>
+            </head></html>"#,
+        );
+        assert_eq!(extract_title(&doc), Some("Fallback Title".to_string()));
+    }
+
+    #[test]
+    fn extract_title_none_when_no_title() {
+        let doc = parse("<html><head></head><body>Hello</body></html>");
+        assert_eq!(extract_title(&doc), None);
+    }
+
+    #[test]
+    fn extract_title_none_when_empty_title_tag() {
+        let doc = parse("<html><head><title>   </title></head></html>");
+        assert_eq!(extract_title(&doc), None);
+    }
+
+    #[test]
+    fn extract_title_trims_whitespace() {
+        let doc =
+            parse(r#"<html><head><meta property="og:title" content="  Trimmed  "></head></html>"#);
+        assert_eq!(extract_title(&doc), Some("Trimmed".to_string()));
+    }
+
+    // --- extract_body tests ---
+
+    #[test]
+    fn extract_body_from_article_element() {
+        let doc = parse(r#"<html><body><article><p>Article content</p></article></body></html>"#);
+        assert_eq!(
+            extract_body(&doc),
+            Some("<p>Article content</p>".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_body_article_takes_precedence_over_description() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:description" content="OG Desc">
+            </head><body><article><p>Article content</p></article></body></html>"#,
+        );
+        assert_eq!(
+            extract_body(&doc),
+            Some("<p>Article content</p>".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_body_falls_back_to_og_description() {
+        let doc = parse(
+            r#"<html><head><meta property="og:description" content="OG Desc"></head><body><p>Some text</p></body></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("OG Desc".to_string()));
+    }
+
+    #[test]
+    fn extract_body_falls_back_to_meta_description() {
+        let doc = parse(
+            r#"<html><head><meta name="description" content="Meta Desc"></head><body><p>Some text</p></body></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("Meta Desc".to_string()));
+    }
+
+    #[test]
+    fn extract_body_og_description_takes_precedence_over_meta_description() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:description" content="OG Desc">
+            <meta name="description" content="Meta Desc">
+            </head></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("OG Desc".to_string()));
+    }
+
+    #[test]
+    fn extract_body_falls_back_when_og_empty() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:description" content="">
+            <meta name="description" content="Fallback Desc">
+            </head></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("Fallback Desc".to_string()));
+    }
+
+    #[test]
+    fn extract_body_falls_back_when_og_whitespace() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:description" content="   ">
+            <meta name="description" content="Fallback Desc">
+            </head></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("Fallback Desc".to_string()));
+    }
+
+    #[test]
+    fn extract_body_none_when_missing() {
+        let doc = parse("<html><head></head><body>Hello</body></html>");
+        assert_eq!(extract_body(&doc), None);
+    }
+
+    #[test]
+    fn extract_body_none_when_both_descriptions_empty() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:description" content="">
+            <meta name="description" content="">
+            </head></html>"#,
+        );
+        assert_eq!(extract_body(&doc), None);
+    }
+
+    #[test]
+    fn extract_body_trims_whitespace() {
+        let doc = parse(
+            r#"<html><head><meta property="og:description" content="  Trimmed  "></head></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("Trimmed".to_string()));
+    }
+
+    #[test]
+    fn extract_body_empty_article_falls_back_to_description() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:description" content="OG Desc">
+            </head><body><article>   </article></body></html>"#,
+        );
+        assert_eq!(extract_body(&doc), Some("OG Desc".to_string()));
+    }
+
+    // --- detect_source_type tests ---
+
+    #[test]
+    fn detect_source_type_from_video_og_type() {
+        let doc =
+            parse(r#"<html><head><meta property="og:type" content="video.other"></head></html>"#);
+        assert_eq!(detect_source_type(&doc), Some(EntrySourceType::Video));
+    }
+
+    #[test]
+    fn detect_source_type_from_og_video_tag() {
+        let doc = parse(
+            r#"<html><head>
+            <meta property="og:type" content="website">
+            <meta property="og:video:url" content="https://example.com/embed/1">
+            </head></html>"#,
+        );
+        assert_eq!(detect_source_type(&doc), Some(EntrySourceType::Video));
+    }
+
+    #[test]
+    fn detect_source_type_article_for_other_og_types() {
+        let doc = parse(r#"<html><head><meta property="og:type" content="article"></head></html>"#);
+        assert_eq!(detect_source_type(&doc), Some(EntrySourceType::Article));
+    }
+
+    #[test]
+    fn detect_source_type_none_without_signals() {
+        let doc = parse("<html><head><title>Plain</title></head></html>");
+        assert_eq!(detect_source_type(&doc), None);
+    }
+
+    // --- page_metadata tests ---
+
+    fn page(html: &str) -> PageMetadata {
+        parse_page(html, &Url::parse("https://example.com/posts/1").unwrap())
+    }
+
+    #[test]
+    fn page_metadata_extracts_rich_fields() {
+        let meta = page(
+            r#"<html lang="en_US"><head>
+            <meta name="author" content="Jane Doe">
+            <meta property="article:published_time" content="2024-05-01T10:00:00Z">
+            <meta property="og:site_name" content="Example Blog">
+            <link rel="canonical" href="/posts/hello">
+            <meta property="og:image" content="https://cdn.example.com/lead.jpg">
+            </head></html>"#,
+        );
+
+        assert_eq!(meta.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(
+            meta.published_at.map(|d| d.to_rfc3339()),
+            Some("2024-05-01T10:00:00+00:00".to_string())
+        );
+        assert_eq!(meta.site_name.as_deref(), Some("Example Blog"));
+        assert_eq!(
+            meta.canonical_url.as_deref(),
+            Some("https://example.com/posts/hello")
+        );
+        assert_eq!(
+            meta.image_url.as_deref(),
+            Some("https://cdn.example.com/lead.jpg")
+        );
+        assert_eq!(meta.language.as_deref(), Some("en-US"));
+    }
+
+    #[test]
+    fn page_metadata_uses_fallback_sources() {
+        let meta = page(
+            r#"<html><head>
+            <meta property="article:author" content="https://example.com/authors/jane">
+            <meta property="og:url" content="https://example.com/canonical">
+            <meta name="twitter:image" content="/img/lead.png">
+            <meta property="og:locale" content="pt_BR">
+            </head><body>
+            <span rel="author">Jane Roe</span>
+            <time datetime="2023-12-24">Christmas Eve</time>
+            </body></html>"#,
+        );
+
+        assert_eq!(meta.author.as_deref(), Some("Jane Roe"));
+        assert_eq!(
+            meta.published_at.map(|d| d.to_rfc3339()),
+            Some("2023-12-24T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            meta.canonical_url.as_deref(),
+            Some("https://example.com/canonical")
+        );
+        assert_eq!(
+            meta.image_url.as_deref(),
+            Some("https://example.com/img/lead.png")
+        );
+        assert_eq!(meta.language.as_deref(), Some("pt-BR"));
+    }
+
+    #[test]
+    fn page_metadata_language_from_content_language_header_tag() {
+        let meta =
+            page(r#"<html><head><meta http-equiv="Content-Language" content="de"></head></html>"#);
+
+        assert_eq!(meta.language.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn page_metadata_none_without_signals() {
+        let meta = page("<html><head><title>Bare</title></head></html>");
+
+        assert_eq!(meta.author, None);
+        assert_eq!(meta.published_at, None);
+        assert_eq!(meta.site_name, None);
+        assert_eq!(meta.canonical_url, None);
+        assert_eq!(meta.image_url, None);
+        assert_eq!(meta.language, None);
+    }
+}
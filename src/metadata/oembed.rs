@@ -0,0 +1,257 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::metadata::{FetchResult, Fetcher, PageMetadata, escape_html, non_empty, text_to_html};
+use crate::models::EntrySourceType;
+
+/// A well-known oEmbed provider whose endpoint can be used without discovery.
+struct Provider {
+    hosts: &'static [&'static str],
+    endpoint: &'static str,
+}
+
+/// Providers that either don't advertise their endpoint on the page or are worth skipping
+/// discovery for. Any other provider is found through `<link rel="alternate">` discovery.
+const PROVIDERS: &[Provider] = &[
+    Provider {
+        hosts: &[
+            "youtube.com",
+            "www.youtube.com",
+            "m.youtube.com",
+            "music.youtube.com",
+            "youtu.be",
+        ],
+        endpoint: "https://www.youtube.com/oembed",
+    },
+    Provider {
+        hosts: &["vimeo.com", "www.vimeo.com", "player.vimeo.com"],
+        endpoint: "https://vimeo.com/api/oembed.json",
+    },
+    Provider {
+        hosts: &["soundcloud.com", "www.soundcloud.com", "m.soundcloud.com"],
+        endpoint: "https://soundcloud.com/oembed",
+    },
+    Provider {
+        hosts: &["open.spotify.com"],
+        endpoint: "https://open.spotify.com/oembed",
+    },
+    Provider {
+        hosts: &["flickr.com", "www.flickr.com", "flic.kr"],
+        endpoint: "https://www.flickr.com/services/oembed/",
+    },
+    Provider {
+        hosts: &["dailymotion.com", "www.dailymotion.com", "dai.ly"],
+        endpoint: "https://www.dailymotion.com/services/oembed",
+    },
+];
+
+/// An oEmbed response. Only the fields we use are deserialized.
+#[derive(serde::Deserialize, Default)]
+pub struct OEmbed {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub html: Option<String>,
+    pub provider_name: Option<String>,
+    /// Not part of the spec, but returned by Vimeo and others.
+    pub description: Option<String>,
+}
+
+/// The oEmbed endpoint for a URL from the built-in provider registry, with the query filled in.
+pub fn provider_endpoint(url: &Url) -> Option<Url> {
+    let host = url.host_str()?;
+    let provider = PROVIDERS.iter().find(|p| p.hosts.contains(&host))?;
+
+    let mut endpoint = Url::parse(provider.endpoint).ok()?;
+    endpoint
+        .query_pairs_mut()
+        .append_pair("url", url.as_str())
+        .append_pair("format", "json");
+    Some(endpoint)
+}
+
+/// Find an advertised JSON oEmbed endpoint in a page, resolved against the page URL.
+pub fn discover(document: &Html, page_url: &Url) -> Option<Url> {
+    let selector = Selector::parse(
+        r#"link[rel~="alternate"][type="application/json+oembed"], link[rel~="alternate"][type="text/json+oembed"]"#,
+    )
+    .ok()?;
+
+    document
+        .select(&selector)
+        .filter_map(|el| el.value().attr("href"))
+        .find_map(|href| page_url.join(href.trim()).ok())
+        .filter(|endpoint| matches!(endpoint.scheme(), "http" | "https"))
+}
+
+/// Fetch an oEmbed response from a fully-formed endpoint URL.
+pub async fn fetch(fetcher: &Fetcher, endpoint: &Url) -> FetchResult<OEmbed> {
+    fetcher.fetch_json(endpoint, "application/json").await
+}
+
+/// Map an oEmbed response `type` to a source type.
+pub fn source_type(kind: &str) -> Option<EntrySourceType> {
+    match kind {
+        "video" => Some(EntrySourceType::Video),
+        "link" | "photo" | "rich" => Some(EntrySourceType::Article),
+        _ => None,
+    }
+}
+
+/// Combine an oEmbed response with whatever was scraped from the page.
+/// The oEmbed title and type win, and the body gets a byline and the embed (or thumbnail)
+/// ahead of the page's own body.
+pub fn merge(page: PageMetadata, oembed: OEmbed) -> PageMetadata {
+    let mut body = String::new();
+
+    let author = non_empty_opt(&oembed.author_name);
+    if let Some(author) = &author {
+        body.push_str(&format!("<p>By {}</p>", escape_html(author)));
+    }
+
+    if let Some(html) = non_empty_opt(&oembed.html) {
+        body.push_str(&html);
+    } else if let Some(thumbnail) = non_empty_opt(&oembed.thumbnail_url) {
+        body.push_str(&format!(
+            r#"<p><img src="{}"></p>"#,
+            escape_html(&thumbnail)
+        ));
+    }
+
+    match page.body {
+        Some(page_body) => body.push_str(&page_body),
+        None => {
+            if let Some(description) = non_empty_opt(&oembed.description) {
+                body.push_str(&text_to_html(&description));
+            }
+        }
+    }
+
+    PageMetadata {
+        title: non_empty_opt(&oembed.title).or(page.title),
+        body: non_empty(&body),
+        source_type: oembed
+            .kind
+            .as_deref()
+            .and_then(source_type)
+            .or(page.source_type),
+        author: author.or(page.author),
+        site_name: page.site_name.or(non_empty_opt(&oembed.provider_name)),
+        image_url: page.image_url.or(non_empty_opt(&oembed.thumbnail_url)),
+        ..page
+    }
+}
+
+fn non_empty_opt(value: &Option<String>) -> Option<String> {
+    value.as_deref().and_then(non_empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_endpoint_for_known_hosts() {
+        let url = Url::parse("https://soundcloud.com/artist/track").unwrap();
+        let endpoint = provider_endpoint(&url).unwrap();
+
+        assert_eq!(endpoint.host_str(), Some("soundcloud.com"));
+        assert_eq!(endpoint.path(), "/oembed");
+        assert!(
+            endpoint
+                .query_pairs()
+                .any(|(k, v)| k == "url" && v == "https://soundcloud.com/artist/track")
+        );
+    }
+
+    #[test]
+    fn provider_endpoint_none_for_unknown_hosts() {
+        let url = Url::parse("https://example.com/video").unwrap();
+        assert!(provider_endpoint(&url).is_none());
+    }
+
+    #[test]
+    fn discover_resolves_relative_link() {
+        let doc = Html::parse_document(
+            r#"<html><head>
+            <link rel="alternate" type="application/json+oembed" href="/services/oembed?url=x">
+            </head></html>"#,
+        );
+        let page_url = Url::parse("https://peertube.example/w/abc").unwrap();
+
+        assert_eq!(
+            discover(&doc, &page_url).map(|u| u.to_string()),
+            Some("https://peertube.example/services/oembed?url=x".to_string())
+        );
+    }
+
+    #[test]
+    fn discover_ignores_xml_oembed_and_missing_links() {
+        let doc = Html::parse_document(
+            r#"<html><head>
+            <link rel="alternate" type="text/xml+oembed" href="/oembed.xml">
+            </head></html>"#,
+        );
+        let page_url = Url::parse("https://example.com/").unwrap();
+
+        assert!(discover(&doc, &page_url).is_none());
+    }
+
+    #[test]
+    fn source_type_maps_types() {
+        assert_eq!(source_type("video"), Some(EntrySourceType::Video));
+        assert_eq!(source_type("rich"), Some(EntrySourceType::Article));
+        assert_eq!(source_type("unknown"), None);
+    }
+
+    #[test]
+    fn merge_prefers_oembed_title_and_adds_byline_and_embed() {
+        let page = PageMetadata {
+            title: Some("Page Title".to_string()),
+            body: Some("<p>Description</p>".to_string()),
+            site_name: Some("Page Site".to_string()),
+            ..Default::default()
+        };
+        let oembed = OEmbed {
+            kind: Some("video".to_string()),
+            title: Some("Video Title".to_string()),
+            author_name: Some("Author & Co".to_string()),
+            html: Some("<iframe></iframe>".to_string()),
+            provider_name: Some("Provider".to_string()),
+            ..Default::default()
+        };
+
+        let meta = merge(page, oembed);
+
+        assert_eq!(meta.title.as_deref(), Some("Video Title"));
+        assert_eq!(
+            meta.body.as_deref(),
+            Some("<p>By Author &amp; Co</p><iframe></iframe><p>Description</p>")
+        );
+        assert_eq!(meta.source_type, Some(EntrySourceType::Video));
+        assert_eq!(meta.author.as_deref(), Some("Author & Co"));
+        assert_eq!(meta.site_name.as_deref(), Some("Page Site"));
+    }
+
+    #[test]
+    fn merge_uses_thumbnail_and_description_without_html() {
+        let oembed = OEmbed {
+            kind: Some("photo".to_string()),
+            thumbnail_url: Some("https://example.com/t.jpg".to_string()),
+            description: Some("A photo".to_string()),
+            ..Default::default()
+        };
+
+        let meta = merge(PageMetadata::default(), oembed);
+
+        assert_eq!(meta.title, None);
+        assert_eq!(
+            meta.body.as_deref(),
+            Some(r#"<p><img src="https://example.com/t.jpg"></p><p>A photo</p>"#)
+        );
+        assert_eq!(meta.source_type, Some(EntrySourceType::Article));
+        assert_eq!(meta.image_url.as_deref(), Some("https://example.com/t.jpg"));
+    }
+}
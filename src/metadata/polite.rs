@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use texting_robots::Robot;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+use crate::config::Config;
+
+/// Default number of requests allowed in flight to the same host.
+const DEFAULT_HOST_CONCURRENCY: usize = 2;
+
+/// Default minimum time between the start of two requests to the same host.
+const DEFAULT_HOST_DELAY_MS: u64 = 1000;
+
+/// Default longest `Retry-After` we are willing to wait out before retrying.
+const DEFAULT_MAX_RETRY_AFTER_SECS: u64 = 30;
+
+/// How long a fetched robots.txt is trusted.
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Hosts tracked before idle ones are forgotten.
+const MAX_TRACKED_HOSTS: usize = 1024;
+
+struct HostSlot {
+    permits: Arc<Semaphore>,
+    /// Earliest time the next request to this host may start.
+    next_request: Mutex<Instant>,
+}
+
+/// Limits how many requests run against the same host at once and how quickly they start.
+pub struct HostLimiter {
+    concurrency: usize,
+    min_delay: Duration,
+    pub max_retry_after: Duration,
+    hosts: Mutex<HashMap<String, Arc<HostSlot>>>,
+}
+
+impl HostLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            concurrency: config
+                .fetch_host_concurrency
+                .filter(|&n| n > 0)
+                .unwrap_or(DEFAULT_HOST_CONCURRENCY),
+            min_delay: Duration::from_millis(
+                config.fetch_host_delay_ms.unwrap_or(DEFAULT_HOST_DELAY_MS),
+            ),
+            max_retry_after: Duration::from_secs(
+                config
+                    .fetch_max_retry_after_secs
+                    .unwrap_or(DEFAULT_MAX_RETRY_AFTER_SECS),
+            ),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn slot(&self, host: &str) -> Arc<HostSlot> {
+        let mut hosts = self.hosts.lock().unwrap();
+
+        if hosts.len() >= MAX_TRACKED_HOSTS && !hosts.contains_key(host) {
+            let now = Instant::now();
+            hosts.retain(|_, slot| {
+                Arc::strong_count(slot) > 1 || *slot.next_request.lock().unwrap() > now
+            });
+        }
+
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                Arc::new(HostSlot {
+                    permits: Arc::new(Semaphore::new(self.concurrency)),
+                    next_request: Mutex::new(Instant::now()),
+                })
+            })
+            .clone()
+    }
+
+    /// Wait for a free slot and the per-host delay. The request may start once this returns,
+    /// and counts against the host's concurrency until the permit is dropped.
+    pub async fn acquire(&self, host: &str) -> OwnedSemaphorePermit {
+        let slot = self.slot(host);
+        let permit = slot
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("host semaphore is never closed");
+
+        let start = {
+            let mut next = slot.next_request.lock().unwrap();
+            let start = (*next).max(Instant::now());
+            *next = start + self.min_delay;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+
+        permit
+    }
+
+    /// Hold off all requests to a host for a while, e.g. after it answered with `Retry-After`.
+    pub fn back_off(&self, host: &str, wait: Duration) {
+        let slot = self.slot(host);
+        let mut next = slot.next_request.lock().unwrap();
+        *next = (*next).max(Instant::now() + wait);
+    }
+}
+
+/// Parse a `Retry-After` header value, given either as seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+struct CachedRobots {
+    fetched_at: Instant,
+    /// `None` if there was no usable robots.txt, so anything may be fetched.
+    robot: Option<Arc<Robot>>,
+}
+
+/// Parsed robots.txt files, keyed by origin.
+pub struct RobotsCache {
+    /// The user agent token rules are matched against.
+    pub agent: String,
+    entries: Mutex<HashMap<String, CachedRobots>>,
+}
+
+impl RobotsCache {
+    /// A cache if robots.txt should be respected, matching rules for our user agent's product
+    /// token (e.g. `Laterfeed` for `Laterfeed/1.0`).
+    pub fn new(config: &Config) -> Option<Self> {
+        if !config.fetch_respect_robots.unwrap_or(false) {
+            return None;
+        }
+
+        let agent = config
+            .fetch_user_agent
+            .as_deref()
+            .and_then(|ua| ua.split(['/', ' ']).next())
+            .filter(|token| !token.is_empty())
+            .unwrap_or("Laterfeed");
+
+        Some(Self {
+            agent: agent.to_string(),
+            entries: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The cached rules for an origin, unless missing or expired.
+    pub fn get(&self, origin: &str) -> Option<Option<Arc<Robot>>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(origin)
+            .filter(|cached| cached.fetched_at.elapsed() < ROBOTS_TTL)
+            .map(|cached| cached.robot.clone())
+    }
+
+    /// Parse and cache a robots.txt body. An unparseable file allows everything.
+    pub fn insert(&self, origin: &str, txt: Option<&[u8]>) -> Option<Arc<Robot>> {
+        let robot = txt
+            .and_then(|txt| Robot::new(&self.agent, txt).ok())
+            .map(Arc::new);
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_TRACKED_HOSTS {
+            entries.retain(|_, cached| cached.fetched_at.elapsed() < ROBOTS_TTL);
+        }
+        entries.insert(
+            origin.to_string(),
+            CachedRobots {
+                fetched_at: Instant::now(),
+                robot: robot.clone(),
+            },
+        );
+
+        robot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(concurrency: usize, delay_ms: u64) -> HostLimiter {
+        HostLimiter::new(&Config {
+            fetch_host_concurrency: Some(concurrency),
+            fetch_host_delay_ms: Some(delay_ms),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire_spaces_out_requests_to_the_same_host() {
+        let limiter = limiter(4, 500);
+        let start = Instant::now();
+
+        drop(limiter.acquire("example.com").await);
+        drop(limiter.acquire("example.com").await);
+        drop(limiter.acquire("example.com").await);
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+
+        // Other hosts aren't held up
+        drop(limiter.acquire("example.org").await);
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire_limits_concurrency_per_host() {
+        let limiter = limiter(1, 0);
+
+        let permit = limiter.acquire("example.com").await;
+        let blocked = tokio::time::timeout(Duration::from_secs(1), limiter.acquire("example.com"));
+        assert!(blocked.await.is_err());
+
+        drop(permit);
+        let freed = tokio::time::timeout(Duration::from_secs(1), limiter.acquire("example.com"));
+        assert!(freed.await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn back_off_delays_the_next_request() {
+        let limiter = limiter(1, 0);
+        let start = Instant::now();
+
+        limiter.back_off("example.com", Duration::from_secs(10));
+        drop(limiter.acquire("example.com").await);
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn parse_retry_after_seconds_and_dates() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:29:00 GMT", now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn robots_cache_matches_our_agent() {
+        let config = Config {
+            fetch_respect_robots: Some(true),
+            fetch_user_agent: Some("MyReader/2.0 (+https://example.com)".to_string()),
+            ..Default::default()
+        };
+        let cache = RobotsCache::new(&config).unwrap();
+        assert_eq!(cache.agent, "MyReader");
+
+        let robot = cache
+            .insert(
+                "https://example.com",
+                Some(b"User-agent: MyReader\nDisallow: /private\n\nUser-agent: *\nDisallow: /"),
+            )
+            .unwrap();
+        assert!(robot.allowed("https://example.com/articles/1"));
+        assert!(!robot.allowed("https://example.com/private/1"));
+
+        assert!(cache.get("https://example.com").is_some());
+        assert!(cache.get("https://example.org").is_none());
+    }
+
+    #[test]
+    fn robots_cache_is_disabled_by_default() {
+        assert!(RobotsCache::new(&Config::default()).is_none());
+    }
+}
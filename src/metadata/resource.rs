@@ -0,0 +1,208 @@
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::metadata::{PageMetadata, escape_html, fetch::Document, non_empty, text_to_html};
+use crate::models::EntrySourceType;
+
+/// Longest first line of a text file that is still used as its title.
+const MAX_TEXT_TITLE_CHARS: usize = 200;
+
+/// Metadata for a resource that isn't an HTML page, such as an image, a PDF or a text file.
+pub fn resource_metadata(url: &Url, document: &Document) -> PageMetadata {
+    let name = file_name(url);
+    let href = escape_html(url.as_str());
+
+    match document {
+        // HTML pages are parsed by the HTML extractor
+        Document::Html(_) => PageMetadata::default(),
+        Document::Text(text) => {
+            let first_line = text.lines().find_map(non_empty);
+            let title = first_line
+                .filter(|line| line.chars().count() <= MAX_TEXT_TITLE_CHARS)
+                .unwrap_or(name);
+
+            PageMetadata {
+                title: Some(title),
+                body: non_empty(text).map(|text| text_to_html(&text)),
+                source_type: Some(EntrySourceType::Article),
+                non_html: true,
+                ..Default::default()
+            }
+        }
+        Document::Other {
+            content_type,
+            content_length,
+        } => {
+            let alt = escape_html(&name);
+            let (body, source_type) = match content_type.split('/').next() {
+                Some("image") => (
+                    format!(r#"<p><img src="{href}" alt="{alt}"></p>"#),
+                    EntrySourceType::Article,
+                ),
+                Some("video") => (
+                    format!(r#"<p><video src="{href}" controls></video></p>"#),
+                    EntrySourceType::Video,
+                ),
+                Some("audio") => (
+                    format!(r#"<p><audio src="{href}" controls></audio></p>"#),
+                    EntrySourceType::Article,
+                ),
+                _ => (
+                    format!(
+                        r#"<p>{}: <a href="{href}">{alt}</a></p>"#,
+                        escape_html(&describe(content_type, *content_length))
+                    ),
+                    EntrySourceType::Article,
+                ),
+            };
+
+            PageMetadata {
+                title: Some(name),
+                body: Some(body),
+                source_type: Some(source_type),
+                image_url: content_type.starts_with("image/").then(|| url.to_string()),
+                non_html: true,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// The last path segment of a URL, percent-decoded, or the host for bare domains.
+fn file_name(url: &Url) -> String {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        .or_else(|| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// A human-readable description of a file, e.g. "PDF document (1.2 MB)".
+fn describe(content_type: &str, content_length: Option<u64>) -> String {
+    let kind = match content_type {
+        "application/pdf" => "PDF document".to_string(),
+        "" => "File".to_string(),
+        other => format!("{other} file"),
+    };
+
+    match content_length {
+        Some(length) => format!("{kind} ({})", format_size(length)),
+        None => kind,
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn pdf_gets_file_name_title_and_link() {
+        let meta = resource_metadata(
+            &url("https://example.com/papers/My%20Paper.pdf"),
+            &Document::Other {
+                content_type: "application/pdf".to_string(),
+                content_length: Some(1_258_291),
+            },
+        );
+
+        assert_eq!(meta.title.as_deref(), Some("My Paper.pdf"));
+        assert_eq!(
+            meta.body.as_deref(),
+            Some(
+                r#"<p>PDF document (1.2 MB): <a href="https://example.com/papers/My%20Paper.pdf">My Paper.pdf</a></p>"#
+            )
+        );
+        assert_eq!(meta.source_type, Some(EntrySourceType::Article));
+    }
+
+    #[test]
+    fn image_is_embedded_and_used_as_lead_image() {
+        let meta = resource_metadata(
+            &url("https://example.com/cat.jpg"),
+            &Document::Other {
+                content_type: "image/jpeg".to_string(),
+                content_length: None,
+            },
+        );
+
+        assert_eq!(meta.title.as_deref(), Some("cat.jpg"));
+        assert_eq!(
+            meta.body.as_deref(),
+            Some(r#"<p><img src="https://example.com/cat.jpg" alt="cat.jpg"></p>"#)
+        );
+        assert_eq!(
+            meta.image_url.as_deref(),
+            Some("https://example.com/cat.jpg")
+        );
+    }
+
+    #[test]
+    fn video_file_is_a_video() {
+        let meta = resource_metadata(
+            &url("https://example.com/clip.mp4"),
+            &Document::Other {
+                content_type: "video/mp4".to_string(),
+                content_length: None,
+            },
+        );
+
+        assert_eq!(meta.source_type, Some(EntrySourceType::Video));
+    }
+
+    #[test]
+    fn text_uses_first_line_as_title() {
+        let meta = resource_metadata(
+            &url("https://example.com/notes.txt"),
+            &Document::Text("\n  Release notes\n\nFixed <bugs>.".to_string()),
+        );
+
+        assert_eq!(meta.title.as_deref(), Some("Release notes"));
+        assert_eq!(
+            meta.body.as_deref(),
+            Some("<p>Release notes</p><p>Fixed &lt;bugs&gt;.</p>")
+        );
+    }
+
+    #[test]
+    fn text_with_long_first_line_uses_file_name() {
+        let meta = resource_metadata(
+            &url("https://example.com/rfc.txt"),
+            &Document::Text("x".repeat(MAX_TEXT_TITLE_CHARS + 1)),
+        );
+
+        assert_eq!(meta.title.as_deref(), Some("rfc.txt"));
+    }
+
+    #[test]
+    fn file_name_falls_back_to_host() {
+        assert_eq!(file_name(&url("https://example.com/")), "example.com");
+    }
+
+    #[test]
+    fn format_size_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use reqwest::header::{COOKIE, HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+use url::Url;
+
+use crate::metadata::FetchResult;
+
+/// A rule as written in the rules file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    name: String,
+    hosts: Vec<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    cookies: BTreeMap<String, String>,
+}
+
+/// Headers and cookies attached to every fetch from matching hosts, e.g. to get past the login
+/// wall of a site we have an account on. Header values are secrets: they are marked sensitive
+/// and never logged, only the rule's name is.
+pub struct FetchRule {
+    pub name: String,
+    /// Hosts the rule applies to, along with their subdomains.
+    hosts: Vec<String>,
+    pub headers: HeaderMap,
+}
+
+impl fmt::Debug for FetchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FetchRule")
+            .field("name", &self.name)
+            .field("hosts", &self.hosts)
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl FetchRule {
+    fn from_config(config: RuleConfig) -> FetchResult<Self> {
+        let name = config.name.trim().to_string();
+        if name.is_empty() {
+            return Err("fetch rule without a name".into());
+        }
+
+        let hosts: Vec<String> = config
+            .hosts
+            .iter()
+            .map(|host| {
+                host.trim()
+                    .trim_start_matches("*.")
+                    .trim_start_matches('.')
+                    .trim_end_matches('.')
+                    .to_ascii_lowercase()
+            })
+            .filter(|host| !host.is_empty())
+            .collect();
+        if hosts.is_empty() {
+            return Err(format!("fetch rule `{name}` has no hosts").into());
+        }
+
+        // Errors name the rule and header, but never include the value
+        let mut headers = HeaderMap::new();
+        for (header, value) in &config.headers {
+            let header_name = HeaderName::try_from(header.as_str()).map_err(|_| {
+                format!("fetch rule `{name}` has an invalid header name `{header}`")
+            })?;
+            let value = sensitive_value(value).ok_or_else(|| {
+                format!("fetch rule `{name}` has an invalid value for `{header}`")
+            })?;
+            headers.insert(header_name, value);
+        }
+
+        if !config.cookies.is_empty() {
+            let cookies: Vec<String> = config
+                .cookies
+                .iter()
+                .map(|(cookie, value)| format!("{cookie}={value}"))
+                .collect();
+            let value = sensitive_value(&cookies.join("; "))
+                .ok_or_else(|| format!("fetch rule `{name}` has an invalid cookie"))?;
+            headers.insert(COOKIE, value);
+        }
+
+        Ok(Self {
+            name,
+            hosts,
+            headers,
+        })
+    }
+
+    fn matches_host(&self, host: &str) -> bool {
+        self.hosts.iter().any(|rule| {
+            host == rule
+                || host
+                    .strip_suffix(rule.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+fn sensitive_value(value: &str) -> Option<HeaderValue> {
+    let mut value = HeaderValue::try_from(value).ok()?;
+    value.set_sensitive(true);
+    Some(value)
+}
+
+/// The per-host fetch rules from `FETCH_RULES_FILE`, in file order.
+#[derive(Debug, Default)]
+pub struct FetchRules(Vec<FetchRule>);
+
+impl FetchRules {
+    /// Load the rules from a JSON file holding an array of rules.
+    pub fn load(path: &Path) -> FetchResult<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read fetch rules from {}: {e}", path.display()))?;
+        Self::parse(&json)
+    }
+
+    fn parse(json: &str) -> FetchResult<Self> {
+        // serde_json errors can quote the offending value, so only say where it is
+        let configs: Vec<RuleConfig> = serde_json::from_str(json).map_err(|e| {
+            format!(
+                "invalid fetch rules at line {}, column {}",
+                e.line(),
+                e.column()
+            )
+        })?;
+
+        configs
+            .into_iter()
+            .map(FetchRule::from_config)
+            .collect::<FetchResult<_>>()
+            .map(Self)
+    }
+
+    /// The first rule matching a URL's host, if any.
+    pub fn find(&self, url: &Url) -> Option<&FetchRule> {
+        let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+        self.0.iter().find(|rule| rule.matches_host(&host))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"[
+        {
+            "name": "wiki",
+            "hosts": ["wiki.internal"],
+            "headers": {"Authorization": "Bearer s3cret"}
+        },
+        {
+            "name": "newspaper",
+            "hosts": ["*.paper.example", "paper-cdn.example"],
+            "headers": {"X-Subscriber": "yes"},
+            "cookies": {"session": "abc123", "consent": "1"}
+        }
+    ]"#;
+
+    fn find<'a>(rules: &'a FetchRules, url: &str) -> Option<&'a FetchRule> {
+        rules.find(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn find_matches_hosts_and_subdomains() {
+        let rules = FetchRules::parse(RULES).unwrap();
+
+        assert_eq!(
+            find(&rules, "https://wiki.internal/page").unwrap().name,
+            "wiki"
+        );
+        assert_eq!(
+            find(&rules, "https://www.paper.example/a").unwrap().name,
+            "newspaper"
+        );
+        assert_eq!(
+            find(&rules, "https://paper.example/a").unwrap().name,
+            "newspaper"
+        );
+        assert!(find(&rules, "https://notwiki.internal/").is_none());
+        assert!(find(&rules, "https://example.com/").is_none());
+    }
+
+    #[test]
+    fn rules_build_sensitive_headers_and_cookies() {
+        let rules = FetchRules::parse(RULES).unwrap();
+        let rule = find(&rules, "https://paper.example/").unwrap();
+
+        assert_eq!(rule.headers["x-subscriber"], "yes");
+        assert_eq!(rule.headers[COOKIE], "consent=1; session=abc123");
+        assert!(rule.headers.values().all(HeaderValue::is_sensitive));
+    }
+
+    #[test]
+    fn debug_output_hides_secrets() {
+        let rules = FetchRules::parse(RULES).unwrap();
+        let debug = format!("{rules:?}");
+
+        assert!(debug.contains("wiki"));
+        assert!(!debug.contains("s3cret"));
+        assert!(!debug.contains("abc123"));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_rules_without_leaking_values() {
+        let error = FetchRules::parse(
+            r#"[{"name": "bad", "hosts": ["a.example"], "headers": {"X-Token": "s3cret\n"}}]"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("bad"));
+        assert!(!error.contains("s3cret"));
+
+        assert!(FetchRules::parse(r#"[{"name": "no-hosts", "hosts": []}]"#).is_err());
+        assert!(FetchRules::parse(r#"[{"name": "", "hosts": ["a.example"]}]"#).is_err());
+        assert!(
+            FetchRules::parse(r#"[{"name": "typo", "hosts": ["a.example"], "header": {}}]"#)
+                .is_err()
+        );
+    }
+}
@@ -1,10 +1,17 @@
 use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, query, query_as, sqlite::SqlitePool};
+use sqlx::{
+    FromRow, query, query_as,
+    sqlite::{SqliteExecutor, SqlitePool},
+    types::Json,
+};
+use url::Url;
 
-#[derive(sqlx::Type, Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(sqlx::Type, Serialize, Deserialize, Copy, Clone, Debug, Default, Eq, PartialEq)]
 #[repr(i64)]
 pub enum EntrySourceType {
+    #[default]
     Article = 0,
     Video = 1,
 }
@@ -19,60 +26,396 @@ impl From<i64> for EntrySourceType {
     }
 }
 
-#[derive(FromRow, Serialize, Deserialize)]
+/// How fetching an entry's page for its metadata went.
+#[derive(sqlx::Type, Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(i64)]
+pub enum FetchOutcome {
+    /// The page was fetched and parsed.
+    Success = 0,
+    /// The URL was fetched but isn't an HTML page, e.g. an image or a PDF.
+    NonHtml = 1,
+    Timeout = 2,
+    /// The server answered with an error status.
+    HttpError = 3,
+    /// The URL was refused by the fetch guard or robots.txt.
+    Blocked = 4,
+    /// The response couldn't be made sense of.
+    ParseError = 5,
+    /// The host couldn't be resolved or connected to.
+    NetworkError = 6,
+}
+
+#[derive(FromRow, Serialize, Deserialize, Clone)]
 pub struct Entry {
     pub id: i64,
     pub url: String,
     pub title: String,
     pub body: Option<String>,
     pub source_type: EntrySourceType,
+    pub source_type_inferred: bool,
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub site_name: Option<String>,
+    pub canonical_url: Option<String>,
+    pub image_url: Option<String>,
+    pub language: Option<String>,
+    pub word_count: Option<i64>,
+    /// Length of a video, in seconds.
+    pub duration_secs: Option<i64>,
+    /// Page of the author, e.g. a video's channel.
+    pub author_url: Option<String>,
+    /// Chapters of a video, in order.
+    pub chapters: Option<Json<Vec<Chapter>>>,
+    /// Transcript of a video, in order.
+    pub transcript: Option<Json<Vec<TranscriptSegment>>>,
+    /// Name of the per-host fetch rule applied when fetching the page.
+    pub fetch_rule: Option<String>,
+    /// How fetching the page's metadata went. Unknown for entries saved before it was recorded.
+    pub fetch_outcome: Option<FetchOutcome>,
+    /// Status the page answered with when the fetch failed with an HTTP error.
+    pub fetch_http_status: Option<i64>,
+    /// URL the page was served from, after redirects.
+    pub final_url: Option<String>,
+    /// How long fetching the metadata took, in milliseconds.
+    pub fetch_duration_ms: Option<i64>,
+    /// Labels given when saving, e.g. tags or folders carried over from an import.
+    pub tags: Option<Json<Vec<String>>>,
+    /// Snapshot of the page, relative to the data directory.
+    pub archive_path: Option<String>,
+    /// When the link was last checked by the dead-link checker.
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Status the link answered with on its last check, if it answered at all.
+    pub http_status: Option<i64>,
+    /// Whether the link was gone (404/410, or its domain no longer resolves) when last checked.
+    pub dead: bool,
     pub created_at: DateTime<Utc>,
+    /// When the entry was moved to the trash. Trashed entries are left out everywhere but the
+    /// trash itself.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// The fields of an entry to be inserted. `id` and `created_at` are assigned on insert.
+#[derive(Default, Debug, PartialEq)]
+pub struct NewEntry {
+    pub url: String,
+    pub title: String,
+    pub body: Option<String>,
+    pub source_type: EntrySourceType,
+    pub source_type_inferred: bool,
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub site_name: Option<String>,
+    pub canonical_url: Option<String>,
+    pub image_url: Option<String>,
+    pub language: Option<String>,
+    pub word_count: Option<i64>,
+    pub duration_secs: Option<i64>,
+    pub author_url: Option<String>,
+    pub chapters: Option<Json<Vec<Chapter>>>,
+    pub transcript: Option<Json<Vec<TranscriptSegment>>>,
+    pub fetch_rule: Option<String>,
+    pub fetch_outcome: Option<FetchOutcome>,
+    pub fetch_http_status: Option<i64>,
+    pub final_url: Option<String>,
+    pub fetch_duration_ms: Option<i64>,
+    pub tags: Option<Json<Vec<String>>>,
+    /// Snapshot of the page, relative to the data directory. Only carried over by imports of
+    /// this server's exports, like the dead-link checker's results and the trash state below.
+    pub archive_path: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub http_status: Option<i64>,
+    pub dead: bool,
+    /// When the entry was saved. Defaults to now; imports carry over the original time.
+    pub created_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// A chapter of a video, starting at `start_secs`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub start_secs: i64,
+    pub title: String,
+}
+
+/// A line of a video transcript, spoken from `start_secs`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TranscriptSegment {
+    pub start_secs: i64,
+    pub text: String,
+}
+
+/// Which entries to list. Unset fields don't filter.
+#[derive(Default)]
+pub struct EntryFilter {
+    pub dead: Option<bool>,
+    /// Whether fetching the page's metadata failed, i.e. ended in anything but `Success` or
+    /// `NonHtml`. Entries without a recorded outcome count as not failed.
+    pub failed: Option<bool>,
+    /// Text that the title, body or transcript must contain, ignoring ASCII case.
+    pub search: Option<String>,
+    pub source_type: Option<EntrySourceType>,
+    /// Saved at or after this time.
+    pub saved_after: Option<DateTime<Utc>>,
+    /// Saved before this time.
+    pub saved_before: Option<DateTime<Utc>>,
+    /// Host of the URL, as returned by `url_domain`. Subdomains match too.
+    pub domain: Option<String>,
+    /// Whether the entry has a non-empty body.
+    pub has_body: Option<bool>,
+    pub sort: EntrySort,
+}
+
+/// Order to list entries in. Ties are listed newest first.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum EntrySort {
+    #[default]
+    Newest,
+    Oldest,
+    /// By title, ignoring case.
+    Title,
+    Domain,
+}
+
+impl EntrySort {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntrySort::Newest => "newest",
+            EntrySort::Oldest => "oldest",
+            EntrySort::Title => "title",
+            EntrySort::Domain => "domain",
+        }
+    }
 }
 
 impl Entry {
     pub async fn create(
-        pool: &SqlitePool,
-        url: &str,
-        title: &str,
-        body: Option<&str>,
-        source_type: EntrySourceType,
+        executor: impl SqliteExecutor<'_>,
+        entry: &NewEntry,
     ) -> Result<Entry, sqlx::Error> {
-        let now = Utc::now();
+        let created_at = entry.created_at.unwrap_or_else(Utc::now);
+        let domain = url_domain(&entry.url);
 
         query_as!(
             Entry,
             r#"
-            INSERT INTO entries (url, title, body, source_type, created_at) VALUES (?, ?, ?, ?, ?)
-            RETURNING id, url, title, body, source_type, created_at as "created_at: DateTime<Utc>"
+            INSERT INTO entries (
+                url, title, body, source_type, source_type_inferred, author, published_at,
+                site_name, canonical_url, image_url, language, word_count, duration_secs,
+                author_url, chapters, transcript, fetch_rule, fetch_outcome, fetch_http_status,
+                final_url, fetch_duration_ms, tags, archive_path, last_checked_at, http_status,
+                dead, domain, created_at, deleted_at
+            )
+            VALUES (
+                ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+            )
+            RETURNING id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
             "#,
-            url,
-            title,
-            body,
-            source_type,
-            now
+            entry.url,
+            entry.title,
+            entry.body,
+            entry.source_type,
+            entry.source_type_inferred,
+            entry.author,
+            entry.published_at,
+            entry.site_name,
+            entry.canonical_url,
+            entry.image_url,
+            entry.language,
+            entry.word_count,
+            entry.duration_secs,
+            entry.author_url,
+            entry.chapters,
+            entry.transcript,
+            entry.fetch_rule,
+            entry.fetch_outcome,
+            entry.fetch_http_status,
+            entry.final_url,
+            entry.fetch_duration_ms,
+            entry.tags,
+            entry.archive_path,
+            entry.last_checked_at,
+            entry.http_status,
+            entry.dead,
+            domain,
+            created_at,
+            entry.deleted_at
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await
     }
 
-    pub async fn fetch_all(pool: &SqlitePool) -> Result<Vec<Entry>, sqlx::Error> {
+    pub async fn fetch_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Entry>, sqlx::Error> {
         query_as!(
             Entry,
             r#"
-            SELECT id, url, title, body, source_type, created_at as "created_at: DateTime<Utc>"
-            FROM entries ORDER BY created_at DESC
-            "#
+            SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            FROM entries WHERE id = ? AND deleted_at IS NULL
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Fetch the entry saved with any of the given URLs, trying them in order.
+    pub async fn fetch_by_url(
+        pool: &SqlitePool,
+        urls: &[&str],
+    ) -> Result<Option<Entry>, sqlx::Error> {
+        for url in urls {
+            let entry = query_as!(
+                Entry,
+                r#"
+                SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                    published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                    image_url, language, word_count, duration_secs, author_url,
+                    chapters as "chapters: Json<Vec<Chapter>>",
+                    transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                    fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                    fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                    last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                    created_at as "created_at: DateTime<Utc>",
+                    deleted_at as "deleted_at: DateTime<Utc>"
+                FROM entries WHERE url = ? AND deleted_at IS NULL
+                "#,
+                url
+            )
+            .fetch_optional(pool)
+            .await?;
+            if entry.is_some() {
+                return Ok(entry);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetch all entries matching a filter, newest first.
+    pub async fn fetch_all(
+        pool: &SqlitePool,
+        filter: &EntryFilter,
+    ) -> Result<Vec<Entry>, sqlx::Error> {
+        let search = filter.search.as_deref().map(like_pattern);
+        let subdomains = filter.domain.as_deref().map(|domain| format!("%.{domain}"));
+        let sort = filter.sort.as_str();
+
+        query_as!(
+            Entry,
+            r#"
+            SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            FROM entries
+            WHERE deleted_at IS NULL
+                AND (? IS NULL OR dead = ?)
+                AND (? IS NULL OR (COALESCE(fetch_outcome, 0) NOT IN (0, 1)) = ?)
+                AND (? IS NULL OR title LIKE ? ESCAPE '\' OR body LIKE ? ESCAPE '\'
+                    OR transcript LIKE ? ESCAPE '\')
+                AND (? IS NULL OR source_type = ?)
+                AND (? IS NULL OR julianday(created_at) >= julianday(?))
+                AND (? IS NULL OR julianday(created_at) < julianday(?))
+                AND (? IS NULL OR domain = ? OR domain LIKE ?)
+                AND (? IS NULL OR (COALESCE(body, '') != '') = ?)
+            ORDER BY
+                CASE WHEN ? = 'oldest' THEN created_at END ASC,
+                CASE WHEN ? = 'title' THEN title END COLLATE NOCASE ASC,
+                CASE WHEN ? = 'domain' THEN domain END ASC,
+                created_at DESC
+            "#,
+            filter.dead,
+            filter.dead,
+            filter.failed,
+            filter.failed,
+            search,
+            search,
+            search,
+            search,
+            filter.source_type,
+            filter.source_type,
+            filter.saved_after,
+            filter.saved_after,
+            filter.saved_before,
+            filter.saved_before,
+            filter.domain,
+            filter.domain,
+            subdomains,
+            filter.has_body,
+            filter.has_body,
+            sort,
+            sort,
+            sort
         )
         .fetch_all(pool)
         .await
     }
 
+    /// Stream all entries, newest first, without loading them all into memory. Trashed entries
+    /// are left out unless `include_trashed` is set.
+    pub fn stream_all(
+        pool: &SqlitePool,
+        include_trashed: bool,
+    ) -> BoxStream<'_, Result<Entry, sqlx::Error>> {
+        // The stream borrows its arguments for as long as it lives
+        let include_trashed: &'static bool = if include_trashed { &true } else { &false };
+        query_as!(
+            Entry,
+            r#"
+            SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            FROM entries WHERE ? OR deleted_at IS NULL ORDER BY created_at DESC
+            "#,
+            *include_trashed
+        )
+        .fetch(pool)
+    }
+
     pub async fn fetch_latest(pool: &SqlitePool, limit: i64) -> Result<Vec<Entry>, sqlx::Error> {
         query_as!(
             Entry,
             r#"
-            SELECT id, url, title, body, source_type, created_at as "created_at: DateTime<Utc>"
-            FROM entries ORDER BY created_at DESC LIMIT ?
+            SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            FROM entries WHERE deleted_at IS NULL ORDER BY created_at DESC LIMIT ?
             "#,
             limit
         )
@@ -80,7 +423,112 @@ impl Entry {
         .await
     }
 
-    /// Delete an entry by ID. Returns true if an entry was deleted, false if not found.
+    /// Whether an entry with exactly this URL has been saved, and isn't in the trash.
+    pub async fn exists_with_url(pool: &SqlitePool, url: &str) -> Result<bool, sqlx::Error> {
+        let row = query!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM entries WHERE url = ? AND deleted_at IS NULL)
+                as "exists!: bool"
+            "#,
+            url
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.exists)
+    }
+
+    /// Record where an entry's page snapshot was stored. Returns false if the entry is gone.
+    pub async fn set_archive_path(
+        pool: &SqlitePool,
+        id: i64,
+        archive_path: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = query!(
+            "UPDATE entries SET archive_path = ? WHERE id = ?",
+            archive_path,
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Replace an entry's body. Returns false if the entry is gone.
+    pub async fn set_body(pool: &SqlitePool, id: i64, body: &str) -> Result<bool, sqlx::Error> {
+        let result = query!("UPDATE entries SET body = ? WHERE id = ?", body, id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The bodies of all entries that may refer to cached media, trashed ones included.
+    pub async fn bodies_with_media(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+        let rows = query!(r#"SELECT body as "body!" FROM entries WHERE body LIKE '%/media/%'"#)
+            .fetch_all(pool)
+            .await?;
+        Ok(rows.into_iter().map(|row| row.body).collect())
+    }
+
+    /// The snapshot paths of all entries that have one, trashed ones included.
+    pub async fn archive_paths(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+        let rows = query!(
+            r#"SELECT archive_path as "archive_path!" FROM entries WHERE archive_path IS NOT NULL"#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.archive_path).collect())
+    }
+
+    /// Entries whose link hasn't been checked since `checked_before`, never-checked ones first.
+    /// Entries without a web page, such as newsletters received by email, are left out.
+    pub async fn fetch_due_for_link_check(
+        pool: &SqlitePool,
+        checked_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        let rows = query!(
+            r#"
+            SELECT id as "id!", url FROM entries
+            WHERE deleted_at IS NULL
+                AND (last_checked_at IS NULL OR last_checked_at < ?)
+                AND (url LIKE 'http://%' OR url LIKE 'https://%')
+            ORDER BY last_checked_at IS NOT NULL, last_checked_at
+            LIMIT ?
+            "#,
+            checked_before,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.id, row.url)).collect())
+    }
+
+    /// Record the outcome of a link check. `dead` is left as is when `None`, e.g. after a
+    /// timeout that says nothing about whether the link still works.
+    pub async fn record_link_check(
+        pool: &SqlitePool,
+        id: i64,
+        checked_at: DateTime<Utc>,
+        http_status: Option<i64>,
+        dead: Option<bool>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = query!(
+            r#"
+            UPDATE entries SET last_checked_at = ?, http_status = ?, dead = COALESCE(?, dead)
+            WHERE id = ?
+            "#,
+            checked_at,
+            http_status,
+            dead,
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete an entry by ID for good, even from the trash. Returns true if an entry was deleted,
+    /// false if not found.
     pub async fn delete_by_id(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
         let result = query!("DELETE FROM entries WHERE id = ?", id)
             .execute(pool)
@@ -88,33 +536,419 @@ impl Entry {
         Ok(result.rows_affected() > 0)
     }
 
-    /// Delete entries older than the given cutoff datetime. Returns the number of entries deleted.
+    /// Delete entries older than the given cutoff datetime, leaving the trash to
+    /// `purge_trash`. Returns the IDs of the entries deleted.
     pub async fn delete_older_than(
         pool: &SqlitePool,
         cutoff: DateTime<Utc>,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let rows = query!(
+            r#"
+            DELETE FROM entries WHERE created_at < ? AND deleted_at IS NULL
+            RETURNING id as "id!"
+            "#,
+            cutoff
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// Delete entries beyond the N most recent, not counting the trash. Returns the IDs of the
+    /// entries deleted.
+    pub async fn delete_beyond_limit(pool: &SqlitePool, max: u32) -> Result<Vec<i64>, sqlx::Error> {
+        let rows = query!(
+            r#"
+            DELETE FROM entries WHERE deleted_at IS NULL AND id NOT IN (
+                SELECT id FROM entries WHERE deleted_at IS NULL ORDER BY created_at DESC LIMIT ?
+            )
+            RETURNING id as "id!"
+            "#,
+            max
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// Fetch an entry from the trash.
+    pub async fn fetch_trashed_by_id(
+        pool: &SqlitePool,
+        id: i64,
+    ) -> Result<Option<Entry>, sqlx::Error> {
+        query_as!(
+            Entry,
+            r#"
+            SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            FROM entries WHERE id = ? AND deleted_at IS NOT NULL
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Fetch the entries in the trash, most recently trashed first.
+    pub async fn fetch_trash(pool: &SqlitePool) -> Result<Vec<Entry>, sqlx::Error> {
+        query_as!(
+            Entry,
+            r#"
+            SELECT id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            FROM entries WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC
+            "#
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Move an entry to the trash. Returns false if not found or already trashed.
+    pub async fn trash(
+        pool: &SqlitePool,
+        id: i64,
+        deleted_at: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = query!(
+            "UPDATE entries SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+            deleted_at,
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Take an entry out of the trash. Returns `None` if it isn't in the trash.
+    pub async fn restore(pool: &SqlitePool, id: i64) -> Result<Option<Entry>, sqlx::Error> {
+        query_as!(
+            Entry,
+            r#"
+            UPDATE entries SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL
+            RETURNING id as "id!", url, title, body, source_type, source_type_inferred, author,
+                published_at as "published_at: DateTime<Utc>", site_name, canonical_url,
+                image_url, language, word_count, duration_secs, author_url,
+                chapters as "chapters: Json<Vec<Chapter>>",
+                transcript as "transcript: Json<Vec<TranscriptSegment>>", fetch_rule,
+                fetch_outcome as "fetch_outcome: FetchOutcome", fetch_http_status, final_url,
+                fetch_duration_ms, tags as "tags: Json<Vec<String>>", archive_path,
+                last_checked_at as "last_checked_at: DateTime<Utc>", http_status, dead,
+                created_at as "created_at: DateTime<Utc>",
+                deleted_at as "deleted_at: DateTime<Utc>"
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Delete the entries trashed before `trashed_before` for good, or the whole trash if
+    /// `None`. Returns the number of entries deleted.
+    pub async fn purge_trash(
+        pool: &SqlitePool,
+        trashed_before: Option<DateTime<Utc>>,
     ) -> Result<u64, sqlx::Error> {
-        let result = query!("DELETE FROM entries WHERE created_at < ?", cutoff)
+        let result = query!(
+            r#"
+            DELETE FROM entries
+            WHERE deleted_at IS NOT NULL
+                AND (? IS NULL OR julianday(deleted_at) < julianday(?))
+            "#,
+            trashed_before,
+            trashed_before
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Delete a trashed entry with this URL for good, so the URL can be saved again. Returns
+    /// false if there was none.
+    pub async fn purge_trashed_url(
+        executor: impl SqliteExecutor<'_>,
+        url: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = query!(
+            "DELETE FROM entries WHERE url = ? AND deleted_at IS NOT NULL",
+            url
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Where a webhook delivery stands.
+#[derive(sqlx::Type, Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(i64)]
+pub enum DeliveryStatus {
+    /// Not delivered yet, due at `next_attempt_at`.
+    Pending = 0,
+    Succeeded = 1,
+    /// Given up on after too many failed attempts.
+    Failed = 2,
+}
+
+/// A subscription to entry events, delivered to `url`.
+#[derive(FromRow)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// Key the payloads are signed with.
+    pub secret: String,
+    /// Names of the events delivered, e.g. `entry.created`.
+    pub events: Json<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One event to be sent to one webhook, and how sending it went.
+#[derive(FromRow)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub event: String,
+    /// JSON body sent.
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    /// Status the webhook answered with on the last attempt, if it answered.
+    pub response_status: Option<i64>,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A delivery that is due, with where to send it.
+pub struct DueDelivery {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub event: String,
+    pub payload: String,
+    pub attempts: i64,
+}
+
+/// The outcome of a delivery attempt.
+pub struct DeliveryAttempt {
+    pub status: DeliveryStatus,
+    pub attempted_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i64>,
+    pub error: Option<String>,
+}
+
+impl Webhook {
+    pub async fn create(
+        pool: &SqlitePool,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> Result<Webhook, sqlx::Error> {
+        let events = Json(events);
+        let created_at = Utc::now();
+
+        query_as!(
+            Webhook,
+            r#"
+            INSERT INTO webhooks (url, secret, events, created_at) VALUES (?, ?, ?, ?)
+            RETURNING id, url, secret, events as "events: Json<Vec<String>>",
+                created_at as "created_at: DateTime<Utc>"
+            "#,
+            url,
+            secret,
+            events,
+            created_at
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn fetch_all(pool: &SqlitePool) -> Result<Vec<Webhook>, sqlx::Error> {
+        query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events as "events: Json<Vec<String>>",
+                created_at as "created_at: DateTime<Utc>"
+            FROM webhooks ORDER BY id
+            "#
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Webhooks subscribed to an event.
+    pub async fn fetch_for_event(
+        pool: &SqlitePool,
+        event: &str,
+    ) -> Result<Vec<Webhook>, sqlx::Error> {
+        query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events as "events: Json<Vec<String>>",
+                created_at as "created_at: DateTime<Utc>"
+            FROM webhooks
+            WHERE EXISTS (SELECT 1 FROM json_each(webhooks.events) WHERE value = ?)
+            ORDER BY id
+            "#,
+            event
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn exists(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+        let row = query!(
+            r#"SELECT EXISTS(SELECT 1 FROM webhooks WHERE id = ?) as "exists!: bool""#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.exists)
+    }
+
+    /// Delete a webhook and its deliveries. Returns false if not found.
+    pub async fn delete_by_id(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+        let result = query!("DELETE FROM webhooks WHERE id = ?", id)
             .execute(pool)
             .await?;
-        Ok(result.rows_affected())
+        Ok(result.rows_affected() > 0)
     }
+}
 
-    /// Delete entries beyond the N most recent. Returns the number of entries deleted.
-    pub async fn delete_beyond_limit(pool: &SqlitePool, max: u32) -> Result<u64, sqlx::Error> {
+impl WebhookDelivery {
+    /// Queue an event for a webhook, due right away.
+    pub async fn enqueue(
+        pool: &SqlitePool,
+        webhook_id: i64,
+        event: &str,
+        payload: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let now = Utc::now();
+        let row = query!(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event, payload, next_attempt_at, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING id as "id!"
+            "#,
+            webhook_id,
+            event,
+            payload,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.id)
+    }
+
+    /// Pending deliveries due at `now`, oldest first.
+    pub async fn fetch_due(
+        pool: &SqlitePool,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<DueDelivery>, sqlx::Error> {
+        query_as!(
+            DueDelivery,
+            r#"
+            SELECT d.id as "id!", w.url, w.secret, d.event, d.payload, d.attempts
+            FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.status = 0 AND julianday(d.next_attempt_at) <= julianday(?)
+            ORDER BY d.next_attempt_at, d.id
+            LIMIT ?
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn record_attempt(
+        pool: &SqlitePool,
+        id: i64,
+        attempt: &DeliveryAttempt,
+    ) -> Result<bool, sqlx::Error> {
         let result = query!(
             r#"
-            DELETE FROM entries WHERE id NOT IN (
-                SELECT id FROM entries ORDER BY created_at DESC LIMIT ?
-            )
+            UPDATE webhook_deliveries
+            SET status = ?, attempts = attempts + 1, last_attempt_at = ?, next_attempt_at = ?,
+                response_status = ?, error = ?
+            WHERE id = ?
             "#,
-            max
+            attempt.status,
+            attempt.attempted_at,
+            attempt.next_attempt_at,
+            attempt.response_status,
+            attempt.error,
+            id
         )
         .execute(pool)
         .await?;
-        Ok(result.rows_affected())
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The latest deliveries to a webhook, newest first.
+    pub async fn fetch_for_webhook(
+        pool: &SqlitePool,
+        webhook_id: i64,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id as "id!", event, payload, status as "status: DeliveryStatus",
+                attempts,
+                next_attempt_at as "next_attempt_at: DateTime<Utc>",
+                last_attempt_at as "last_attempt_at: DateTime<Utc>", response_status, error,
+                created_at as "created_at: DateTime<Utc>"
+            FROM webhook_deliveries WHERE webhook_id = ?
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            webhook_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
     }
 }
 
+/// The host of a URL, lowercased and without a leading `www.`, as stored in `domain`.
+pub fn url_domain(url: &str) -> Option<String> {
+    let host = Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    Some(
+        host.strip_prefix("www.")
+            .map(str::to_string)
+            .unwrap_or(host),
+    )
+}
+
+/// A `LIKE` pattern matching values that contain `text`, with `\` as the escape character.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,4 +971,23 @@ mod tests {
         assert_eq!(EntrySourceType::from(i64::MAX), EntrySourceType::Article);
         assert_eq!(EntrySourceType::from(i64::MIN), EntrySourceType::Article);
     }
+
+    #[test]
+    fn url_domain_strips_www() {
+        assert_eq!(
+            url_domain("https://WWW.Example.com:8080/a").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            url_domain("https://blog.example.com/").as_deref(),
+            Some("blog.example.com")
+        );
+        assert_eq!(url_domain("not a url"), None);
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("rust"), "%rust%");
+        assert_eq!(like_pattern("100%_\\"), "%100\\%\\_\\\\%");
+    }
 }
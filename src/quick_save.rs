@@ -0,0 +1,190 @@
+use axum::{
+    http::{StatusCode, Uri, header},
+    response::{IntoResponse, Redirect, Response},
+};
+use url::{Url, form_urlencoded};
+
+use crate::{
+    AppState, auth,
+    dto::QuickSaveParams,
+    errors::Result,
+    metadata::escape_html,
+    models::Entry,
+    save::{self, SaveRequest},
+};
+
+/// Confirmation pages load nothing and can't be framed, so a page can't trick the user into
+/// saving by overlaying one.
+const PAGE_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'; frame-ancestors 'none'";
+
+/// Save a link given as plain parameters, for clients that can't send JSON with a bearer
+/// header. The token parameter is the only credential, so another site can't make the browser
+/// save anything without knowing it. Responds with an HTML page, or a redirect to the saved
+/// page, never anywhere else.
+pub async fn quick_save(state: &AppState, params: QuickSaveParams) -> Result<Response> {
+    if !auth::is_valid_token(state, params.token.as_deref().unwrap_or_default()) {
+        return Ok(page(
+            StatusCode::UNAUTHORIZED,
+            "Not saved",
+            "Invalid token.",
+            None,
+        ));
+    }
+
+    let Some(url) = params.url.as_deref().and_then(parse_url) else {
+        return Ok(page(
+            StatusCode::BAD_REQUEST,
+            "Not saved",
+            "That isn't a link to a web page.",
+            None,
+        ));
+    };
+
+    // Saving the same link again, e.g. from a reloaded page, just confirms it
+    let normalized = save::normalize_url(&url);
+    let (status, heading, entry) =
+        match Entry::fetch_by_url(&state.pool, &[&normalized, &url]).await? {
+            Some(entry) => (StatusCode::OK, "Already saved", entry),
+            None => match save::save_entry(
+                state,
+                SaveRequest {
+                    url,
+                    title: params.title.filter(|title| !title.trim().is_empty()),
+                    body: None,
+                    source_type: params.source_type.map(Into::into),
+                    tags: params
+                        .tags
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(str::to_string)
+                        .collect(),
+                    created_at: None,
+                    fetch_metadata: true,
+                },
+            )
+            .await
+            {
+                Ok(entry) => (StatusCode::CREATED, "Saved", entry),
+                // Saved by another request in the meantime
+                Err(e) if save::is_duplicate(&e) => {
+                    let entry = Entry::fetch_by_url(&state.pool, &[&normalized])
+                        .await?
+                        .ok_or(e)?;
+                    (StatusCode::OK, "Already saved", entry)
+                }
+                Err(e) => return Err(e.into()),
+            },
+        };
+
+    if params.redirect.unwrap_or(false) {
+        return Ok(with_headers(Redirect::to(&entry.url).into_response()));
+    }
+
+    Ok(page(status, heading, &entry.title, Some(&entry.url)))
+}
+
+/// The page for parameters that couldn't be read, e.g. an unknown `source_type`.
+pub fn invalid_params(error: &str) -> Response {
+    page(StatusCode::BAD_REQUEST, "Not saved", error, None)
+}
+
+/// A request's path and query for logs, with the value of the `token` parameter hidden.
+pub fn redact_token(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.path().to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| {
+            // Names are decoded when parsed, so `tok%65n` is a token too
+            let is_token = form_urlencoded::parse(pair.as_bytes())
+                .next()
+                .is_some_and(|(name, _)| name == "token");
+            if is_token { "token=REDACTED" } else { pair }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}?{query}", uri.path())
+}
+
+/// Accept only HTTP(S) URLs, which are the only ones a redirect back can go to safely.
+fn parse_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let parsed = Url::parse(url).ok()?;
+    matches!(parsed.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+fn page(status: StatusCode, heading: &str, message: &str, link: Option<&str>) -> Response {
+    let link = link
+        .map(|url| {
+            format!(
+                r#"<p><a href="{}">Back to the page</a></p>"#,
+                escape_html(url)
+            )
+        })
+        .unwrap_or_default();
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{heading} - Laterfeed</title>
+<style>body {{ font-family: system-ui, sans-serif; margin: 2em; }}</style>
+</head>
+<body>
+<h1>{heading}</h1>
+<p>{}</p>
+{link}
+</body>
+</html>
+"#,
+        escape_html(message)
+    );
+
+    with_headers(
+        (
+            status,
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            html,
+        )
+            .into_response(),
+    )
+}
+
+/// Keep the token out of caches and out of the `Referer` sent when leaving the page.
+fn with_headers(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, "no-store".parse().unwrap());
+    headers.insert(header::REFERRER_POLICY, "no-referrer".parse().unwrap());
+    headers.insert(header::CONTENT_SECURITY_POLICY, PAGE_CSP.parse().unwrap());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_only_accepts_web_pages() {
+        assert_eq!(
+            parse_url(" https://example.com/a ").as_deref(),
+            Some("https://example.com/a")
+        );
+        assert_eq!(parse_url("javascript:alert(1)"), None);
+        assert_eq!(parse_url("example.com"), None);
+    }
+
+    #[test]
+    fn redact_token_hides_only_the_token() {
+        let redact = |uri: &str| redact_token(&uri.parse().unwrap());
+
+        assert_eq!(
+            redact("/save?url=https%3A%2F%2Fexample.com&token=s3cret&redirect=true"),
+            "/save?url=https%3A%2F%2Fexample.com&token=REDACTED&redirect=true"
+        );
+        assert_eq!(redact("/save?tok%65n=s3cret"), "/save?token=REDACTED");
+        assert_eq!(redact("/entries?q=token"), "/entries?q=token");
+        assert_eq!(redact("/feed"), "/feed");
+    }
+}
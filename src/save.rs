@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, stream};
+use sqlx::types::Json;
+use tracing::warn;
+use url::Url;
+
+use crate::{
+    AppState, archive, events, media, metadata,
+    models::{Entry, EntrySourceType, NewEntry},
+};
+
+/// Pages fetched at the same time when saving a batch. Fetches to the same host are further
+/// limited by the fetcher.
+const BATCH_FETCH_CONCURRENCY: usize = 8;
+
+/// Query parameters that only track where a link was shared, removed when saving.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi",
+];
+
+/// What to save, as given by a client or an import.
+#[derive(Default)]
+pub struct SaveRequest {
+    pub url: String,
+    /// Title to use instead of the page's.
+    pub title: Option<String>,
+    /// Body to use instead of the page's, e.g. from an export.
+    pub body: Option<String>,
+    /// Source type to use instead of the one detected from the page.
+    pub source_type: Option<EntrySourceType>,
+    pub tags: Vec<String>,
+    /// When the link was originally saved, for imports. Defaults to now.
+    pub created_at: Option<DateTime<Utc>>,
+    /// Whether to fetch the page for its metadata. Without it the entry only has a title.
+    pub fetch_metadata: bool,
+}
+
+/// Save an entry: fetch the page's metadata, store it, and start archiving and image caching
+/// in the background if enabled. A trashed entry with the same URL is replaced.
+pub async fn save_entry(state: &AppState, mut request: SaveRequest) -> Result<Entry, sqlx::Error> {
+    request.url = normalize_url(&request.url);
+    let prepared = prepare_entry(state, request).await;
+    Entry::purge_trashed_url(&state.pool, &prepared.entry.url).await?;
+    let entry = Entry::create(&state.pool, &prepared.entry).await?;
+    prepared.after_save(state, &entry).await;
+
+    Ok(entry)
+}
+
+/// Store an entry exported by this server as it was, without fetching its page. Its snapshot
+/// is carried over if it's in the data directory, e.g. when the directory was copied along. A
+/// trashed entry with the same URL is replaced.
+pub async fn restore_entry(state: &AppState, mut new: NewEntry) -> Result<Entry, sqlx::Error> {
+    new.url = normalize_url(&new.url);
+    let archive_path = new.archive_path.take();
+
+    Entry::purge_trashed_url(&state.pool, &new.url).await?;
+    let mut entry = Entry::create(&state.pool, &new).await?;
+
+    if let Some(relative) = archive_path {
+        match archive::restore_snapshot(&state.config.data_dir(), entry.id, &relative).await {
+            Ok(Some(path)) => {
+                Entry::set_archive_path(&state.pool, entry.id, &path).await?;
+                entry.archive_path = Some(path);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(
+                "Failed to restore snapshot {} of {}: {}",
+                relative, entry.url, e
+            ),
+        }
+    }
+
+    if entry.deleted_at.is_none() {
+        events::publish(state, events::Event::Created, &entry).await;
+    }
+
+    Ok(entry)
+}
+
+/// Whether saving failed because the URL is already saved, e.g. by a request that raced this
+/// one.
+pub fn is_duplicate(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|e| e.is_unique_violation())
+}
+
+/// What happened to one link of a batch.
+pub enum BatchOutcome {
+    Created(Box<Entry>),
+    /// Skipped since the URL was already saved, or came earlier in the batch.
+    Duplicate,
+    Failed(String),
+}
+
+/// Save many entries at once. Pages are fetched concurrently, up to `BATCH_FETCH_CONCURRENCY`
+/// at a time, then all entries are stored in one transaction, replacing trashed entries with
+/// the same URLs. Outcomes are in request order; only a failure to commit fails the whole batch.
+pub async fn save_batch(
+    state: &AppState,
+    requests: Vec<SaveRequest>,
+) -> Result<Vec<BatchOutcome>, sqlx::Error> {
+    // Leave out links already saved before spending a fetch on them
+    let mut seen = HashSet::new();
+    let mut pending = Vec::with_capacity(requests.len());
+    for mut request in requests {
+        request.url = normalize_url(&request.url);
+        let duplicate = !seen.insert(request.url.clone())
+            || Entry::exists_with_url(&state.pool, &request.url).await?;
+        pending.push((!duplicate).then_some(request));
+    }
+
+    let prepared: Vec<Option<PreparedEntry>> = stream::iter(pending)
+        .map(|request| async move {
+            match request {
+                Some(request) => Some(prepare_entry(state, request).await),
+                None => None,
+            }
+        })
+        .buffered(BATCH_FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut tx = state.pool.begin().await?;
+    let mut outcomes = Vec::with_capacity(prepared.len());
+    for prepared in &prepared {
+        let Some(prepared) = prepared else {
+            outcomes.push(BatchOutcome::Duplicate);
+            continue;
+        };
+        // A failed insert leaves the rest of an SQLite transaction usable
+        let created = match Entry::purge_trashed_url(&mut *tx, &prepared.entry.url).await {
+            Ok(_) => Entry::create(&mut *tx, &prepared.entry).await,
+            Err(e) => Err(e),
+        };
+        outcomes.push(match created {
+            Ok(entry) => BatchOutcome::Created(Box::new(entry)),
+            Err(e) if is_duplicate(&e) => BatchOutcome::Duplicate,
+            Err(e) => {
+                warn!("Failed to save {}: {}", prepared.entry.url, e);
+                BatchOutcome::Failed(e.to_string())
+            }
+        });
+    }
+    tx.commit().await?;
+
+    for (prepared, outcome) in prepared.iter().zip(&outcomes) {
+        if let (Some(prepared), BatchOutcome::Created(entry)) = (prepared, outcome) {
+            prepared.after_save(state, entry).await;
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// An entry ready to be stored, with its page's metadata fetched.
+struct PreparedEntry {
+    entry: NewEntry,
+    fetched: bool,
+}
+
+impl PreparedEntry {
+    /// Publish the new entry, and start archiving the page and caching the body's images in the
+    /// background if enabled.
+    async fn after_save(&self, state: &AppState, entry: &Entry) {
+        events::publish(state, events::Event::Created, entry).await;
+
+        // Snapshot the page in the background, it can take a while with many images
+        if self.fetched && state.config.archive_pages.unwrap_or(false) {
+            tokio::spawn(archive::archive_entry(
+                state.clone(),
+                entry.id,
+                entry.url.clone(),
+            ));
+        }
+
+        // Same for caching the body's images locally
+        if state.config.cache_images.unwrap_or(false)
+            && let Some(body) = entry.body.clone()
+        {
+            tokio::spawn(media::cache_entry_images(
+                state.clone(),
+                entry.id,
+                entry.url.clone(),
+                body,
+            ));
+        }
+    }
+}
+
+/// Fetch the page's metadata if asked to, and merge it with what the request gives.
+async fn prepare_entry(state: &AppState, request: SaveRequest) -> PreparedEntry {
+    let (meta, report) = if request.fetch_metadata {
+        let (meta, report) = metadata::fetch_metadata(&state.fetcher, &request.url).await;
+        (meta, Some(report))
+    } else {
+        Default::default()
+    };
+
+    // Fall back to using the URL as the title if still missing
+    let title = request
+        .title
+        .or(meta.title)
+        .unwrap_or_else(|| request.url.clone());
+
+    // Use the given source type if any, otherwise whatever the page suggests
+    let (source_type, source_type_inferred) = match request.source_type {
+        Some(source_type) => (source_type, false),
+        None => (meta.source_type.unwrap_or_default(), true),
+    };
+
+    let tags = normalize_tags(request.tags);
+
+    PreparedEntry {
+        entry: NewEntry {
+            url: request.url,
+            title,
+            body: request.body.or(meta.body),
+            source_type,
+            source_type_inferred,
+            author: meta.author,
+            published_at: meta.published_at,
+            site_name: meta.site_name,
+            canonical_url: meta.canonical_url,
+            image_url: meta.image_url,
+            language: meta.language,
+            word_count: meta.word_count,
+            duration_secs: meta.duration_secs,
+            author_url: meta.author_url,
+            chapters: (!meta.chapters.is_empty()).then_some(Json(meta.chapters)),
+            transcript: (!meta.transcript.is_empty()).then_some(Json(meta.transcript)),
+            fetch_rule: meta.fetch_rule,
+            fetch_outcome: report.as_ref().map(|report| report.outcome),
+            fetch_http_status: report
+                .as_ref()
+                .and_then(|report| report.http_status)
+                .map(i64::from),
+            final_url: meta.final_url,
+            fetch_duration_ms: report
+                .as_ref()
+                .map(|report| i64::try_from(report.duration.as_millis()).unwrap_or(i64::MAX)),
+            tags: (!tags.is_empty()).then_some(Json(tags)),
+            created_at: request.created_at,
+            ..Default::default()
+        },
+        fetched: request.fetch_metadata,
+    }
+}
+
+/// Trim tags and drop empty and duplicate ones, ignoring case, keeping the first spelling.
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// Normalize a URL so the same link is saved once: the scheme and host are lowercased, default
+/// ports, the fragment and tracking parameters such as `utm_source` are removed. Fragments that
+/// look like routes of a single-page app (`#/path`, `#!path`) are kept, since they pick the
+/// page. Anything that isn't an HTTP(S) URL is only trimmed.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return url.to_string();
+    }
+
+    if !parsed
+        .fragment()
+        .is_some_and(|fragment| fragment.starts_with('!') || fragment.contains('/'))
+    {
+        parsed.set_fragment(None);
+    }
+
+    // Filter the raw pairs rather than re-encoding them, which could change the URL
+    if let Some(query) = parsed.query() {
+        let query = query
+            .split('&')
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or_default();
+                !pair.is_empty()
+                    && !name.to_ascii_lowercase().starts_with("utm_")
+                    && !TRACKING_PARAMS
+                        .iter()
+                        .any(|param| name.eq_ignore_ascii_case(param))
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        parsed.set_query((!query.is_empty()).then_some(&query));
+    }
+
+    parsed.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_tags_trims_and_dedupes() {
+        let tags = vec![
+            " rust ".to_string(),
+            "".to_string(),
+            "Rust".to_string(),
+            "web dev".to_string(),
+        ];
+        assert_eq!(normalize_tags(tags), vec!["rust", "web dev"]);
+    }
+
+    #[test]
+    fn normalize_url_removes_tracking_and_fragments() {
+        assert_eq!(
+            normalize_url(" HTTPS://Example.COM:443/Post?id=1&utm_source=x&fbclid=y#comments "),
+            "https://example.com/Post?id=1"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/?utm_medium=email"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/search?q=a%20b+c&ref=1"),
+            "https://example.com/search?q=a%20b+c&ref=1"
+        );
+        assert_eq!(normalize_url("https://example.com"), "https://example.com/");
+        assert_eq!(
+            normalize_url("https://app.example.com/#/notes/1?utm_source=x"),
+            "https://app.example.com/#/notes/1?utm_source=x"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/#!page"),
+            "https://example.com/#!page"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/docs#section/intro"),
+            "https://example.com/docs#section/intro"
+        );
+        assert_eq!(
+            normalize_url("mailto:me@example.com"),
+            "mailto:me@example.com"
+        );
+    }
+}
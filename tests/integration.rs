@@ -1,6 +1,9 @@
+use std::time::Duration;
+
 use axum::{
     body::Body,
     http::{Request, StatusCode, header},
+    routing::get,
 };
 use http_body_util::BodyExt;
 use laterfeed::config::Config;
@@ -132,6 +135,358 @@ async fn add_entry_without_title_uses_url_as_fallback() {
     assert_eq!(json["title"], "https://invalid.nonexistent.example/page");
 }
 
+#[tokio::test]
+async fn add_entry_with_saved_url_returns_conflict() {
+    let app = setup_app().await;
+
+    let mut statuses = Vec::new();
+    for url in [
+        "https://invalid.nonexistent.example/saved",
+        "https://INVALID.nonexistent.example/saved?utm_source=x#top",
+    ] {
+        let body = json!({ "url": url, "title": "Saved" });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/entries")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        statuses.push(response.status());
+    }
+
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::CONFLICT]);
+}
+
+#[tokio::test]
+async fn add_entry_without_source_type_infers_it() {
+    let app = setup_app().await;
+
+    // The page can't be fetched and the host gives no hint, so it defaults to an article
+    let body = json!({
+        "url": "https://invalid.nonexistent.example/unknown",
+        "title": "Unknown Type"
+    });
+
+    let response = app
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["source_type"], "article");
+    assert_eq!(json["source_type_inferred"], true);
+}
+
+#[tokio::test]
+async fn add_entry_with_source_type_is_not_inferred() {
+    let app = setup_app().await;
+
+    let body = json!({
+        "url": "https://invalid.nonexistent.example/video",
+        "title": "Explicit Video",
+        "source_type": "video"
+    });
+
+    let response = app
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["source_type"], "video");
+    assert_eq!(json["source_type_inferred"], false);
+}
+
+#[tokio::test]
+async fn add_entries_reports_each_result() {
+    let app = setup_app().await;
+
+    let post = |uri: &str, body: Value| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+    };
+
+    post(
+        "/entries",
+        json!({"url": "https://example.com/saved", "title": "Saved"}),
+    )
+    .await
+    .unwrap();
+
+    let response = post(
+        "/entries/batch",
+        json!([
+            {"url": "https://example.com/saved"},
+            {"url": "https://example.com/new", "title": "New", "tags": ["rust"]},
+            {"url": "not a url"},
+            {"url": "https://example.com/new"},
+            {"url": "https://example.com/other", "title": "Other", "source_type": "video"}
+        ]),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let entries = json["entries"].as_array().unwrap();
+    let statuses: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        statuses,
+        vec!["duplicate", "created", "invalid", "duplicate", "created"]
+    );
+    assert_eq!(entries[1]["entry"]["title"], "New");
+    assert_eq!(entries[1]["entry"]["tags"], json!(["rust"]));
+    assert_eq!(entries[2]["url"], "not a url");
+    assert!(entries[2]["error"].is_string());
+    assert_eq!(entries[4]["entry"]["source_type"], "video");
+
+    let response = app
+        .oneshot(Request::get("/entries").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["entries"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn add_entries_rejects_oversized_batches() {
+    let app = setup_app().await;
+
+    let body: Vec<Value> = (0..101)
+        .map(|i| json!({"url": format!("https://example.com/{i}")}))
+        .collect();
+
+    let response = app
+        .oneshot(
+            Request::post("/entries/batch")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// --- Get entry ---
+
+#[tokio::test]
+async fn get_entry_returns_entry_or_not_found() {
+    let app = setup_app().await;
+
+    let body = json!({"url": "https://example.com/single", "title": "Single"});
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let created: Value = serde_json::from_slice(&body).unwrap();
+    let id = created["id"].as_i64().unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get(format!("/entries/{id}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["title"], "Single");
+
+    let response = app
+        .oneshot(
+            Request::get(format!("/entries/{}", id + 1))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn lookup_entry_normalizes_url() {
+    let app = setup_app().await;
+
+    let body = json!({"url": "https://Example.com/post?id=1&utm_source=feed#top", "title": "Post"});
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let created: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(created["url"], "https://example.com/post?id=1");
+
+    let lookup = |url: &str| {
+        app.clone().oneshot(
+            Request::get(format!(
+                "/entries/lookup?url={}",
+                url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>()
+            ))
+            .body(Body::empty())
+            .unwrap(),
+        )
+    };
+
+    let response = lookup("https://example.com/post?id=1&fbclid=abc")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["id"], created["id"]);
+
+    let response = lookup("https://example.com/post?id=2").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// --- Quick save ---
+
+#[tokio::test]
+async fn quick_save_saves_and_confirms() {
+    let app = setup_app().await;
+
+    let save = |query: &str| {
+        app.clone().oneshot(
+            Request::get(format!("/save?{query}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    let response = save("url=https%3A%2F%2Fexample.com%2Fshared&title=%3Cb%3EShared%3C%2Fb%3E&tags=phone,later&token=test-token")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    assert_eq!(response.headers()[header::REFERRER_POLICY], "no-referrer");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+    assert!(html.contains("<h1>Saved</h1>"));
+    assert!(html.contains("&lt;b&gt;Shared&lt;/b&gt;"));
+
+    let response =
+        save("url=https%3A%2F%2Fexample.com%2Fshared%23top&token=test-token&redirect=true")
+            .await
+            .unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        "https://example.com/shared"
+    );
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/entries").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(json["entries"][0]["tags"], json!(["phone", "later"]));
+
+    let response = save("url=https%3A%2F%2Fexample.com%2Fother&token=wrong")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = save("url=javascript%3Aalert(1)&token=test-token")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response =
+        save("url=https%3A%2F%2Fexample.com%2Fother&source_type=podcast&token=test-token")
+            .await
+            .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+    assert!(html.contains("<h1>Not saved</h1>"));
+}
+
+#[tokio::test]
+async fn quick_save_accepts_forms() {
+    let app = setup_app().await;
+
+    let response = app
+        .oneshot(
+            Request::post("/save")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(
+                    "url=https%3A%2F%2Fexample.com%2Fform&title=Form&source_type=video&token=test-token",
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+    assert!(html.contains(r#"<a href="https://example.com/form">"#));
+}
+
 // --- List entries ---
 
 #[tokio::test]
@@ -184,6 +539,174 @@ async fn list_entries_returns_created_entries() {
     assert_eq!(entries[0]["title"], "Listed Entry");
 }
 
+#[tokio::test]
+async fn list_entries_filters_on_dead_flag() {
+    let app = setup_app().await;
+
+    let add_body = json!({
+        "url": "https://example.com/alive",
+        "title": "Alive",
+        "source_type": "article"
+    });
+    app.clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&add_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    for (query, expected) in [("?dead=true", 0), ("?dead=false", 1), ("", 1)] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get(format!("/entries{query}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["entries"].as_array().unwrap().len(),
+            expected,
+            "{query}"
+        );
+        if expected > 0 {
+            assert_eq!(json["entries"][0]["dead"], false);
+        }
+    }
+}
+
+#[tokio::test]
+async fn list_entries_searches_text() {
+    let app = setup_app().await;
+
+    for (url, title) in [
+        ("https://example.com/rust", "Learning Rust"),
+        ("https://example.com/go", "Learning Go"),
+    ] {
+        let add_body = json!({ "url": url, "title": title, "source_type": "article" });
+        app.clone()
+            .oneshot(
+                Request::post("/entries")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(Body::from(serde_json::to_string(&add_body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    for (query, expected) in [("rust", 1), ("LEARNING", 2), ("100%25", 0)] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get(format!("/entries?q={query}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["entries"].as_array().unwrap().len(),
+            expected,
+            "{query}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_entries_filters_and_sorts() {
+    let app = setup_app().await;
+
+    // Import entries with known save times and bodies, without fetching anything
+    let export = json!({"entries": [
+        {"url": "https://www.example.com/a", "title": "banana", "source_type": "article",
+         "body": "<p>Text</p>", "created_at": "2024-01-01T00:00:00Z"},
+        {"url": "https://blog.example.com/b", "title": "Apple", "source_type": "video",
+         "created_at": "2024-02-01T00:00:00Z"},
+        {"url": "https://other.org/c", "title": "cherry", "source_type": "article",
+         "created_at": "2024-03-01T00:00:00Z"}
+    ]});
+    app.clone()
+        .oneshot(
+            Request::post("/imports?fetch=false")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(export.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let titles = |query: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::get(format!("/entries?{query}"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{query}");
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: Value = serde_json::from_slice(&body).unwrap();
+            json["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| entry["title"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    for _ in 0..50 {
+        if titles("").await.len() == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    for (query, expected) in [
+        ("", vec!["cherry", "Apple", "banana"]),
+        ("sort=oldest", vec!["banana", "Apple", "cherry"]),
+        ("sort=title", vec!["Apple", "banana", "cherry"]),
+        ("sort=domain", vec!["Apple", "banana", "cherry"]),
+        ("source_type=video", vec!["Apple"]),
+        ("saved_after=2024-02-01T00:00:00Z", vec!["cherry", "Apple"]),
+        ("saved_before=2024-02-01T00:00:00Z", vec!["banana"]),
+        ("domain=example.com", vec!["Apple", "banana"]),
+        ("domain=WWW.example.com", vec!["Apple", "banana"]),
+        ("domain=blog.example.com", vec!["Apple"]),
+        ("has_body=true", vec!["banana"]),
+        ("has_body=false&source_type=article", vec!["cherry"]),
+    ] {
+        assert_eq!(titles(query).await, expected, "{query}");
+    }
+
+    let response = app
+        .oneshot(
+            Request::get("/entries?sort=random")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 // --- Feed ---
 
 #[tokio::test]
@@ -353,3 +876,1338 @@ async fn delete_entry_removes_entry() {
     let entries = json["entries"].as_array().unwrap();
     assert!(entries.is_empty());
 }
+
+// --- Archive ---
+
+/// Serve a small site on a random local port, returning its base URL.
+async fn spawn_site(site: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, site).await.unwrap() });
+    format!("http://{addr}")
+}
+
+#[tokio::test]
+async fn get_archive_for_unarchived_entry_returns_not_found() {
+    let app = setup_app().await;
+
+    let body = json!({
+        "url": "https://example.com/not-archived",
+        "title": "Not Archived",
+        "source_type": "article"
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["archived"], false);
+    let id = json["id"].as_i64().unwrap();
+
+    let response = app
+        .oneshot(
+            Request::get(format!("/entries/{id}/archive"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn add_entry_archives_page_when_enabled() {
+    let site = spawn_site(
+        axum::Router::new()
+            .route(
+                "/post",
+                get(|| async {
+                    axum::response::Html(
+                        r#"<html><head><title>Archived Post</title><link rel="stylesheet" href="/style.css"></head>
+                        <body><p>Hello</p><img src="/pixel.gif"><img src="/large.gif"><script>alert(1)</script></body></html>"#,
+                    )
+                }),
+            )
+            .route(
+                "/style.css",
+                get(|| async { ([(header::CONTENT_TYPE, "text/css")], "p { color: red }") }),
+            )
+            .route(
+                "/pixel.gif",
+                get(|| async { ([(header::CONTENT_TYPE, "image/gif")], &b"GIF89a"[..]) }),
+            )
+            .route(
+                "/large.gif",
+                get(|| async {
+                    let mut gif = b"GIF89a".to_vec();
+                    gif.resize(3 * 1024 * 1024, 0);
+                    ([(header::CONTENT_TYPE, "image/gif")], gif)
+                }),
+            ),
+    )
+    .await;
+
+    let data_dir = std::env::temp_dir().join(format!(
+        "laterfeed-test-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    let config = Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        data_dir: Some(data_dir.to_string_lossy().into_owned()),
+        archive_pages: Some(true),
+        fetch_allowlist: Some("127.0.0.1".to_string()),
+        fetch_host_delay_ms: Some(0),
+        ..Default::default()
+    };
+    let (router, _, _) = laterfeed::app(config).await;
+
+    let body = json!({ "url": format!("{site}/post") });
+    let response = router
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["title"], "Archived Post");
+    let id = json["id"].as_i64().unwrap();
+
+    // Archiving happens in the background
+    let mut archive = None;
+    for _ in 0..50 {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::get(format!("/entries/{id}/archive"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        if response.status() == StatusCode::OK {
+            archive = Some(response);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let response = archive.expect("page was not archived");
+
+    assert!(
+        response
+            .headers()
+            .get(header::CONTENT_SECURITY_POLICY)
+            .is_some()
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let html = String::from_utf8(body.to_vec()).unwrap();
+
+    assert!(html.contains("<style>p { color: red }</style>"));
+    assert!(html.contains(r#"<img src="data:image/gif;base64,R0lGODlh">"#));
+    // Too large to inline, so left pointing at the site
+    assert!(html.contains(r#"<img src="/large.gif">"#));
+    assert!(!html.contains("<script"));
+
+    // Trashing the entry keeps its snapshot, deleting it for good removes it
+    router
+        .clone()
+        .oneshot(
+            Request::delete(format!("/entries/{id}"))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(data_dir.join(format!("archive/{id}.html")).exists());
+    router
+        .oneshot(
+            Request::delete(format!("/entries/{id}?permanent=true"))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(!data_dir.join(format!("archive/{id}.html")).exists());
+
+    let _ = std::fs::remove_dir_all(data_dir);
+}
+
+#[tokio::test]
+async fn add_entry_caches_images_when_enabled() {
+    let site = spawn_site(
+        axum::Router::new()
+            .route(
+                "/post",
+                get(|| async {
+                    axum::response::Html(
+                        r#"<html><head><title>Post</title></head>
+                        <body><article><p>Hello</p><img src="/pixel.gif" srcset="/pixel@2x.gif 2x"></article></body></html>"#,
+                    )
+                }),
+            )
+            .route(
+                "/pixel.gif",
+                get(|| async { ([(header::CONTENT_TYPE, "image/gif")], &b"GIF89a"[..]) }),
+            ),
+    )
+    .await;
+
+    let data_dir = std::env::temp_dir().join(format!(
+        "laterfeed-test-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    let config = Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        data_dir: Some(data_dir.to_string_lossy().into_owned()),
+        cache_images: Some(true),
+        fetch_allowlist: Some("127.0.0.1".to_string()),
+        fetch_host_delay_ms: Some(0),
+        ..Default::default()
+    };
+    let (router, _, _) = laterfeed::app(config).await;
+
+    let body = json!({ "url": format!("{site}/post") });
+    router
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Caching happens in the background
+    let mut cached_body = None;
+    for _ in 0..50 {
+        let response = router
+            .clone()
+            .oneshot(Request::get("/entries").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let entry_body = json["entries"][0]["body"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        if entry_body.contains("/media/") {
+            cached_body = Some(entry_body);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let cached_body = cached_body.expect("images were not cached");
+    assert!(!cached_body.contains("srcset"));
+
+    let start = cached_body.find("http://localhost:3000/media/").unwrap();
+    let path = &cached_body[start + "http://localhost:3000".len()..];
+    let path = &path[..path.find('"').unwrap()];
+
+    let response = router
+        .oneshot(Request::get(path).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/gif");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&body[..], b"GIF89a");
+
+    let _ = std::fs::remove_dir_all(data_dir);
+}
+
+#[tokio::test]
+async fn fetch_rule_headers_do_not_follow_redirects_to_other_hosts() {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::HeaderMap, response::Redirect};
+
+    type Keys = Arc<Mutex<Vec<(&'static str, Option<String>)>>>;
+    let keys: Keys = Arc::default();
+    let api_key = |headers: &HeaderMap| {
+        headers
+            .get("x-api-key")
+            .map(|value| value.to_str().unwrap().to_string())
+    };
+
+    let other = spawn_site(
+        axum::Router::new()
+            .route(
+                "/page",
+                get(async move |State(keys): State<Keys>, headers: HeaderMap| {
+                    keys.lock().unwrap().push(("other", api_key(&headers)));
+                    axum::response::Html("<html><head><title>Elsewhere</title></head></html>")
+                }),
+            )
+            .with_state(keys.clone()),
+    )
+    .await;
+    // Another host name for the same loopback address
+    let other = other.replace("127.0.0.1", "localhost");
+    let site = spawn_site(
+        axum::Router::new()
+            .route(
+                "/start",
+                get(async move |State(keys): State<Keys>, headers: HeaderMap| {
+                    keys.lock().unwrap().push(("rule", api_key(&headers)));
+                    Redirect::temporary(&format!("{other}/page"))
+                }),
+            )
+            .with_state(keys.clone()),
+    )
+    .await;
+
+    let rules_file = std::env::temp_dir().join(format!(
+        "laterfeed-rules-{}.json",
+        site.rsplit(':').next().unwrap()
+    ));
+    std::fs::write(
+        &rules_file,
+        r#"[{"name": "local", "hosts": ["127.0.0.1"], "headers": {"X-Api-Key": "s3cret"}}]"#,
+    )
+    .unwrap();
+    let config = Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        fetch_allowlist: Some("127.0.0.1,localhost".to_string()),
+        fetch_rules_file: Some(rules_file.to_string_lossy().into_owned()),
+        fetch_host_delay_ms: Some(0),
+        ..Default::default()
+    };
+    let (router, _, _) = laterfeed::app(config).await;
+    std::fs::remove_file(rules_file).unwrap();
+
+    let body = json!({ "url": format!("{site}/start") });
+    let response = router
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["title"], "Elsewhere");
+
+    assert_eq!(
+        *keys.lock().unwrap(),
+        [("rule", Some("s3cret".to_string())), ("other", None)]
+    );
+}
+
+#[tokio::test]
+async fn get_media_rejects_invalid_hashes() {
+    let app = setup_app().await;
+
+    let response = app
+        .oneshot(
+            Request::get("/media/..%2F..%2Fetc%2Fpasswd")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn add_entry_records_fetch_outcome() {
+    let site = spawn_site(
+        axum::Router::new()
+            .route(
+                "/old",
+                get(|| async { axum::response::Redirect::permanent("/post") }),
+            )
+            .route(
+                "/post",
+                get(|| async {
+                    axum::response::Html("<html><head><title>Moved Post</title></head></html>")
+                }),
+            )
+            .route("/gone", get(|| async { StatusCode::NOT_FOUND })),
+    )
+    .await;
+
+    let config = Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        fetch_allowlist: Some("127.0.0.1".to_string()),
+        fetch_host_delay_ms: Some(0),
+        ..Default::default()
+    };
+    let (router, _, _) = laterfeed::app(config).await;
+
+    let mut entries = Vec::new();
+    for path in ["/old", "/gone"] {
+        let body = json!({ "url": format!("{site}{path}") });
+        let response = router
+            .clone()
+            .oneshot(
+                Request::post("/entries")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        entries.push(serde_json::from_slice::<Value>(&body).unwrap());
+    }
+
+    assert_eq!(entries[0]["title"], "Moved Post");
+    assert_eq!(entries[0]["fetch_outcome"], "success");
+    assert_eq!(entries[0]["final_url"], format!("{site}/post"));
+    assert!(entries[0]["fetch_duration_ms"].is_i64());
+    assert_eq!(entries[1]["fetch_outcome"], "http_error");
+    assert_eq!(entries[1]["fetch_http_status"], 404);
+
+    for (query, expected) in [("?failed=true", "/gone"), ("?failed=false", "/old")] {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::get(format!("/entries{query}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1, "{query}");
+        assert_eq!(entries[0]["url"], format!("{site}{expected}"));
+    }
+}
+
+#[tokio::test]
+async fn import_saves_links_with_original_times_and_tags() {
+    let app = setup_app().await;
+    let file = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><H3>Reading</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/a" ADD_DATE="1577836800" TAGS="rust">Article A</A>
+        <DT><A HREF="https://example.com/b" ADD_DATE="1577923200">Article B</A>
+    </DL><p>
+</DL><p>"#;
+
+    let start_import = || {
+        Request::post("/imports?fetch=false")
+            .header(header::AUTHORIZATION, "Bearer test-token")
+            .body(Body::from(file))
+            .unwrap()
+    };
+    let wait_for_import = |id: i64| {
+        let app = app.clone();
+        async move {
+            for _ in 0..50 {
+                let response = app
+                    .clone()
+                    .oneshot(
+                        Request::get(format!("/imports/{id}"))
+                            .header(header::AUTHORIZATION, "Bearer test-token")
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                let json: Value = serde_json::from_slice(&body).unwrap();
+                if json["finished"] == true {
+                    return json;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            panic!("import did not finish");
+        }
+    };
+
+    let response = app.clone().oneshot(start_import()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["format"], "netscape");
+    assert_eq!(json["total"], 2);
+
+    let job = wait_for_import(json["id"].as_i64().unwrap()).await;
+    assert_eq!(job["imported"], 2);
+    assert_eq!(job["items"][0]["status"], "imported");
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/entries").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["title"], "Article B");
+    assert!(
+        entries[1]["created_at"]
+            .as_str()
+            .unwrap()
+            .starts_with("2020-01-01")
+    );
+    assert_eq!(entries[1]["tags"], json!(["Reading", "rust"]));
+
+    // Importing the same file again skips what is already saved
+    let response = app.clone().oneshot(start_import()).await.unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let job = wait_for_import(json["id"].as_i64().unwrap()).await;
+    assert_eq!(job["duplicates"], 2);
+    assert_eq!(job["imported"], 0);
+}
+
+#[tokio::test]
+async fn import_rejects_files_without_links() {
+    let app = setup_app().await;
+
+    let response = app
+        .oneshot(
+            Request::post("/imports?format=wallabag")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from("not json"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn export_round_trips_through_import() {
+    let site = spawn_site(axum::Router::new().route(
+        "/talk",
+        get(|| async {
+            axum::response::Html(
+                r#"<html lang="en"><head><title>Exported</title>
+                <meta name="author" content="Jane Doe">
+                <meta property="og:site_name" content="Talks">
+                <meta property="og:image" content="https://example.com/cover.png">
+                <meta property="article:published_time" content="2020-01-01T00:00:00Z">
+                </head><body><p>Saved with its metadata</p></body></html>"#,
+            )
+        }),
+    ))
+    .await;
+
+    // Both servers share a data directory, as when it's copied along with the export
+    let data_dir = std::env::temp_dir().join(format!(
+        "laterfeed-test-{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    let config = || Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        data_dir: Some(data_dir.to_string_lossy().into_owned()),
+        archive_pages: Some(true),
+        fetch_allowlist: Some("127.0.0.1".to_string()),
+        fetch_host_delay_ms: Some(0),
+        ..Default::default()
+    };
+    let (app, _, _) = laterfeed::app(config()).await;
+
+    let add = |body: Value| {
+        app.clone().oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+    };
+    let response = add(json!({
+        "url": "https://example.com/trashed",
+        "title": "Trashed"
+    }))
+    .await
+    .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let trashed: Value = serde_json::from_slice(&body).unwrap();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::delete(format!("/entries/{}", trashed["id"]))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = add(json!({
+        "url": format!("{site}/talk"),
+        "source_type": "video",
+        "tags": ["talks"]
+    }))
+    .await
+    .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let id = serde_json::from_slice::<Value>(&body).unwrap()["id"].clone();
+
+    // Archiving happens in the background
+    for _ in 0..50 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get(format!("/entries/{id}/archive"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        if response.status() == StatusCode::OK {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let export = |app: &axum::Router, format: &str| {
+        app.clone().oneshot(
+            Request::get(format!("/export?format={format}"))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+    };
+
+    let response = export(&app, "csv").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    assert!(csv.starts_with("id,url,title,"));
+    assert!(csv.contains("/talk,Exported,video,Jane Doe,Talks,"));
+    assert!(!csv.contains("Trashed"));
+
+    let response = export(&app, "json").await.unwrap();
+    assert!(
+        response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .contains("laterfeed.json")
+    );
+    let exported = response.into_body().collect().await.unwrap().to_bytes();
+    let original: Value = serde_json::from_slice(&exported).unwrap();
+    assert_eq!(original["entries"][0]["archived"], true);
+    assert_eq!(original["entries"][1]["title"], "Trashed");
+    assert!(original["entries"][1]["deleted_at"].is_string());
+
+    // Import into an empty server
+    let (other, _, _) = laterfeed::app(config()).await;
+    let response = other
+        .clone()
+        .oneshot(
+            Request::post("/imports?fetch=false")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(exported.clone()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let job: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job["format"], "laterfeed");
+
+    let mut finished = false;
+    for _ in 0..50 {
+        let response = other
+            .clone()
+            .oneshot(
+                Request::get(format!("/imports/{}", job["id"]))
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let job: Value = serde_json::from_slice(&body).unwrap();
+        if job["finished"] == true {
+            assert_eq!(job["imported"], 2);
+            finished = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(finished, "import did not finish");
+
+    // Exporting again gives the same entries, apart from their IDs and snapshot copies
+    let response = export(&other, "json").await.unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let imported: Value = serde_json::from_slice(&body).unwrap();
+    let without_ids = |export: &Value| {
+        let mut entries = export["entries"].as_array().unwrap().clone();
+        for entry in &mut entries {
+            let entry = entry.as_object_mut().unwrap();
+            entry.remove("id");
+            entry.remove("archive_path");
+        }
+        entries
+    };
+    assert_eq!(without_ids(&imported), without_ids(&original));
+    assert_eq!(imported["entries"][0]["archived"], true);
+
+    // The snapshot was carried over
+    let response = other
+        .clone()
+        .oneshot(
+            Request::get(format!("/entries/{}/archive", imported["entries"][0]["id"]))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(String::from_utf8_lossy(&body).contains("Saved with its metadata"));
+}
+
+#[tokio::test]
+async fn export_requires_auth() {
+    let app = setup_app().await;
+
+    let response = app
+        .oneshot(Request::get("/export").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+// --- Email inbox ---
+
+#[tokio::test]
+async fn smtp_inbox_saves_links_and_newsletters() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        smtp_port: Some(port),
+        smtp_address: Some("127.0.0.1".to_string()),
+        smtp_allowed_senders: Some("me@example.com".to_string()),
+        smtp_recipient_token: Some("s3cret".to_string()),
+        ..Default::default()
+    };
+    let state = laterfeed::init_state(config).await;
+    let (app, _) = laterfeed::router(state.clone());
+    laterfeed::start_background_tasks(&state).await;
+
+    let stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut send = async |command: &str| {
+        if !command.is_empty() {
+            writer.write_all(command.as_bytes()).await.unwrap();
+        }
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            reply.push_str(&line);
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return reply;
+            }
+        }
+    };
+
+    assert!(send("").await.starts_with("220"));
+    assert!(send("EHLO client\r\n").await.starts_with("250"));
+    assert!(
+        send("MAIL FROM:<spam@example.org>\r\n")
+            .await
+            .starts_with("550")
+    );
+
+    let newsletter = format!(
+        "From: Me <me@example.com>\r\n\
+         Subject: Weekly digest\r\n\
+         Message-ID: <digest-1@example.com>\r\n\
+         Content-Type: text/html; charset=utf-8\r\n\
+         \r\n\
+         <html><body><p>{}</p></body></html>\r\n",
+        "news ".repeat(80)
+    );
+    let link = "From: me@example.com\r\nSubject: Fwd\r\n\r\nhttps://example.com/emailed\r\n";
+    for message in [newsletter.as_str(), link] {
+        assert!(
+            send("MAIL FROM:<me@example.com>\r\n")
+                .await
+                .starts_with("250")
+        );
+        // A forged sender alone isn't enough, the recipient must carry the token
+        assert!(
+            send("RCPT TO:<save@localhost>\r\n")
+                .await
+                .starts_with("550")
+        );
+        assert!(
+            send("RCPT TO:<save+s3cret@localhost>\r\n")
+                .await
+                .starts_with("250")
+        );
+        assert!(send("DATA\r\n").await.starts_with("354"));
+        assert!(send(&format!("{message}.\r\n")).await.starts_with("250"));
+    }
+    assert!(send("QUIT\r\n").await.starts_with("221"));
+
+    let mut entries = Vec::new();
+    for _ in 0..100 {
+        let response = app
+            .clone()
+            .oneshot(Request::get("/entries").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        entries = json["entries"].as_array().unwrap().clone();
+        if entries.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(entries.len(), 2);
+    let newsletter = entries
+        .iter()
+        .find(|entry| entry["title"] == "Weekly digest")
+        .expect("newsletter was not saved");
+    assert_eq!(newsletter["url"], "mid:digest%2D1%40example%2Ecom");
+    assert!(
+        newsletter["body"]
+            .as_str()
+            .unwrap()
+            .starts_with("<p>news news")
+    );
+    assert!(
+        entries
+            .iter()
+            .any(|entry| entry["url"] == "https://example.com/emailed")
+    );
+}
+
+// --- Webhooks ---
+
+#[tokio::test]
+async fn webhooks_deliver_signed_events() {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::HeaderMap, routing::post};
+    use hmac::{Hmac, Mac};
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+    let received: Received = Arc::default();
+    let site = axum::Router::new()
+        .route(
+            "/hook",
+            post(
+                async |State(received): State<Received>, headers: HeaderMap, body: String| {
+                    received.lock().unwrap().push((headers, body));
+                    StatusCode::NO_CONTENT
+                },
+            ),
+        )
+        .route("/broken", post(async || StatusCode::INTERNAL_SERVER_ERROR))
+        .with_state(received.clone());
+    let site_url = spawn_site(site).await;
+
+    // Webhooks can't reach internal services unless they are allowed like fetches
+    let response = setup_app()
+        .await
+        .oneshot(
+            Request::post("/webhooks")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(
+                    json!({ "url": format!("{site_url}/hook") }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let config = Config {
+        port: 0,
+        database_url: "sqlite::memory:".to_string(),
+        base_url: "http://localhost:3000".to_string(),
+        auth_token: "test-token".to_string(),
+        fetch_allowlist: Some("127.0.0.1".to_string()),
+        ..Default::default()
+    };
+    let state = laterfeed::init_state(config).await;
+    let (app, _) = laterfeed::router(state.clone());
+    laterfeed::start_background_tasks(&state).await;
+
+    let create = async |body: Value| {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/webhooks")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice::<Value>(&body).unwrap()
+    };
+    let hook = create(json!({ "url": format!("{site_url}/hook"), "secret": "s3cret" })).await;
+    let broken = create(json!({
+        "url": format!("{site_url}/broken"),
+        "events": ["entry.deleted"]
+    }))
+    .await;
+    assert_eq!(hook["secret"], "s3cret");
+    assert_eq!(
+        hook["events"],
+        json!(["entry.created", "entry.updated", "entry.deleted"])
+    );
+    assert!(broken["secret"].as_str().unwrap().len() >= 32);
+
+    // Secrets are only shown once
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/webhooks")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["webhooks"].as_array().unwrap().len(), 2);
+    assert!(json["webhooks"][0].get("secret").is_none());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(
+                    json!({ "url": "https://example.com/hooked", "title": "Hooked" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let id = serde_json::from_slice::<Value>(&body).unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::delete(format!("/entries/{id}"))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let deliveries = async |webhook: &Value| {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get(format!("/webhooks/{}/deliveries", webhook["id"]))
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice::<Value>(&body).unwrap()["deliveries"]
+            .as_array()
+            .unwrap()
+            .clone()
+    };
+    let mut delivered = Vec::new();
+    let mut failed = Vec::new();
+    for _ in 0..100 {
+        delivered = deliveries(&hook).await;
+        failed = deliveries(&broken).await;
+        if delivered.iter().all(|d| d["status"] == "succeeded")
+            && failed.iter().all(|d| d["attempts"] == 1)
+            && delivered.len() == 2
+            && failed.len() == 1
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(delivered.len(), 2);
+    assert_eq!(delivered[0]["event"], "entry.deleted");
+    assert_eq!(delivered[0]["status"], "succeeded");
+    assert_eq!(delivered[1]["event"], "entry.created");
+    assert_eq!(delivered[1]["status"], "succeeded");
+
+    // The broken endpoint is retried later
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["status"], "pending");
+    assert_eq!(failed[0]["attempts"], 1);
+    assert_eq!(failed[0]["response_status"], 500);
+    assert!(failed[0]["next_attempt_at"].is_string());
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 2);
+    let (headers, body) = received
+        .iter()
+        .find(|(headers, _)| headers["x-laterfeed-event"] == "entry.created")
+        .unwrap();
+    let payload: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(payload["event"], "entry.created");
+    assert_eq!(payload["entry"]["url"], "https://example.com/hooked");
+
+    let timestamp = headers["x-laterfeed-timestamp"].to_str().unwrap();
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"s3cret").unwrap();
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    let expected = format!("sha256={:x}", mac.finalize().into_bytes());
+    assert_eq!(headers["x-laterfeed-signature"], expected.as_str());
+
+    // Deleting a webhook removes its log
+    let response = app
+        .clone()
+        .oneshot(
+            Request::delete(format!("/webhooks/{}", broken["id"]))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(
+            Request::get(format!("/webhooks/{}/deliveries", broken["id"]))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// --- Events ---
+
+/// Read Server-Sent Events from a stream until `count` have arrived, as (event, id, data).
+async fn read_events(body: &mut Body, count: usize) -> Vec<(String, String, Value)> {
+    let mut text = String::new();
+    loop {
+        let events: Vec<(String, String, Value)> = text
+            .split("\n\n")
+            .filter(|block| block.contains("data:"))
+            .map(|block| {
+                let field = |name: &str| {
+                    block
+                        .lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                };
+                (
+                    field("event:"),
+                    field("id:"),
+                    serde_json::from_str(&field("data:")).unwrap(),
+                )
+            })
+            .collect();
+        if events.len() >= count && text.ends_with("\n\n") {
+            return events;
+        }
+
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .expect("timed out waiting for events")
+            .unwrap()
+            .unwrap();
+        if let Ok(data) = frame.into_data() {
+            text.push_str(std::str::from_utf8(&data).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn events_stream_entry_changes_and_resume() {
+    let app = setup_app().await;
+    let subscribe = async |last_event_id: Option<&str>| {
+        let mut request = Request::get("/events");
+        if let Some(id) = last_event_id {
+            request = request.header("Last-Event-ID", id);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/event-stream")
+        );
+        response.into_body()
+    };
+    let mut stream = subscribe(None).await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post("/entries")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::from(
+                    json!({ "url": "https://example.com/streamed", "title": "Streamed" })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let id = serde_json::from_slice::<Value>(&body).unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::delete(format!("/entries/{id}"))
+                .header(header::AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let events = read_events(&mut stream, 2).await;
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].0, "entry.created");
+    assert_eq!(events[0].2["id"], id);
+    assert_eq!(events[0].2["entry"]["title"], "Streamed");
+    assert_eq!(events[1].0, "entry.deleted");
+    assert_eq!(events[1].2, json!({ "id": id }));
+
+    // Reconnecting resumes after the last event received
+    let mut resumed = subscribe(Some(&events[0].1)).await;
+    let missed = read_events(&mut resumed, 1).await;
+    assert_eq!(missed.len(), 1);
+    assert_eq!(missed[0].0, "entry.deleted");
+    assert_eq!(missed[0].1, events[1].1);
+
+    // Events the server doesn't have any more can't be resumed from
+    let mut reset = subscribe(Some("1")).await;
+    let missed = read_events(&mut reset, 1).await;
+    assert_eq!(missed[0].0, "reset");
+    assert_eq!(missed[0].1, events[1].1);
+}
+
+#[tokio::test]
+async fn events_report_restored_entries_as_created() {
+    let app = setup_app().await;
+    let send = async |request: axum::http::request::Builder, body: Body| {
+        app.clone()
+            .oneshot(
+                request
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, "Bearer test-token")
+                    .body(body)
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    };
+    let mut stream = send(Request::get("/events"), Body::empty())
+        .await
+        .into_body();
+
+    let response = send(
+        Request::post("/entries"),
+        Body::from(
+            json!({ "url": "https://example.com/restored", "title": "Restored" }).to_string(),
+        ),
+    )
+    .await;
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let id = serde_json::from_slice::<Value>(&body).unwrap()["id"]
+        .as_i64()
+        .unwrap();
+    send(Request::delete(format!("/entries/{id}")), Body::empty()).await;
+    let response = send(Request::post(format!("/trash/{id}/restore")), Body::empty()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = read_events(&mut stream, 3).await;
+    let names: Vec<&str> = events.iter().map(|event| event.0.as_str()).collect();
+    assert_eq!(names, ["entry.created", "entry.deleted", "entry.created"]);
+    assert_eq!(events[2].2["id"], id);
+    assert_eq!(events[2].2["entry"]["title"], "Restored");
+}
+
+// --- Trash ---
+
+#[tokio::test]
+async fn deleted_entries_go_to_trash_and_can_be_restored() {
+    let app = setup_app().await;
+    let send = async |request: axum::http::request::Builder, body: Option<Value>| {
+        let request = request.header(header::AUTHORIZATION, "Bearer test-token");
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        };
+        let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    };
+    let add = async |url: &str| {
+        let (status, json) = send(
+            Request::post("/entries"),
+            Some(json!({ "url": url, "title": "Trashable" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        json["id"].as_i64().unwrap()
+    };
+
+    let id = add("https://example.com/trashable").await;
+    let (status, _) = send(Request::delete(format!("/entries/{id}")), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Gone from everywhere but the trash
+    let (status, _) = send(Request::get(format!("/entries/{id}")), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, json) = send(Request::get("/entries"), None).await;
+    assert!(json["entries"].as_array().unwrap().is_empty());
+    let (status, json) = send(Request::get("/trash"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["entries"][0]["id"], id);
+    assert!(json["entries"][0]["deleted_at"].is_string());
+
+    let (status, json) = send(Request::post(format!("/trash/{id}/restore")), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["id"], id);
+    assert!(json["deleted_at"].is_null());
+    let (status, _) = send(Request::get(format!("/entries/{id}")), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(Request::post(format!("/trash/{id}/restore")), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Deleting for good works on live and trashed entries
+    send(Request::delete(format!("/entries/{id}")), None).await;
+    let (status, _) = send(
+        Request::delete(format!("/entries/{id}?permanent=true")),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, json) = send(Request::get("/trash"), None).await;
+    assert!(json["entries"].as_array().unwrap().is_empty());
+    let (status, _) = send(
+        Request::delete(format!("/entries/{id}?permanent=true")),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Saving a trashed URL again replaces the trashed entry
+    let first = add("https://example.com/again").await;
+    send(Request::delete(format!("/entries/{first}")), None).await;
+    let second = add("https://example.com/again").await;
+    assert_ne!(first, second);
+    let (_, json) = send(Request::get("/trash"), None).await;
+    assert!(json["entries"].as_array().unwrap().is_empty());
+
+    let other = add("https://example.com/other").await;
+    send(Request::delete(format!("/entries/{second}")), None).await;
+    send(Request::delete(format!("/entries/{other}")), None).await;
+    let (status, json) = send(Request::delete("/trash"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["purged"], 2);
+    let (_, json) = send(Request::get("/trash"), None).await;
+    assert!(json["entries"].as_array().unwrap().is_empty());
+}